@@ -0,0 +1,733 @@
+//! Extracts the link structure of a document.
+//!
+//! pulldown-cmark resolves reference links itself and only keeps the first
+//! definition of every label, which is enough for rendering but not for
+//! reporting problems with them. [`Analysis`] keeps everything, including
+//! broken references and duplicate definitions, along with their source
+//! ranges.
+
+use std::ops::Range;
+
+use pulldown_cmark::{BrokenLink, CowStr, Event, LinkDef, LinkType, Options, Parser, Tag};
+use text_edit::{TextRange, TextSize};
+
+use crate::slug::{SlugStyle, Slugger};
+
+/// The parser options used everywhere a document is parsed.
+pub(crate) fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES
+}
+
+pub(crate) fn text_range(range: Range<usize>) -> TextRange {
+    TextRange::new(
+        TextSize::from(range.start as u32),
+        TextSize::from(range.end as u32),
+    )
+}
+
+/// Normalizes a link label the way `CommonMark` matches them: case-insensitively
+/// with surrounding whitespace stripped and inner whitespace collapsed.
+pub(crate) fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .map(case_fold)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Folds the case of a word like Unicode full case folding, which
+/// pulldown-cmark matches labels with, so that `ẞ`, `ß` and `ss` are equal.
+///
+/// Lowercasing alone keeps `ß` and final `ς`; uppercasing expands and unifies
+/// them before the final lowercasing.
+fn case_fold(word: &str) -> String {
+    word.to_lowercase().to_uppercase().to_lowercase()
+}
+
+/// Returns the range of the YAML (`---`) or TOML (`+++`) front matter at the
+/// start of the document, including the closing fence.
+#[allow(clippy::string_slice)]
+pub(crate) fn front_matter(text: &str) -> Option<TextRange> {
+    let first = text.split_inclusive('\n').next()?;
+    let fence = first.trim_end();
+    if fence != "---" && fence != "+++" {
+        return None;
+    }
+    let mut end = first.len();
+    for line in text[end..].split_inclusive('\n') {
+        end += line.len();
+        if line.trim_end() == fence {
+            return Some(text_range(0..end));
+        }
+    }
+    None
+}
+
+/// Splits a link destination into a path and a fragment.
+///
+/// Returns `None` for absolute URLs, which don't point into the workspace. The
+/// path is empty for links to an anchor in the same document.
+pub(crate) fn local_destination(destination: &str) -> Option<(&str, Option<&str>)> {
+    if lsp_types::Url::parse(destination).is_ok() {
+        return None;
+    }
+    Some(match destination.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (destination, None),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LinkKind {
+    /// `[text](destination)`
+    Inline,
+    /// `[text][label]`
+    Reference,
+    /// `[label][]`
+    Collapsed,
+    /// `[label]`
+    Shortcut,
+    /// `<https://example.com>` or `<john@example.com>`
+    Autolink,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Link {
+    /// The range of the whole link, including the text.
+    pub(crate) range: TextRange,
+    pub(crate) kind: LinkKind,
+    pub(crate) is_image: bool,
+    /// The plain text of the link.
+    pub(crate) text: String,
+    pub(crate) destination: String,
+    /// The range of the destination as written, for inline links and
+    /// autolinks whose destination contains no escapes.
+    pub(crate) destination_range: Option<TextRange>,
+    pub(crate) title: String,
+    /// The label for reference-style links.
+    pub(crate) label: Option<Label>,
+    /// Whether a reference-style link has a matching definition.
+    pub(crate) resolved: bool,
+}
+
+impl Link {
+    pub(crate) fn is_reference(&self) -> bool {
+        matches!(
+            self.kind,
+            LinkKind::Reference | LinkKind::Collapsed | LinkKind::Shortcut
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Label {
+    /// The label as written, without the brackets.
+    pub(crate) text: String,
+    pub(crate) normalized: String,
+    /// The range of the label, without the brackets.
+    pub(crate) range: TextRange,
+}
+
+/// A `[label]: destination "title"` reference definition.
+#[derive(Clone, Debug)]
+pub(crate) struct Definition {
+    pub(crate) label: Label,
+    /// The range of the definition, including the trailing line break.
+    pub(crate) range: TextRange,
+    pub(crate) destination: String,
+    /// The range of the destination as written, unless it contains escapes.
+    pub(crate) destination_range: Option<TextRange>,
+    pub(crate) title: Option<String>,
+}
+
+/// A `[^label]: text` footnote definition.
+#[derive(Clone, Debug)]
+pub(crate) struct FootnoteDefinition {
+    pub(crate) label: Label,
+    /// The footnote text, with continuation lines unindented.
+    pub(crate) text: String,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Heading {
+    pub(crate) level: usize,
+    /// The plain text of the heading.
+    pub(crate) text: String,
+    pub(crate) range: TextRange,
+    pub(crate) anchor: String,
+    /// Whether the anchor is an explicit `{#id}` rather than generated.
+    pub(crate) explicit: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Analysis {
+    pub(crate) headings: Vec<Heading>,
+    pub(crate) paragraphs: Vec<TextRange>,
+    pub(crate) links: Vec<Link>,
+    /// Every definition in source order, including duplicates.
+    pub(crate) definitions: Vec<Definition>,
+    /// The labels of `[^label]` footnote references.
+    pub(crate) footnote_references: Vec<Label>,
+    pub(crate) footnote_definitions: Vec<FootnoteDefinition>,
+}
+
+impl Analysis {
+    pub(crate) fn new(text: &str, slug: SlugStyle) -> Self {
+        let mut callback = |_: BrokenLink<'_>| Some((CowStr::Borrowed(""), CowStr::Borrowed("")));
+        let parser =
+            Parser::new_with_broken_link_callback(text, parser_options(), Some(&mut callback));
+        // The first definition of each label, wherever it is.
+        let parsed_definitions = parser
+            .reference_definitions()
+            .iter()
+            .map(|(label, def)| {
+                let def = LinkDef {
+                    dest: def.dest.to_string().into(),
+                    title: def.title.as_ref().map(|title| title.to_string().into()),
+                    span: def.span.clone(),
+                };
+                (label.to_owned(), def)
+            })
+            .collect::<Vec<_>>();
+        let parser = parser.into_offset_iter();
+
+        let mut headings = Vec::new();
+        let mut paragraphs = Vec::new();
+        let mut links = Vec::new();
+        let mut blocks = Vec::new();
+        let mut footnote_references = Vec::new();
+        let mut footnote_definitions = Vec::new();
+
+        let mut slugger = Slugger::new(slug);
+        // The text of the heading currently being parsed.
+        let mut heading_text = None;
+        // The indices of the links currently being parsed.
+        let mut open_links = Vec::new();
+
+        // Front matter isn't Markdown, even though it parses as a thematic
+        // break and a setext heading.
+        let prose_start = front_matter(text).map_or(0, |range| usize::from(range.end()));
+        if prose_start > 0 {
+            blocks.push(0..prose_start);
+        }
+
+        for (event, range) in parser.filter(|(_, range)| range.start >= prose_start) {
+            match event {
+                Event::Start(Tag::Link(ty, destination, title)) => {
+                    open_links.push(links.len());
+                    links.push(link(text, range, ty, false, &destination, &title));
+                }
+                Event::Start(Tag::Image(ty, destination, title)) => {
+                    open_links.push(links.len());
+                    links.push(link(text, range, ty, true, &destination, &title));
+                }
+                Event::End(Tag::Link(..) | Tag::Image(..)) => {
+                    open_links.pop();
+                }
+                Event::Start(Tag::Heading(..)) => {
+                    blocks.push(range);
+                    heading_text = Some(String::new());
+                }
+                Event::End(Tag::Heading(level, id, _)) => {
+                    let text = heading_text.take().unwrap_or_default();
+                    let anchor = match id {
+                        Some(id) => slugger.explicit(id),
+                        None => slugger.slug(&text),
+                    };
+                    headings.push(Heading {
+                        level: level as usize,
+                        anchor,
+                        explicit: id.is_some(),
+                        text,
+                        range: text_range(range),
+                    });
+                }
+                Event::Text(content) | Event::Code(content) => {
+                    if let Some(heading_text) = &mut heading_text {
+                        heading_text.push_str(&content);
+                    }
+                    for &index in &open_links {
+                        let link: &mut Link = &mut links[index];
+                        link.text.push_str(&content);
+                    }
+                }
+                Event::Start(Tag::Paragraph) => {
+                    blocks.push(range.clone());
+                    paragraphs.push(text_range(range));
+                }
+                Event::Start(Tag::CodeBlock(_) | Tag::Table(_)) | Event::Html(_) => {
+                    blocks.push(range);
+                }
+                Event::Start(Tag::FootnoteDefinition(label)) => {
+                    blocks.push(range.clone());
+                    footnote_definitions.push(footnote_definition(text, range, &label));
+                }
+                Event::FootnoteReference(label) => {
+                    // The range covers `[^label]`.
+                    let range = range.start + 2..range.end - 1;
+                    footnote_references.push(Label {
+                        text: label.to_string(),
+                        normalized: normalize_label(&label),
+                        range: text_range(range),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            headings,
+            paragraphs,
+            links,
+            definitions: definitions(text, &blocks, parsed_definitions),
+            footnote_references,
+            footnote_definitions,
+        }
+    }
+
+    /// Returns the innermost link at the given offset.
+    pub(crate) fn link_at(&self, offset: TextSize) -> Option<&Link> {
+        // Images can be nested in links, and come after them.
+        self.links
+            .iter()
+            .rev()
+            .find(|link| link.range.contains_inclusive(offset))
+    }
+
+    /// Returns the heading a `#anchor` points to.
+    pub(crate) fn heading(&self, anchor: &str) -> Option<&Heading> {
+        self.headings
+            .iter()
+            .find(|heading| heading.anchor == anchor)
+    }
+
+    /// Returns the range of the section started by a heading, which ends at
+    /// the next heading of the same or a higher level.
+    pub(crate) fn section(&self, heading: &Heading, len: TextSize) -> TextRange {
+        let end = self
+            .headings
+            .iter()
+            .skip_while(|other| other.range != heading.range)
+            .skip(1)
+            .find(|other| other.level <= heading.level)
+            .map_or(len, |other| other.range.start());
+        TextRange::new(heading.range.start(), end)
+    }
+
+    /// Returns the footnote label, either of a reference or a definition, at
+    /// the given offset.
+    pub(crate) fn footnote_at(&self, offset: TextSize) -> Option<&Label> {
+        self.footnote_references
+            .iter()
+            .chain(self.footnote_definitions.iter().map(|def| &def.label))
+            .find(|label| label.range.contains_inclusive(offset))
+    }
+
+    pub(crate) fn footnote_definition(&self, normalized: &str) -> Option<&FootnoteDefinition> {
+        self.footnote_definitions
+            .iter()
+            .find(|definition| definition.label.normalized == normalized)
+    }
+
+    /// Returns the definition a label resolves to, if any.
+    pub(crate) fn definition(&self, normalized: &str) -> Option<&Definition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.label.normalized == normalized)
+    }
+
+    /// Returns the reference-style links that use a label.
+    pub(crate) fn references<'a>(
+        &'a self,
+        normalized: &'a str,
+    ) -> impl Iterator<Item = &'a Link> + 'a {
+        self.links.iter().filter(move |link| {
+            link.label
+                .as_ref()
+                .is_some_and(|label| label.normalized == normalized)
+        })
+    }
+}
+
+#[allow(clippy::string_slice)]
+fn footnote_definition(text: &str, range: Range<usize>, label: &str) -> FootnoteDefinition {
+    // The definition starts with `[^label]:`.
+    let label_start = range.start + 2;
+    let label_end = label_start + label.len();
+    let content = text[label_end..range.end]
+        .strip_prefix("]:")
+        .unwrap_or_default();
+
+    let text = content
+        .trim()
+        .lines()
+        .map(|line| {
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            &line[indent.min(4)..]
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    FootnoteDefinition {
+        label: Label {
+            text: label.to_owned(),
+            normalized: normalize_label(label),
+            range: text_range(label_start..label_end),
+        },
+        text,
+    }
+}
+
+#[allow(clippy::string_slice)]
+fn link(
+    text: &str,
+    range: Range<usize>,
+    ty: LinkType,
+    is_image: bool,
+    destination: &str,
+    title: &str,
+) -> Link {
+    let (kind, resolved) = match ty {
+        LinkType::Inline => (LinkKind::Inline, true),
+        LinkType::Reference => (LinkKind::Reference, true),
+        LinkType::ReferenceUnknown => (LinkKind::Reference, false),
+        LinkType::Collapsed => (LinkKind::Collapsed, true),
+        LinkType::CollapsedUnknown => (LinkKind::Collapsed, false),
+        LinkType::Shortcut => (LinkKind::Shortcut, true),
+        LinkType::ShortcutUnknown => (LinkKind::Shortcut, false),
+        LinkType::Autolink | LinkType::Email => (LinkKind::Autolink, true),
+    };
+
+    let source = &text[range.clone()];
+    let label = match kind {
+        LinkKind::Reference => source
+            .strip_suffix(']')
+            .and_then(|rest| rest.rfind('[').map(|open| (open + 1, rest.len()))),
+        // The range of a collapsed link doesn't include the trailing `[]`.
+        LinkKind::Collapsed | LinkKind::Shortcut => source
+            .strip_suffix(']')
+            .map(|rest| (usize::from(is_image) + 1, rest.len())),
+        LinkKind::Inline | LinkKind::Autolink => None,
+    }
+    .filter(|(start, end)| start <= end)
+    .map(|(start, end)| {
+        let label = &source[start..end];
+        Label {
+            text: label.to_owned(),
+            normalized: normalize_label(label),
+            range: text_range(range.start + start..range.start + end),
+        }
+    });
+
+    let destination_range = match kind {
+        // The destination follows the last `](` that it can follow.
+        LinkKind::Inline => source
+            .match_indices("](")
+            .filter_map(|(i, _)| destination_after(text, range.start + i + 2, destination))
+            .last(),
+        LinkKind::Autolink => Some(range.start + 1..range.end - 1),
+        LinkKind::Reference | LinkKind::Collapsed | LinkKind::Shortcut => None,
+    };
+
+    Link {
+        range: text_range(range),
+        kind,
+        is_image,
+        text: String::new(),
+        destination: destination.to_owned(),
+        destination_range: destination_range.map(text_range),
+        title: title.to_owned(),
+        label,
+        resolved,
+    }
+}
+
+/// Returns the range of `destination` if it is written at `start`, after
+/// optional whitespace and an opening `<`.
+#[allow(clippy::string_slice)]
+fn destination_after(text: &str, start: usize, destination: &str) -> Option<Range<usize>> {
+    let rest = &text[start..];
+    let trimmed = rest.trim_start();
+    let trimmed = trimmed.strip_prefix('<').unwrap_or(trimmed);
+    let start = start + rest.len() - trimmed.len();
+    trimmed
+        .starts_with(destination)
+        .then_some(start..start + destination.len())
+}
+
+/// Finds every reference definition in the document.
+///
+/// Definitions never produce events, so they are found in the gaps between
+/// leaf blocks. Each candidate line is parsed on its own so that duplicates,
+/// which pulldown-cmark discards, are found too. Definitions in block quotes
+/// and list items don't start their lines, so those come from the definitions
+/// the parser kept.
+#[allow(clippy::string_slice)]
+fn definitions(
+    text: &str,
+    blocks: &[Range<usize>],
+    parsed: Vec<(String, LinkDef<'_>)>,
+) -> Vec<Definition> {
+    let mut definitions: Vec<Definition> = Vec::new();
+
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let start = line_start;
+        line_start += line.len();
+
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if indent > 3 || !line[indent..].starts_with('[') {
+            continue;
+        }
+        if blocks.iter().any(|block| block.contains(&start))
+            || definitions
+                .last()
+                .is_some_and(|last| usize::from(last.range.end()) > start)
+        {
+            continue;
+        }
+
+        // A definition ends at the next blank line or block at the latest.
+        let end = text[start..]
+            .find("\n\n")
+            .map_or(text.len(), |offset| start + offset + 1);
+        let end = blocks
+            .iter()
+            .map(|block| block.start)
+            .filter(|&block_start| block_start > start)
+            .fold(end, usize::min);
+
+        let parser = Parser::new_ext(&text[start..end], parser_options());
+        let Some((label, def)) = parser
+            .reference_definitions()
+            .iter()
+            .find(|(_, def)| def.span.start <= indent)
+        else {
+            continue;
+        };
+        let span = start + def.span.start..start + def.span.end;
+        definitions.push(definition(text, start, label, def, span));
+    }
+
+    for (label, def) in parsed {
+        let is_found = definitions
+            .iter()
+            .any(|found| found.range.contains(text_range(def.span.clone()).start()));
+        if !is_found {
+            let span = def.span.clone();
+            definitions.push(definition(text, span.start, &label, &def, span));
+        }
+    }
+    definitions.sort_by_key(|def| def.range.start());
+
+    definitions
+}
+
+/// Builds a definition starting at `start` from the parser's, whose `span`
+/// starts at the `[` of the label.
+#[allow(clippy::string_slice)]
+fn definition(
+    text: &str,
+    start: usize,
+    label: &str,
+    def: &LinkDef<'_>,
+    span: Range<usize>,
+) -> Definition {
+    let label_start = span.start + 1;
+    let label_end = text[label_start..]
+        .find(']')
+        .map_or(label_start, |i| label_start + i);
+    let definition_end = text[span.end..]
+        .find('\n')
+        .map_or(text.len(), |i| span.end + i + 1);
+
+    Definition {
+        label: Label {
+            text: text[label_start..label_end].to_owned(),
+            normalized: normalize_label(label),
+            range: text_range(label_start..label_end),
+        },
+        range: text_range(start..definition_end),
+        destination: def.dest.to_string(),
+        destination_range: destination_after(text, label_end + 2, &def.dest).map(text_range),
+        title: def.title.as_ref().map(ToString::to_string),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions() {
+        let text = "[a]: /a\n[B]: /b 'title'\n\n    [c]: /c\n\n[b]: /other\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+
+        let labels = analysis
+            .definitions
+            .iter()
+            .map(|def| def.label.normalized.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["a", "b", "b"]);
+
+        let b = analysis.definition("b").unwrap();
+        assert_eq!(b.destination, "/b");
+        assert_eq!(b.title.as_deref(), Some("title"));
+        assert_eq!(b.range, text_range(8..24));
+        assert_eq!(b.label.range, text_range(9..10));
+        assert_eq!(&text[b.destination_range.unwrap()], "/b");
+    }
+
+    #[test]
+    fn test_normalize_label() {
+        assert_eq!(normalize_label("  Foo\n  BAR "), "foo bar");
+        assert_eq!(normalize_label("ẞ"), "ss");
+        assert_eq!(normalize_label("Straße"), normalize_label("STRASSE"));
+        assert_eq!(normalize_label("ΣΟΦΟΣ"), normalize_label("σοφος"));
+
+        let text = "[t][ss]\n\n[ẞ]: /x\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let label = analysis.links[0].label.as_ref().unwrap();
+        assert_eq!(
+            analysis.definition(&label.normalized).unwrap().destination,
+            "/x"
+        );
+    }
+
+    #[test]
+    fn test_nested_definitions() {
+        let text = "> [a]: /a\n\n- item\n\n  [b]: /b 'title'\n- [c]: /c\n\n[a]: /other\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+
+        let labels = analysis
+            .definitions
+            .iter()
+            .map(|def| def.label.normalized.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["a", "b", "c", "a"]);
+
+        let a = analysis.definition("a").unwrap();
+        assert_eq!(a.destination, "/a");
+        assert_eq!(&text[a.label.range], "a");
+        assert_eq!(&text[a.range], "[a]: /a\n");
+        let b = analysis.definition("b").unwrap();
+        assert_eq!(b.title.as_deref(), Some("title"));
+        assert_eq!(&text[b.destination_range.unwrap()], "/b");
+        assert_eq!(analysis.definition("c").unwrap().destination, "/c");
+    }
+
+    #[test]
+    fn test_destinations() {
+        let text = "[a](x.md), [`](`](<y z.md> 'y'), ![a](\\), <https://a.b>\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+
+        let destinations = analysis
+            .links
+            .iter()
+            .map(|link| link.destination_range.map(|range| &text[range]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            destinations,
+            [Some("x.md"), Some("y z.md"), None, Some("https://a.b")]
+        );
+        let texts = analysis
+            .links
+            .iter()
+            .map(|link| link.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["a", "](", "a", "https://a.b"]);
+    }
+
+    #[test]
+    fn test_reference_labels() {
+        let text =
+            "[x][Foo  Bar], [foo bar][], ![foo bar] and [missing][nope]\n\n[Foo Bar]: /foo\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+
+        let labels = analysis
+            .links
+            .iter()
+            .map(|link| {
+                let label = link.label.as_ref().unwrap();
+                (&text[label.range], link.resolved)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                ("Foo  Bar", true),
+                ("foo bar", true),
+                ("foo bar", true),
+                ("nope", false)
+            ]
+        );
+        assert_eq!(analysis.references("foo bar").count(), 3);
+    }
+
+    #[test]
+    fn test_headings() {
+        let text = "# Title\n\nIntro.\n\n## `Usage`\n\nText.\n\n## Usage\n\n# Other\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+
+        let anchors = analysis
+            .headings
+            .iter()
+            .map(|heading| (heading.level, heading.anchor.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            anchors,
+            [(1, "title"), (2, "usage"), (2, "usage-1"), (1, "other")]
+        );
+
+        let usage = analysis.heading("usage").unwrap();
+        assert_eq!(usage.text, "Usage");
+        let section = analysis.section(usage, TextSize::of(text));
+        assert_eq!(&text[section], "## `Usage`\n\nText.\n\n");
+
+        let title = analysis.heading("title").unwrap();
+        let section = analysis.section(title, TextSize::of(text));
+        assert_eq!(
+            section.end(),
+            analysis.heading("other").unwrap().range.start()
+        );
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let text = "# Setup {#install}\n\n# Install\n";
+        let analysis = Analysis::new(text, SlugStyle::Gitlab);
+
+        let anchors = analysis
+            .headings
+            .iter()
+            .map(|heading| (heading.text.as_str(), heading.anchor.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(anchors, [("Setup", "install"), ("Install", "install-1")]);
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let text = "Text[^1] and [^nope].\n\n[^1]: The *first*\n    footnote.\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+
+        let references = analysis
+            .footnote_references
+            .iter()
+            .map(|label| &text[label.range])
+            .collect::<Vec<_>>();
+        assert_eq!(references, ["1", "nope"]);
+
+        let definition = analysis.footnote_definition("1").unwrap();
+        assert_eq!(&text[definition.label.range], "1");
+        assert_eq!(definition.text, "The *first*\nfootnote.");
+
+        assert_eq!(analysis.footnote_at(6.into()).unwrap().normalized, "1");
+        assert_eq!(analysis.footnote_at(26.into()).unwrap().normalized, "1");
+        assert!(analysis.footnote_at(0.into()).is_none());
+    }
+}
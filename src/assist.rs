@@ -0,0 +1,133 @@
+//! Code actions that aren't fixes for a diagnostic.
+
+use text_edit::{TextEdit, TextRange};
+
+use crate::{analysis::Analysis, config::Config, convert, task, toc};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AssistKind {
+    /// Rewrites the construct under the cursor.
+    Rewrite,
+    /// Applies to the whole document.
+    Source,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Assist {
+    pub(crate) title: String,
+    pub(crate) kind: AssistKind,
+    pub(crate) edit: TextEdit,
+}
+
+/// Returns the assists available for a range of the document.
+pub(crate) fn assists(
+    text: &str,
+    analysis: &Analysis,
+    config: &Config,
+    range: TextRange,
+) -> Vec<Assist> {
+    let mut assists = Vec::new();
+    table_of_contents(text, analysis, config, range, &mut assists);
+    convert_links(text, analysis, config, range, &mut assists);
+    toggle_tasks(text, config, range, &mut assists);
+    assists
+}
+
+/// Inserts a table of contents at the cursor, or updates the existing one.
+fn table_of_contents(
+    text: &str,
+    analysis: &Analysis,
+    config: &Config,
+    range: TextRange,
+    assists: &mut Vec<Assist>,
+) {
+    let (title, edit) = match toc::markers(text) {
+        Some(markers) => (
+            "Update table of contents",
+            toc::update(text, analysis, &config.toc, markers),
+        ),
+        None => (
+            "Insert table of contents",
+            toc::insert(text, analysis, &config.toc, range.start()),
+        ),
+    };
+    if let Some(edit) = edit {
+        assists.push(Assist {
+            title: title.to_owned(),
+            kind: AssistKind::Source,
+            edit,
+        });
+    }
+}
+
+/// Converts the link at the cursor, or every link in the document, between
+/// the inline and reference styles.
+fn convert_links(
+    text: &str,
+    analysis: &Analysis,
+    config: &Config,
+    range: TextRange,
+    assists: &mut Vec<Assist>,
+) {
+    let to_reference =
+        |links: &[&_]| convert::to_reference(text, analysis, links, config.definition_placement);
+    let to_inline = |links: &[&_]| convert::to_inline(text, analysis, links);
+
+    if let Some(link) = analysis.link_at(range.start()) {
+        if convert::is_inline(text, link) {
+            assists.push(Assist {
+                title: "Convert to reference link".to_owned(),
+                kind: AssistKind::Rewrite,
+                edit: to_reference(&[link]),
+            });
+        } else if convert::is_reference(link) {
+            assists.push(Assist {
+                title: "Convert to inline link".to_owned(),
+                kind: AssistKind::Rewrite,
+                edit: to_inline(&[link]),
+            });
+        }
+    }
+
+    let inline = analysis
+        .links
+        .iter()
+        .filter(|link| convert::is_inline(text, link))
+        .collect::<Vec<_>>();
+    if !inline.is_empty() {
+        assists.push(Assist {
+            title: "Convert all links to reference links".to_owned(),
+            kind: AssistKind::Source,
+            edit: to_reference(&inline),
+        });
+    }
+    let references = analysis
+        .links
+        .iter()
+        .filter(|link| convert::is_reference(link))
+        .collect::<Vec<_>>();
+    if !references.is_empty() {
+        assists.push(Assist {
+            title: "Convert all links to inline links".to_owned(),
+            kind: AssistKind::Source,
+            edit: to_inline(&references),
+        });
+    }
+}
+
+/// Toggles the checkboxes of the list items at the cursor or in the selection.
+fn toggle_tasks(text: &str, config: &Config, range: TextRange, assists: &mut Vec<Assist>) {
+    if !task::has_items(text, range, &config.task) {
+        return;
+    }
+    let title = if range.is_empty() {
+        "Toggle task"
+    } else {
+        "Toggle tasks"
+    };
+    assists.push(Assist {
+        title: title.to_owned(),
+        kind: AssistKind::Rewrite,
+        edit: task::toggle(text, range, &config.task, task::Date::today()),
+    });
+}
@@ -0,0 +1,55 @@
+//! The commands the server executes through `workspace/executeCommand`.
+
+/// Shows the links to a heading.
+///
+/// Takes the URI of the document, the position of the heading and the
+/// locations of the links, like VS Code's `editor.action.showReferences`. The
+/// server looks the links up again, asks the client to show the only one, or
+/// to pick one of several, and responds with their locations.
+pub(crate) const SHOW_REFERENCES: &str = "mdls.showReferences";
+
+/// Inserts or updates the table of contents of a document.
+///
+/// Takes the URI of the document and optionally the position to insert a new
+/// table of contents at, which defaults to before the first listed heading.
+pub(crate) const UPDATE_TOC: &str = "mdls.updateToc";
+
+/// Adds or updates hierarchical numbers on the headings of a document, and
+/// rewrites the links to them across the workspace.
+///
+/// Takes the URI of the document.
+pub(crate) const NUMBER_HEADINGS: &str = "mdls.numberHeadings";
+
+/// Removes the numbers from the headings of a document, like
+/// [`NUMBER_HEADINGS`].
+pub(crate) const REMOVE_HEADING_NUMBERS: &str = "mdls.removeHeadingNumbers";
+
+/// Toggles the checkboxes of the list items in a range of a document, like
+/// the "Toggle task" code action, for use in keybindings.
+///
+/// Takes the URI of the document, the range and optionally the local date of
+/// the client as `YYYY-MM-DD`, which completion dates default to the UTC date
+/// without.
+pub(crate) const TOGGLE_TASK: &str = "mdls.toggleTask";
+
+/// Moves the section of a heading into a new document, and redirects the
+/// links to its headings across the workspace.
+///
+/// Takes the URI of the document and the position of the heading.
+pub(crate) const EXTRACT_SECTION: &str = "mdls.extractSection";
+
+/// Adds a word to the words file of the workspace, so that it isn't reported
+/// as misspelled anymore.
+///
+/// Takes the word.
+pub(crate) const ADD_WORD: &str = "mdls.addWord";
+
+pub(crate) const ALL: &[&str] = &[
+    SHOW_REFERENCES,
+    UPDATE_TOC,
+    NUMBER_HEADINGS,
+    REMOVE_HEADING_NUMBERS,
+    TOGGLE_TASK,
+    EXTRACT_SECTION,
+    ADD_WORD,
+];
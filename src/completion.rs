@@ -0,0 +1,545 @@
+//! Completion of link destinations, anchors and labels.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::Url;
+use text_edit::{Indel, TextRange, TextSize};
+
+use crate::{
+    analysis::{front_matter, normalize_label, Analysis},
+    hover::is_markdown,
+    ignore::Gitignore,
+    schema,
+    slug::SlugStyle,
+    workspace, Context,
+};
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "apng", "avif", "bmp", "gif", "ico", "jpeg", "jpg", "png", "svg", "webp",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CompletionKind {
+    File,
+    Folder,
+    Heading,
+    Reference,
+    /// A key of the front matter.
+    Property,
+    /// A value of the front matter.
+    Value,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Completion {
+    pub(crate) label: String,
+    pub(crate) kind: CompletionKind,
+    pub(crate) detail: Option<String>,
+    /// Markdown documentation.
+    pub(crate) documentation: Option<String>,
+    /// Overrides the label when the client sorts completions.
+    pub(crate) sort_text: Option<String>,
+    /// The range replaced by the completion.
+    pub(crate) range: TextRange,
+    pub(crate) insert: String,
+    /// Edits elsewhere in the document that are applied with the completion.
+    pub(crate) additional_edits: Vec<Indel>,
+    /// Whether completion should be triggered again once this one is accepted.
+    pub(crate) retrigger: bool,
+}
+
+/// Where in a document completion was requested.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Site {
+    /// A link destination, reference definition destination or `<img>` source.
+    /// Contains the offset of the start of the destination.
+    Destination { start: usize, images_only: bool },
+    /// The label of a `[text][label]` reference or a `[^label]` footnote.
+    Label { start: usize, footnote: bool },
+}
+
+#[allow(clippy::string_slice)]
+pub(crate) fn completions(
+    ctx: &Context,
+    uri: &Url,
+    text: &str,
+    offset: TextSize,
+) -> Vec<Completion> {
+    if front_matter(text).is_some_and(|range| range.contains(offset)) {
+        return schema::completions(&schema::schemas(ctx, uri), text, offset);
+    }
+    let offset = usize::from(offset);
+    let Some(before) = text.get(..offset) else {
+        return Vec::new();
+    };
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    match site(&text[line_start..offset]) {
+        Some(Site::Destination { start, images_only }) => {
+            let destination = &text[line_start + start..offset];
+            match destination.split_once('#') {
+                Some((path, anchor)) if !images_only => {
+                    anchors(ctx, uri, text, path, anchor, offset)
+                }
+                Some(_) => Vec::new(),
+                None => paths(ctx, uri, destination, images_only, offset),
+            }
+        }
+        Some(Site::Label { start, footnote }) => {
+            labels(text, &text[line_start + start..offset], footnote, offset)
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Works out what is being completed from the text of the line before the
+/// cursor.
+#[allow(clippy::string_slice)]
+fn site(before: &str) -> Option<Site> {
+    // `[^label`
+    if let Some(open) = before.rfind("[^") {
+        let start = open + 2;
+        if !before[start..].contains(|c: char| c.is_whitespace() || c == ']') {
+            return Some(Site::Label {
+                start,
+                footnote: true,
+            });
+        }
+    }
+
+    // `[text][label`
+    if let Some(close) = before.rfind("][") {
+        let start = close + 2;
+        if !before[start..].contains(']') && opening_bracket(&before[..close]).is_some() {
+            return Some(Site::Label {
+                start,
+                footnote: false,
+            });
+        }
+    }
+
+    // `<img src="...`
+    if let Some(src) = before.rfind("src=") {
+        let start = src + "src=".len() + 1;
+        let is_quoted = matches!(
+            before[src + "src=".len()..].chars().next(),
+            Some('"' | '\'')
+        );
+        let in_img = before[..src]
+            .rfind("<img")
+            .is_some_and(|tag| !before[tag..].contains('>'));
+        if is_quoted && in_img && !before[start..].contains(['"', '\'']) {
+            return Some(Site::Destination {
+                start,
+                images_only: true,
+            });
+        }
+    }
+
+    // `[text](...` or `![alt](...`
+    if let Some(close) = before.rfind("](") {
+        let start = close + 2;
+        if !before[start..].contains(|c: char| c.is_whitespace() || c == ')') {
+            let images_only =
+                opening_bracket(&before[..close]).is_some_and(|open| before[..open].ends_with('!'));
+            return Some(Site::Destination { start, images_only });
+        }
+    }
+
+    // `[label]: ...`
+    let indent = before.len() - before.trim_start_matches(' ').len();
+    if indent <= 3 && before[indent..].starts_with('[') {
+        if let Some(close) = before.find("]:") {
+            let rest = &before[close + 2..];
+            let start = before.len() - rest.trim_start().len();
+            if !before[start..].contains(char::is_whitespace) {
+                return Some(Site::Destination {
+                    start,
+                    images_only: false,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Finds the `[` matching a `]` at the end of `text`.
+fn opening_bracket(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices().rev() {
+        match c {
+            ']' => depth += 1,
+            '[' if depth == 0 => return Some(i),
+            '[' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Lists the files in the directory the destination points into.
+fn paths(
+    ctx: &Context,
+    uri: &Url,
+    destination: &str,
+    images_only: bool,
+    offset: usize,
+) -> Vec<Completion> {
+    let (dir, name) = match destination.rfind('/') {
+        Some(i) => destination.split_at(i + 1),
+        None => ("", destination),
+    };
+    let Some(dir) = directory(ctx, uri, dir) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let root = ctx
+        .root()
+        .map(Path::to_owned)
+        .or_else(|| repository(&dir))
+        .unwrap_or_else(|| dir.clone());
+    let gitignore = Gitignore::for_directory(&root, &dir);
+
+    let range = TextRange::new(
+        TextSize::from((offset - name.len()) as u32),
+        TextSize::from(offset as u32),
+    );
+
+    let mut completions = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        let Some(file_name) = entry.file_name().to_str().map(ToOwned::to_owned) else {
+            continue;
+        };
+
+        if file_name.starts_with('.') && !name.starts_with('.') {
+            continue;
+        }
+        if gitignore.is_ignored(&path, is_dir) {
+            continue;
+        }
+        if images_only && !is_dir && !is_image(&path) {
+            continue;
+        }
+
+        let encoded = encode(&file_name);
+        completions.push(if is_dir {
+            Completion {
+                label: format!("{file_name}/"),
+                kind: CompletionKind::Folder,
+                detail: None,
+                documentation: None,
+                // Folders are listed before files.
+                sort_text: Some(format!("0{file_name}")),
+                range,
+                insert: format!("{encoded}/"),
+                additional_edits: Vec::new(),
+                retrigger: true,
+            }
+        } else {
+            Completion {
+                sort_text: Some(format!("1{file_name}")),
+                label: file_name,
+                kind: CompletionKind::File,
+                detail: None,
+                documentation: None,
+                range,
+                insert: encoded,
+                additional_edits: Vec::new(),
+                retrigger: false,
+            }
+        });
+    }
+    completions
+}
+
+/// Percent-encodes the characters of a file name that would end or break a
+/// link destination.
+fn encode(file_name: &str) -> String {
+    let mut encoded = String::with_capacity(file_name.len());
+    for c in file_name.chars() {
+        match c {
+            ' ' | '%' | '(' | ')' | '<' | '>' | '#' | '?' | '"' | '\'' => {
+                write!(encoded, "%{:02X}", c as u32).unwrap();
+            }
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Lists the reference or footnote labels defined in the document.
+///
+/// If the label being typed isn't defined yet, it is offered too, along with a
+/// stub definition at the end of the document.
+fn labels(text: &str, label: &str, footnote: bool, offset: usize) -> Vec<Completion> {
+    // Only the definitions are needed, which don't depend on the slug style.
+    let analysis = Analysis::new(text, SlugStyle::default());
+    let range = TextRange::new(
+        TextSize::from((offset - label.len()) as u32),
+        TextSize::from(offset as u32),
+    );
+    let completion = |label: &str, documentation| Completion {
+        label: label.to_owned(),
+        kind: CompletionKind::Reference,
+        detail: None,
+        documentation: Some(documentation),
+        sort_text: None,
+        range,
+        insert: label.to_owned(),
+        additional_edits: Vec::new(),
+        retrigger: false,
+    };
+
+    let mut completions = Vec::new();
+    if footnote {
+        for definition in &analysis.footnote_definitions {
+            completions.push(completion(&definition.label.text, definition.text.clone()));
+        }
+    } else {
+        for definition in &analysis.definitions {
+            let label = &definition.label;
+            // Duplicates are never used.
+            if analysis
+                .definition(&label.normalized)
+                .map(|first| first.range)
+                != Some(definition.range)
+            {
+                continue;
+            }
+            let mut documentation = format!("`{}`", definition.destination);
+            if let Some(title) = &definition.title {
+                write!(documentation, "\n\n{title}").unwrap();
+            }
+            completions.push(completion(&label.text, documentation));
+        }
+    }
+
+    let normalized = normalize_label(label);
+    let is_defined = completions
+        .iter()
+        .any(|completion| normalize_label(&completion.label) == normalized);
+    if !normalized.is_empty() && !is_defined {
+        let stub = if footnote {
+            format!("[^{label}]: ")
+        } else {
+            format!("[{label}]: ")
+        };
+        completions.push(Completion {
+            detail: Some("New definition".to_owned()),
+            documentation: None,
+            additional_edits: vec![append_definition(text, &stub)],
+            ..completion(label, String::new())
+        });
+    }
+    completions
+}
+
+/// Appends a definition to the block of definitions at the end of the
+/// document, starting a new block if there isn't one.
+pub(crate) fn append_definition(text: &str, definition: &str) -> Indel {
+    let last_line = text.trim_end().rsplit('\n').next().unwrap_or_default();
+    let ends_with_definition = last_line.trim_start().starts_with('[') && last_line.contains("]:");
+
+    let mut insert = String::new();
+    if !text.is_empty() && !text.ends_with('\n') {
+        insert.push('\n');
+    }
+    if !text.trim().is_empty() && !ends_with_definition && !text.ends_with("\n\n") {
+        insert.push('\n');
+    }
+    insert.push_str(definition);
+    insert.push('\n');
+    Indel::insert(TextSize::of(text), insert)
+}
+
+/// Lists the heading anchors of the document a link points to.
+fn anchors(
+    ctx: &Context,
+    uri: &Url,
+    text: &str,
+    path: &str,
+    anchor: &str,
+    offset: usize,
+) -> Vec<Completion> {
+    let analysis = if path.is_empty() {
+        Analysis::new(text, ctx.config().slug)
+    } else {
+        let Some(target) = markdown_target(uri, path, ctx.root()) else {
+            return Vec::new();
+        };
+        match ctx.document_text(&target) {
+            Some(text) => Analysis::new(&text, ctx.config().slug),
+            None => return Vec::new(),
+        }
+    };
+
+    let range = TextRange::new(
+        TextSize::from((offset - anchor.len()) as u32),
+        TextSize::from(offset as u32),
+    );
+    analysis
+        .headings
+        .into_iter()
+        .enumerate()
+        .map(|(i, heading)| Completion {
+            detail: Some(format!("{} {}", "#".repeat(heading.level), heading.text)),
+            // Headings are listed in document order.
+            sort_text: Some(format!("{i:05}")),
+            documentation: None,
+            label: heading.anchor.clone(),
+            kind: CompletionKind::Heading,
+            range,
+            insert: heading.anchor,
+            additional_edits: Vec::new(),
+            retrigger: false,
+        })
+        .collect()
+}
+
+/// Resolves the path of a link to a Markdown document, which is relative to
+/// the document or, if it starts with `/`, to the workspace root.
+fn markdown_target(uri: &Url, path: &str, root: Option<&Path>) -> Option<Url> {
+    workspace::resolve(uri, path, root).filter(|target| is_markdown(Path::new(target.path())))
+}
+
+/// Resolves the directory part of a destination, which is relative to the
+/// document or, if it starts with `/`, to the workspace root.
+fn directory(ctx: &Context, uri: &Url, dir: &str) -> Option<PathBuf> {
+    let dir = if dir.is_empty() { "./" } else { dir };
+    let url = match dir.strip_prefix('/') {
+        Some(dir) => Url::from_directory_path(ctx.root()?).ok()?.join(dir),
+        None => uri.join(dir),
+    };
+    url.ok()?.to_file_path().ok()
+}
+
+/// Finds the root of the git repository containing a directory.
+fn repository(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_owned)
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn destination(start: usize, images_only: bool) -> Site {
+        Site::Destination { start, images_only }
+    }
+
+    #[test]
+    fn test_link_site() {
+        assert_eq!(site("See [the docs]("), Some(destination(15, false)));
+        assert_eq!(site("See [the docs](docs/"), Some(destination(15, false)));
+        assert_eq!(site("![alt](img/"), Some(destination(7, true)));
+        assert_eq!(site("[![badge](a.svg)](b"), Some(destination(18, false)));
+        assert_eq!(site("[done](a.md) and more"), None);
+        assert_eq!(site("[x](a b"), None);
+    }
+
+    #[test]
+    fn test_definition_site() {
+        assert_eq!(site("[label]: "), Some(destination(9, false)));
+        assert_eq!(site("  [label]:docs/"), Some(destination(10, false)));
+        assert_eq!(site("[label]: a.md 'ti"), None);
+    }
+
+    #[test]
+    fn test_label_site() {
+        let label = |start, footnote| Some(Site::Label { start, footnote });
+        assert_eq!(site("See [the docs]["), label(15, false));
+        assert_eq!(site("See [the docs][do"), label(15, false));
+        assert_eq!(site("A footnote[^"), label(12, true));
+        assert_eq!(site("A footnote[^no"), label(12, true));
+        assert_eq!(site("A footnote[^1] and"), None);
+        assert_eq!(site("[a][b] and"), None);
+    }
+
+    #[test]
+    fn test_new_label() {
+        let text = "See [docs][new\n\n[old]: /old 'Old'\n";
+        let completions = labels(text, "new", false, 14);
+        let names = completions
+            .iter()
+            .map(|completion| completion.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["old", "new"]);
+        assert_eq!(
+            completions[0].documentation.as_deref(),
+            Some("`/old`\n\nOld")
+        );
+
+        let mut edited = text.to_owned();
+        completions[1].additional_edits[0].apply(&mut edited);
+        assert_eq!(edited, "See [docs][new\n\n[old]: /old 'Old'\n[new]: \n");
+
+        // Labels are matched case-insensitively.
+        let completions = labels("[x][OLD\n\n[old]: /old\n", "OLD", false, 7);
+        assert_eq!(completions.len(), 1);
+    }
+
+    #[test]
+    fn test_append_definition() {
+        let append = |text: &str| {
+            let mut text = text.to_owned();
+            append_definition(&text, "[a]: ").apply(&mut text);
+            text
+        };
+        assert_eq!(append(""), "[a]: \n");
+        assert_eq!(append("Text"), "Text\n\n[a]: \n");
+        assert_eq!(append("Text\n"), "Text\n\n[a]: \n");
+        assert_eq!(append("Text\n\n"), "Text\n\n[a]: \n");
+        assert_eq!(append("Text\n\n[b]: /b\n"), "Text\n\n[b]: /b\n[a]: \n");
+    }
+
+    #[test]
+    fn test_markdown_target() {
+        let uri = Url::parse("file:///root/docs/a.md").unwrap();
+        let root = Path::new("/root");
+        let target = |path| markdown_target(&uri, path, Some(root)).map(String::from);
+        assert_eq!(target("b.md").as_deref(), Some("file:///root/docs/b.md"));
+        assert_eq!(target("../b.md").as_deref(), Some("file:///root/b.md"));
+        assert_eq!(
+            target("/guide/b.md").as_deref(),
+            Some("file:///root/guide/b.md")
+        );
+        assert_eq!(target("b.png"), None);
+        assert_eq!(markdown_target(&uri, "/b.md", None), None);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("a b.md"), "a%20b.md");
+        assert_eq!(encode("a (1).png"), "a%20%281%29.png");
+        assert_eq!(encode("<a>#1?.md"), "%3Ca%3E%231%3F.md");
+        assert_eq!(encode("ü.md"), "ü.md");
+    }
+
+    #[test]
+    fn test_img_site() {
+        assert_eq!(
+            site("<img alt=\"x\" src=\"images/"),
+            Some(destination(18, true))
+        );
+        assert_eq!(site("<img src='a.png'> and"), None);
+        assert_eq!(site("<a src=\""), None);
+    }
+}
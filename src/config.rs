@@ -0,0 +1,113 @@
+//! Server configuration.
+//!
+//! Clients send the configuration in the `initializationOptions` of the
+//! `initialize` request and in `workspace/didChangeConfiguration`
+//! notifications, either directly or nested in an `mdls` section.
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    convert::DefinitionPlacement, lint::LintConfig, schema::FrontMatterConfig, slug::SlugStyle,
+    spell::SpellConfig, task::TaskConfig, toc::TocConfig,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Config {
+    /// How heading anchors are generated.
+    pub(crate) slug: SlugStyle,
+    /// How tables of contents are generated.
+    pub(crate) toc: TocConfig,
+    /// Where links converted to reference links get their definitions.
+    pub(crate) definition_placement: DefinitionPlacement,
+    /// How task list checkboxes are toggled.
+    pub(crate) task: TaskConfig,
+    /// Which style rules are enabled, and their options.
+    pub(crate) lint: LintConfig,
+    /// Where the dictionary and the words of the workspace are.
+    pub(crate) spell: SpellConfig,
+    /// Which JSON Schemas the front matter of documents is checked against.
+    pub(crate) front_matter: FrontMatterConfig,
+}
+
+impl Config {
+    /// Parses the configuration from client settings, falling back to the
+    /// defaults for anything missing or invalid, field by field.
+    pub(crate) fn from_settings(mut settings: serde_json::Value) -> Self {
+        if let Some(section) = settings.get_mut("mdls") {
+            settings = section.take();
+        }
+        let mut settings = match settings {
+            serde_json::Value::Object(settings) => settings,
+            serde_json::Value::Null => return Self::default(),
+            settings => {
+                tracing::warn!("invalid configuration: {}", settings);
+                return Self::default();
+            }
+        };
+        Self {
+            slug: field(&mut settings, "slug"),
+            toc: field(&mut settings, "toc"),
+            definition_placement: field(&mut settings, "definitionPlacement"),
+            task: field(&mut settings, "task"),
+            lint: field(&mut settings, "lint"),
+            spell: field(&mut settings, "spell"),
+            front_matter: field(&mut settings, "frontMatter"),
+        }
+    }
+}
+
+/// Parses a field of the settings, falling back to its default if it's missing
+/// or invalid.
+fn field<T: DeserializeOwned + Default>(
+    settings: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> T {
+    let Some(value) = settings.remove(key) else {
+        return T::default();
+    };
+    serde_json::from_value(value).unwrap_or_else(|error| {
+        tracing::warn!("invalid configuration of `{}`: {}", key, error);
+        T::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_settings() {
+        let settings = serde_json::json!({ "mdls": { "slug": "gitlab" } });
+        assert_eq!(
+            Config::from_settings(settings.clone()).slug,
+            SlugStyle::Gitlab
+        );
+        let settings = serde_json::json!({ "slug": "pandoc", "unknown": 1 });
+        assert_eq!(
+            Config::from_settings(settings.clone()).slug,
+            SlugStyle::Pandoc
+        );
+        assert_eq!(
+            Config::from_settings(serde_json::Value::Null),
+            Config::default()
+        );
+        let settings = serde_json::json!({
+            "frontMatter": { "schemas": { "content/blog/**": "schemas/post.json" } }
+        });
+        assert_eq!(
+            Config::from_settings(settings).front_matter.schemas["content/blog/**"],
+            "schemas/post.json"
+        );
+        let settings = serde_json::json!({ "slug": "commonmark" });
+        assert_eq!(Config::from_settings(settings.clone()), Config::default());
+        let settings = serde_json::json!({
+            "slug": "commonmark",
+            "toc": { "maxLevel": 3 },
+            "definitionPlacement": 1,
+        });
+        let config = Config::from_settings(settings);
+        assert_eq!(config.slug, SlugStyle::default());
+        assert_eq!(config.toc.max_level, 3);
+        assert_eq!(config.definition_placement, DefinitionPlacement::default());
+    }
+}
@@ -0,0 +1,272 @@
+//! Conversion between inline and reference links.
+
+use std::fmt::Write;
+
+use hashbrown::HashMap;
+use serde::Deserialize;
+use text_edit::{TextEdit, TextRange, TextSize};
+
+use crate::{
+    analysis::{normalize_label, Analysis, Link, LinkKind},
+    completion::append_definition,
+};
+
+/// Where the definitions of new reference links are added.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DefinitionPlacement {
+    /// At the end of the document.
+    #[default]
+    Document,
+    /// At the end of the section containing the link.
+    Section,
+}
+
+/// Returns whether an inline link can be converted to a reference link.
+pub(crate) fn is_inline(text: &str, link: &Link) -> bool {
+    link.kind == LinkKind::Inline
+        && !link.destination.is_empty()
+        && destination_part(text, link).is_some()
+}
+
+/// Returns whether a reference link can be converted to an inline link.
+pub(crate) fn is_reference(link: &Link) -> bool {
+    link.is_reference() && link.resolved
+}
+
+/// Turns inline links into reference links.
+///
+/// Links reuse the label of an existing definition with the same destination
+/// and title. Otherwise the label is generated from the link text, or is the
+/// first free number if the text doesn't make a good label.
+#[allow(clippy::string_slice)]
+pub(crate) fn to_reference(
+    text: &str,
+    analysis: &Analysis,
+    links: &[&Link],
+    placement: DefinitionPlacement,
+) -> TextEdit {
+    let mut labels = analysis
+        .definitions
+        .iter()
+        .map(|definition| {
+            let title = definition.title.clone().unwrap_or_default();
+            let target = (definition.destination.clone(), title);
+            (definition.label.normalized.clone(), target)
+        })
+        .collect::<Vec<_>>();
+    // The new definitions, by the offset they are inserted at.
+    let mut definitions = HashMap::<TextSize, String>::new();
+
+    let mut edit = TextEdit::builder();
+    for &link in links {
+        let Some(part) = destination_part(text, link) else {
+            continue;
+        };
+        let text_range = TextRange::new(
+            link.range.start() + TextSize::from(1 + u32::from(link.is_image)),
+            part.start() - TextSize::from(1),
+        );
+        let link_text = &text[text_range];
+
+        let target = (link.destination.clone(), link.title.clone());
+        let existing = labels.iter().find(|(_, other)| *other == target);
+        let label = if let Some((label, _)) = existing {
+            label.clone()
+        } else {
+            let label = new_label(link_text, &labels);
+            labels.push((label.clone(), target));
+            let offset = match placement {
+                DefinitionPlacement::Document => TextSize::of(text),
+                DefinitionPlacement::Section => section_end(text, analysis, link),
+            };
+            let definitions = definitions.entry(offset).or_default();
+            if !definitions.is_empty() {
+                definitions.push('\n');
+            }
+            definitions.push_str(&definition(&label, &link.destination, &link.title));
+            label
+        };
+
+        if normalize_label(link_text) == label {
+            edit.replace(part, "[]".to_owned());
+        } else {
+            edit.replace(part, format!("[{label}]"));
+        }
+    }
+
+    for (offset, definitions) in definitions {
+        let offset = usize::from(offset);
+        let mut indel = append_definition(&text[..offset], &definitions);
+        if offset < text.len() {
+            // Keep a blank line before the next heading.
+            indel.insert.push('\n');
+        }
+        edit.insert(indel.delete.start(), indel.insert);
+    }
+    edit.finish()
+}
+
+/// Turns reference links into inline links, removing the definitions that
+/// are no longer used.
+pub(crate) fn to_inline(text: &str, analysis: &Analysis, links: &[&Link]) -> TextEdit {
+    let mut converted = HashMap::<&str, usize>::new();
+
+    let mut edit = TextEdit::builder();
+    for &link in links {
+        let Some(label) = &link.label else { continue };
+        let Some(definition) = analysis.definition(&label.normalized) else {
+            continue;
+        };
+        let title = definition.title.as_deref().unwrap_or_default();
+        let inline = format!("({})", destination(&definition.destination, title));
+
+        let end = link.range.end();
+        match link.kind {
+            LinkKind::Reference => edit.replace(
+                TextRange::new(label.range.start() - TextSize::from(1), end),
+                inline,
+            ),
+            // The range of a collapsed link doesn't include the `[]`.
+            LinkKind::Collapsed
+                if text
+                    .get(usize::from(end)..)
+                    .is_some_and(|rest| rest.starts_with("[]")) =>
+            {
+                edit.replace(TextRange::at(end, TextSize::from(2)), inline);
+            }
+            LinkKind::Shortcut => edit.insert(end, inline),
+            _ => continue,
+        }
+        *converted.entry(label.normalized.as_str()).or_default() += 1;
+    }
+
+    for (label, count) in converted {
+        if analysis.references(label).count() == count {
+            let definition = analysis.definition(label).expect("label is defined");
+            edit.delete(definition.range);
+        }
+    }
+    edit.finish()
+}
+
+/// Returns the range of the `(destination "title")` part of an inline link.
+#[allow(clippy::string_slice)]
+fn destination_part(text: &str, link: &Link) -> Option<TextRange> {
+    let destination = link.destination_range?;
+    let before = text[..usize::from(destination.start())].trim_end();
+    let before = before.strip_suffix('<').unwrap_or(before).trim_end();
+    let open = before.strip_suffix('(')?.len();
+    text[..open]
+        .ends_with(']')
+        .then(|| TextRange::new(TextSize::from(open as u32), link.range.end()))
+}
+
+/// Generates a label that isn't in use from the text of a link.
+fn new_label(link_text: &str, labels: &[(String, (String, String))]) -> String {
+    let is_taken = |label: &str| labels.iter().any(|(other, _)| other == label);
+    let label = normalize_label(link_text);
+    let is_plain = !label.is_empty()
+        && label.len() <= 40
+        && !label.contains(['[', ']', '\\', '`', '*', '_', '<']);
+    if is_plain && !is_taken(&label) {
+        return label;
+    }
+    // One of the first `labels.len() + 1` numbers is free.
+    (1..=labels.len() + 1)
+        .map(|n| n.to_string())
+        .find(|label| !is_taken(label))
+        .expect("there is a free number")
+}
+
+/// Returns the offset before the heading that follows the innermost section
+/// containing the link.
+fn section_end(text: &str, analysis: &Analysis, link: &Link) -> TextSize {
+    let len = TextSize::of(text);
+    analysis
+        .headings
+        .iter()
+        .map(|heading| analysis.section(heading, len))
+        .filter(|section| section.contains_range(link.range))
+        .min_by_key(|section| section.len())
+        .map_or(len, TextRange::end)
+}
+
+fn definition(label: &str, destination: &str, title: &str) -> String {
+    format!("[{label}]: {}", self::destination(destination, title))
+}
+
+/// Formats a destination and title, with angle brackets around destinations
+/// that would otherwise be misparsed.
+fn destination(destination: &str, title: &str) -> String {
+    let needs_brackets = destination.is_empty()
+        || destination.contains(|c: char| c.is_whitespace() || c == '(' || c == ')');
+    let mut formatted = if needs_brackets {
+        format!("<{destination}>")
+    } else {
+        destination.to_owned()
+    };
+    if !title.is_empty() {
+        write!(formatted, " \"{}\"", title.replace('"', "\\\"")).unwrap();
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    fn apply(text: &str, convert: impl Fn(&Analysis, &[&Link]) -> TextEdit) -> String {
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let links = analysis.links.iter().collect::<Vec<_>>();
+        let mut text = text.to_owned();
+        convert(&analysis, &links).apply(&mut text);
+        text
+    }
+
+    #[test]
+    fn test_to_reference() {
+        let text = "# A\n\n[Rust](https://rust-lang.org \"The \\\"Rust\\\" site\") and [again](https://rust-lang.org 'The \"Rust\" site').\n\n\
+                    ## B\n\n![logo](<my logo.png>), [existing](/e) and [`code`](/c).\n\n[e]: /e\n";
+        assert_eq!(
+            apply(text, |analysis, links| to_reference(text, analysis, links, DefinitionPlacement::Document)),
+            "# A\n\n[Rust][] and [again][rust].\n\n\
+             ## B\n\n![logo][], [existing][e] and [`code`][1].\n\n[e]: /e\n\
+             [rust]: https://rust-lang.org \"The \\\"Rust\\\" site\"\n[logo]: <my logo.png>\n[1]: /c\n"
+        );
+    }
+
+    #[test]
+    fn test_to_reference_in_section() {
+        let text = "# A\n\n[a](/a)\n\n## B\n\n[b](/b)\n\n# C\n\n[c](/c)\n";
+        assert_eq!(
+            apply(text, |analysis, links| to_reference(
+                text,
+                analysis,
+                links,
+                DefinitionPlacement::Section
+            )),
+            "# A\n\n[a][]\n\n## B\n\n[b][]\n\n[a]: /a\n[b]: /b\n\n# C\n\n[c][]\n\n[c]: /c\n"
+        );
+    }
+
+    #[test]
+    fn test_to_inline() {
+        let text = "[a][x], [X][] and ![x].\n\n[x]: /x 'T'\n[y]: /y\n";
+        assert_eq!(
+            apply(text, |analysis, links| to_inline(text, analysis, links)),
+            "[a](/x \"T\"), [X](/x \"T\") and ![x](/x \"T\").\n\n[y]: /y\n"
+        );
+
+        let text = "[a][x] and [b][x]\n\n[x]: /x\n";
+        assert_eq!(
+            apply(text, |analysis, links| to_inline(
+                text,
+                analysis,
+                &links[..1]
+            )),
+            "[a](/x) and [b][x]\n\n[x]: /x\n"
+        );
+    }
+}
@@ -1,4 +1,16 @@
-use crate::{index::LineIndex, Error, Result};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config,
+    index::{LineIndex, PositionEncoding},
+    schema::SchemaCache,
+    spell::SpellChecker,
+    workspace::{self, LinkIndex},
+    Error, Result,
+};
 use crossbeam_channel::{Receiver, Sender};
 use hashbrown::HashMap;
 use lsp_server::{Connection, Incoming, Message, Outgoing, ReqQueue, Request, Response};
@@ -10,6 +22,20 @@ pub struct Context {
     incoming: Incoming<()>,
     outgoing: Outgoing<fn(&mut Context, Response)>,
     files: HashMap<Url, (LineIndex, String)>,
+    /// The root directory of the workspace, if the client opened one.
+    root: Option<PathBuf>,
+    config: Config,
+    encoding: PositionEncoding,
+    /// The last semantic tokens sent for each document, for computing deltas.
+    semantic_tokens: HashMap<Url, lsp_types::SemanticTokens>,
+    next_result_id: u64,
+    /// Built the first time a feature needs it.
+    link_index: Option<LinkIndex>,
+    /// Loaded the first time a document is checked, and `None` if no
+    /// dictionary is configured or it can't be read.
+    spell_checker: Option<SpellChecker>,
+    spell_checker_loaded: bool,
+    schema_cache: SchemaCache,
     _io_threads: lsp_server::IoThreads,
 }
 
@@ -24,15 +50,84 @@ impl Context {
     pub fn init_from_stdio() -> Self {
         let (connection, io_threads) = Connection::stdio();
 
-        let server_capabilities = serde_json::to_value(&lsp_types::ServerCapabilities {
+        let (initialize_id, initialization_params) = connection.initialize_start().unwrap();
+        let encoding = position_encoding(&initialization_params);
+
+        let mut server_capabilities = serde_json::to_value(&lsp_types::ServerCapabilities {
             document_formatting_provider: Some(lsp_types::OneOf::Left(true)),
+            code_action_provider: Some(lsp_types::CodeActionProviderCapability::Options(
+                lsp_types::CodeActionOptions {
+                    code_action_kinds: Some(crate::proto::code_action_kinds()),
+                    work_done_progress_options: lsp_types::WorkDoneProgressOptions::default(),
+                    resolve_provider: None,
+                },
+            )),
+            definition_provider: Some(lsp_types::OneOf::Left(true)),
+            references_provider: Some(lsp_types::OneOf::Left(true)),
+            code_lens_provider: Some(lsp_types::CodeLensOptions {
+                resolve_provider: Some(true),
+            }),
+            execute_command_provider: Some(lsp_types::ExecuteCommandOptions {
+                commands: crate::command::ALL
+                    .iter()
+                    .map(|&command| command.to_owned())
+                    .collect(),
+                work_done_progress_options: lsp_types::WorkDoneProgressOptions::default(),
+            }),
+            document_highlight_provider: Some(lsp_types::OneOf::Left(true)),
+            document_symbol_provider: Some(lsp_types::OneOf::Left(true)),
+            hover_provider: Some(lsp_types::HoverProviderCapability::Simple(true)),
+            completion_provider: Some(lsp_types::CompletionOptions {
+                trigger_characters: Some(["(", "/", "#", "[", "^"].map(ToOwned::to_owned).to_vec()),
+                ..Default::default()
+            }),
+            document_link_provider: Some(lsp_types::DocumentLinkOptions {
+                resolve_provider: Some(true),
+                work_done_progress_options: lsp_types::WorkDoneProgressOptions::default(),
+            }),
+            selection_range_provider: Some(lsp_types::SelectionRangeProviderCapability::Simple(
+                true,
+            )),
+            semantic_tokens_provider: Some(
+                lsp_types::SemanticTokensOptions {
+                    legend: crate::proto::semantic_tokens_legend(),
+                    range: Some(true),
+                    full: Some(lsp_types::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                    ..Default::default()
+                }
+                .into(),
+            ),
             text_document_sync: Some(lsp_types::TextDocumentSyncCapability::Kind(
                 lsp_types::TextDocumentSyncKind::FULL,
             )),
             ..Default::default()
         })
         .unwrap();
-        let _initialization_params = connection.initialize(server_capabilities).unwrap();
+        // `lsp-types` doesn't know about position encodings yet.
+        server_capabilities["positionEncoding"] = match encoding {
+            PositionEncoding::Utf8 => "utf-8",
+            PositionEncoding::Utf16 => "utf-16",
+        }
+        .into();
+        connection
+            .initialize_finish(
+                initialize_id,
+                serde_json::json!({ "capabilities": server_capabilities }),
+            )
+            .unwrap();
+        let initialization_params =
+            serde_json::from_value::<lsp_types::InitializeParams>(initialization_params).unwrap();
+
+        #[allow(deprecated)]
+        let root = initialization_params
+            .workspace_folders
+            .and_then(|folders| folders.into_iter().next().map(|folder| folder.uri))
+            .or(initialization_params.root_uri)
+            .and_then(|uri| uri.to_file_path().ok());
+        let config = initialization_params
+            .initialization_options
+            .map(Config::from_settings)
+            .unwrap_or_default();
 
         let ReqQueue { incoming, outgoing } = ReqQueue::default();
         Self {
@@ -41,6 +136,15 @@ impl Context {
             incoming,
             outgoing,
             files: HashMap::new(),
+            root,
+            config,
+            encoding,
+            semantic_tokens: HashMap::new(),
+            next_result_id: 0,
+            link_index: None,
+            spell_checker: None,
+            spell_checker_loaded: false,
+            schema_cache: SchemaCache::default(),
             _io_threads: io_threads,
         }
     }
@@ -57,6 +161,127 @@ impl Context {
             .ok_or(Error::UnknownDocument)
     }
 
+    pub(crate) fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    pub(crate) fn position_encoding(&self) -> PositionEncoding {
+        self.encoding
+    }
+
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub(crate) fn set_config(&mut self, config: Config) {
+        if config.spell != self.config.spell {
+            self.spell_checker = None;
+            self.spell_checker_loaded = false;
+        }
+        if config.front_matter != self.config.front_matter {
+            self.schema_cache = SchemaCache::default();
+        }
+        self.config = config;
+    }
+
+    /// Returns the semantic tokens last sent for a document.
+    pub(crate) fn semantic_tokens(&self, uri: &Url) -> Option<&lsp_types::SemanticTokens> {
+        self.semantic_tokens.get(uri)
+    }
+
+    /// Assigns a result ID to semantic tokens and remembers them as the last
+    /// ones sent for the document.
+    pub(crate) fn store_semantic_tokens(
+        &mut self,
+        uri: Url,
+        data: Vec<lsp_types::SemanticToken>,
+    ) -> lsp_types::SemanticTokens {
+        self.next_result_id += 1;
+        let tokens = lsp_types::SemanticTokens {
+            result_id: Some(self.next_result_id.to_string()),
+            data,
+        };
+        self.semantic_tokens.insert(uri, tokens.clone());
+        tokens
+    }
+
+    /// Returns the index of the links in the workspace, building it if needed.
+    pub(crate) fn link_index(&mut self) -> &LinkIndex {
+        if self.link_index.is_none() {
+            let files = self
+                .root
+                .as_deref()
+                .map(workspace::markdown_files)
+                .unwrap_or_default();
+            let uris = files
+                .into_iter()
+                .filter_map(|path| Url::from_file_path(path).ok())
+                .chain(self.files.keys().cloned());
+
+            let mut index = LinkIndex::default();
+            for uri in uris {
+                if let Some(text) = self.document_text(&uri) {
+                    let line_index = LineIndex::new(&text, self.encoding);
+                    index.update(uri, &text, line_index, self.root.as_deref());
+                }
+            }
+            self.link_index = Some(index);
+        }
+        self.link_index.get_or_insert_with(LinkIndex::default)
+    }
+
+    /// Reindexes an open document, unless the index hasn't been built yet.
+    pub(crate) fn update_link_index(&mut self, uri: &Url) {
+        if let (Some(index), Some((line_index, text))) = (&mut self.link_index, self.files.get(uri))
+        {
+            index.update(uri.clone(), text, line_index.clone(), self.root.as_deref());
+        }
+    }
+
+    /// Loads the spell checker if needed, and picks up changes to the words
+    /// file, without reloading the dictionary.
+    pub(crate) fn refresh_spell_checker(&mut self) {
+        if !self.spell_checker_loaded {
+            self.spell_checker = SpellChecker::load(&self.config.spell, self.root.as_deref());
+            self.spell_checker_loaded = true;
+        } else if let Some(checker) = &mut self.spell_checker {
+            checker.refresh();
+        }
+    }
+
+    pub(crate) fn spell_checker(&self) -> Option<&SpellChecker> {
+        self.spell_checker.as_ref()
+    }
+
+    pub(crate) fn spell_checker_mut(&mut self) -> Option<&mut SpellChecker> {
+        self.spell_checker.as_mut()
+    }
+
+    /// Reads the front matter schemas that changed since they were last read.
+    pub(crate) fn refresh_schemas(&mut self) {
+        self.schema_cache
+            .refresh(&self.config.front_matter, self.root.as_deref());
+    }
+
+    pub(crate) fn schema_cache(&self) -> &SchemaCache {
+        &self.schema_cache
+    }
+
+    /// Returns the URIs of the open documents.
+    pub(crate) fn open_documents(&self) -> Vec<Url> {
+        self.files.keys().cloned().collect()
+    }
+
+    /// Returns the text of a document, reading it from disk if it isn't open.
+    pub(crate) fn document_text(&self, uri: &Url) -> Option<Cow<'_, str>> {
+        if let Some((_, text)) = self.files.get(uri) {
+            Some(Cow::Borrowed(text))
+        } else {
+            let path = uri.to_file_path().ok()?;
+            std::fs::read_to_string(path).ok().map(Cow::Owned)
+        }
+    }
+
     pub(crate) fn get_mut_contents(&mut self, uri: &Url) -> Result<&mut (LineIndex, String)> {
         self.files
             .get_mut(uri)
@@ -65,7 +290,7 @@ impl Context {
     }
 
     pub(crate) fn insert_file(&mut self, uri: Url, text: String) -> Result<()> {
-        let line_index = LineIndex::new(&text);
+        let line_index = LineIndex::new(&text, self.encoding);
         match self.files.insert(uri, (line_index, text)) {
             None => Ok(()),
             Some(_) => Err(Error::DocumentAlreadyExists),
@@ -83,6 +308,15 @@ impl Context {
         self.send(request.into());
     }
 
+    /// Sends a notification to the client.
+    pub fn send_notification<N>(&mut self, params: N::Params)
+    where
+        N: lsp_types::notification::Notification,
+    {
+        let notification = lsp_server::Notification::new(N::METHOD.to_owned(), params);
+        self.send(notification.into());
+    }
+
     /// Handles a client response.
     pub fn complete_request(&mut self, response: Response) {
         let handler = self
@@ -109,3 +343,17 @@ impl Context {
         self.sender.send(message).unwrap();
     }
 }
+
+/// Picks UTF-8 positions if the client supports them, since that is what the
+/// server uses internally.
+fn position_encoding(initialization_params: &serde_json::Value) -> PositionEncoding {
+    let supports_utf8 = initialization_params
+        .pointer("/capabilities/general/positionEncodings")
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|encodings| encodings.iter().any(|encoding| encoding == "utf-8"));
+    if supports_utf8 {
+        PositionEncoding::Utf8
+    } else {
+        PositionEncoding::Utf16
+    }
+}
@@ -0,0 +1,319 @@
+use text_edit::{TextEdit, TextRange};
+
+use crate::{
+    analysis::{Analysis, LinkKind},
+    config::Config,
+    front_matter::{self, Format},
+    lint, toc,
+};
+
+/// Bounds the passes of [`fix_all`], in case fixes keep undoing each other.
+const MAX_FIX_PASSES: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+    Information,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Diagnostic {
+    pub(crate) range: TextRange,
+    pub(crate) severity: Severity,
+    pub(crate) code: &'static str,
+    pub(crate) message: String,
+    /// Marks the range as unused, which editors usually render faded out.
+    pub(crate) unnecessary: bool,
+    pub(crate) related: Vec<(TextRange, String)>,
+    pub(crate) fixes: Vec<Fix>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(
+        range: TextRange,
+        severity: Severity,
+        code: &'static str,
+        message: String,
+    ) -> Self {
+        Self {
+            range,
+            severity,
+            code,
+            message,
+            unnecessary: false,
+            related: Vec::new(),
+            fixes: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Fix {
+    pub(crate) title: String,
+    pub(crate) edit: TextEdit,
+    /// Whether the fix can be applied without looking at it, as part of
+    /// fixing all problems at once.
+    pub(crate) safe: bool,
+}
+
+pub(crate) fn diagnostics(text: &str, analysis: &Analysis, config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    invalid_front_matter(text, &mut diagnostics);
+    references(analysis, &mut diagnostics);
+    footnotes(analysis, &mut diagnostics);
+    table_of_contents(text, analysis, config, &mut diagnostics);
+    diagnostics.extend(lint::lint(text, analysis, config.slug, &config.lint));
+    diagnostics
+}
+
+/// Applies every safe fix, over and over since fixes can uncover further
+/// problems, e.g. when fixing a heading level makes the next one skip a level.
+pub(crate) fn fix_all(text: &str, config: &Config) -> TextEdit {
+    let mut fixed = text.to_owned();
+    for _ in 0..MAX_FIX_PASSES {
+        let analysis = Analysis::new(&fixed, config.slug);
+        let mut edit = TextEdit::default();
+        for fix in diagnostics(&fixed, &analysis, config)
+            .into_iter()
+            .flat_map(|diagnostic| diagnostic.fixes)
+            .filter(|fix| fix.safe)
+        {
+            // Several problems can have the same fix, such as the blank line
+            // between a heading and a list.
+            if fix
+                .edit
+                .iter()
+                .all(|indel| edit.iter().any(|other| other == indel))
+            {
+                continue;
+            }
+            // Fixes that touch the same text wait for the next pass.
+            let collides = fix.edit.iter().any(|indel| {
+                edit.iter()
+                    .any(|other| other.delete.start() == indel.delete.start())
+            });
+            if !collides {
+                let _ = edit.union(fix.edit);
+            }
+        }
+        if edit.is_empty() {
+            break;
+        }
+        edit.apply(&mut fixed);
+    }
+    crate::fmt::diff(text, &fixed)
+}
+
+/// Reports front matter that doesn't parse.
+fn invalid_front_matter(text: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(front_matter) = front_matter::parse(text) else {
+        return;
+    };
+    if let Some(error) = front_matter.error {
+        let format = match front_matter.format {
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+        };
+        diagnostics.push(Diagnostic::new(
+            error.range,
+            Severity::Error,
+            "invalid-front-matter",
+            format!("invalid {format} front matter: {}", error.message),
+        ));
+    }
+}
+
+/// Reports undefined, unused and duplicate reference labels.
+///
+/// Shortcut links without a definition are not reported because `[text]` is
+/// just as likely to be literal brackets.
+fn references(analysis: &Analysis, diagnostics: &mut Vec<Diagnostic>) {
+    for link in &analysis.links {
+        let Some(label) = &link.label else { continue };
+        if !link.resolved && link.kind != LinkKind::Shortcut {
+            diagnostics.push(Diagnostic::new(
+                label.range,
+                Severity::Error,
+                "undefined-reference",
+                format!("no definition for `[{}]`", label.text),
+            ));
+        }
+    }
+
+    for definition in &analysis.definitions {
+        let label = &definition.label;
+        let first = analysis
+            .definition(&label.normalized)
+            .expect("definition is in the list");
+
+        if first.range != definition.range {
+            let mut diagnostic = Diagnostic::new(
+                label.range,
+                Severity::Warning,
+                "duplicate-definition",
+                format!("`[{}]` is already defined", label.text),
+            );
+            diagnostic.unnecessary = true;
+            diagnostic
+                .related
+                .push((first.label.range, "first defined here".to_owned()));
+            diagnostic.fixes.push(remove_definition(definition.range));
+            diagnostics.push(diagnostic);
+        } else if analysis.references(&label.normalized).next().is_none() {
+            let mut diagnostic = Diagnostic::new(
+                label.range,
+                Severity::Warning,
+                "unused-definition",
+                format!("`[{}]` is never used", label.text),
+            );
+            diagnostic.unnecessary = true;
+            diagnostic.fixes.push(remove_definition(definition.range));
+            diagnostics.push(diagnostic);
+        }
+    }
+}
+
+/// Reports undefined and unused footnotes.
+fn footnotes(analysis: &Analysis, diagnostics: &mut Vec<Diagnostic>) {
+    for label in &analysis.footnote_references {
+        if analysis.footnote_definition(&label.normalized).is_none() {
+            diagnostics.push(Diagnostic::new(
+                label.range,
+                Severity::Error,
+                "undefined-footnote",
+                format!("no definition for footnote `[^{}]`", label.text),
+            ));
+        }
+    }
+
+    for definition in &analysis.footnote_definitions {
+        let label = &definition.label;
+        let is_used = analysis
+            .footnote_references
+            .iter()
+            .any(|reference| reference.normalized == label.normalized);
+        if !is_used {
+            let mut diagnostic = Diagnostic::new(
+                label.range,
+                Severity::Warning,
+                "unused-footnote",
+                format!("footnote `[^{}]` is never referenced", label.text),
+            );
+            diagnostic.unnecessary = true;
+            diagnostics.push(diagnostic);
+        }
+    }
+}
+
+/// Reports a table of contents that doesn't match the headings.
+fn table_of_contents(
+    text: &str,
+    analysis: &Analysis,
+    config: &Config,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let Some(markers) = toc::markers(text) else {
+        return;
+    };
+    if let Some(edit) = toc::update(text, analysis, &config.toc, markers) {
+        let mut diagnostic = Diagnostic::new(
+            markers.start,
+            Severity::Warning,
+            "outdated-toc",
+            "table of contents doesn't match the headings".to_owned(),
+        );
+        diagnostic.fixes.push(Fix {
+            title: "Update table of contents".to_owned(),
+            edit,
+            safe: true,
+        });
+        diagnostics.push(diagnostic);
+    }
+}
+
+fn remove_definition(range: TextRange) -> Fix {
+    Fix {
+        title: "Remove definition".to_owned(),
+        edit: TextEdit::delete(range),
+        safe: false,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    fn codes(text: &str) -> Vec<(&'static str, &str)> {
+        let analysis = Analysis::new(text, SlugStyle::default());
+        // The lint rules have tests of their own.
+        let config = Config {
+            lint: serde_json::from_value(serde_json::json!({ "default": false })).unwrap(),
+            ..Config::default()
+        };
+        diagnostics(text, &analysis, &config)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code, &text[diagnostic.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_reference_diagnostics() {
+        let text = "[a][used], [b][missing], [c][] and [shortcut]\n\n\
+                    [Used]: /used\n[unused]: /unused\n[USED]: /again\n";
+        assert_eq!(
+            codes(text),
+            [
+                ("undefined-reference", "missing"),
+                ("undefined-reference", "c"),
+                ("unused-definition", "unused"),
+                ("duplicate-definition", "USED"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix_all() {
+        let text = "# A\n### B\n#### C\nSee https://x.y \n\n[u]: /u\n";
+        let mut fixed = text.to_owned();
+        fix_all(text, &Config::default()).apply(&mut fixed);
+        assert_eq!(
+            fixed,
+            "# A\n\n## B\n\n### C\n\nSee <https://x.y>\n\n[u]: /u\n"
+        );
+    }
+
+    #[test]
+    fn test_footnote_diagnostics() {
+        let text = "One[^1] and two[^2].\n\n[^1]: First.\n\n[^3]: Third.\n";
+        assert_eq!(
+            codes(text),
+            [("undefined-footnote", "2"), ("unused-footnote", "3")]
+        );
+    }
+
+    #[test]
+    fn test_front_matter_diagnostics() {
+        assert!(codes("---\ntitle: A\n---\n\n# A\n").is_empty());
+        assert_eq!(
+            codes("---\ntitle: A\ntitle: B\n---\n"),
+            [("invalid-front-matter", "title")]
+        );
+        let text = "+++\ntitle = A\n+++\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+        assert_eq!(
+            diagnostics(text, &analysis, &Config::default())[0].message,
+            "invalid TOML front matter: invalid value `A`, strings should be quoted"
+        );
+    }
+
+    #[test]
+    fn test_toc_diagnostics() {
+        let text = "<!-- toc -->\n\n- [A](#a)\n\n<!-- tocstop -->\n\n# A\n";
+        assert!(codes(text).is_empty());
+        let text = "<!-- toc -->\n\n- [A](#a)\n\n<!-- tocstop -->\n\n# B\n";
+        assert_eq!(codes(text), [("outdated-toc", "<!-- toc -->")]);
+    }
+}
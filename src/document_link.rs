@@ -0,0 +1,141 @@
+//! Clickable links.
+//!
+//! Links to a heading in another file are resolved lazily, since finding the
+//! line of the heading means reading and parsing that file.
+
+use std::path::Path;
+
+use lsp_types::Url;
+use text_edit::TextRange;
+
+use crate::{
+    analysis::{local_destination, Analysis, LinkKind},
+    index::LineIndex,
+    workspace, Context,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+    Url(Url),
+    /// A heading in a Markdown document, identified by its anchor.
+    Heading(Url, String),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DocumentLink {
+    pub(crate) range: TextRange,
+    pub(crate) target: Target,
+}
+
+/// Finds the links, images, definitions and autolinks of a document.
+///
+/// Reference-style links are linked through their label, the others through
+/// their destination. Paths starting with `/` are relative to the workspace
+/// root.
+pub(crate) fn document_links(
+    uri: &Url,
+    analysis: &Analysis,
+    root: Option<&Path>,
+) -> Vec<DocumentLink> {
+    let links = analysis.links.iter().filter_map(|link| {
+        let range = match &link.label {
+            Some(label) if link.resolved => label.range,
+            Some(_) => return None,
+            None => link.destination_range?,
+        };
+        let target = if link.kind == LinkKind::Autolink && Url::parse(&link.destination).is_err() {
+            Target::Url(Url::parse(&format!("mailto:{}", link.destination)).ok()?)
+        } else {
+            target(uri, &link.destination, root)?
+        };
+        Some(DocumentLink { range, target })
+    });
+    let definitions = analysis.definitions.iter().filter_map(|definition| {
+        Some(DocumentLink {
+            range: definition.destination_range?,
+            target: target(uri, &definition.destination, root)?,
+        })
+    });
+
+    let mut links = links.chain(definitions).collect::<Vec<_>>();
+    links.sort_by_key(|link| link.range.start());
+    links
+}
+
+fn target(uri: &Url, destination: &str, root: Option<&Path>) -> Option<Target> {
+    if destination.is_empty() {
+        return None;
+    }
+    let Some((path, fragment)) = local_destination(destination) else {
+        return Url::parse(destination).ok().map(Target::Url);
+    };
+
+    let mut target = workspace::resolve(uri, path, root)?;
+    target.set_fragment(None);
+    Some(match fragment {
+        Some(anchor) if !anchor.is_empty() => Target::Heading(target, anchor.to_owned()),
+        _ => Target::Url(target),
+    })
+}
+
+/// Resolves a link to a heading to a `#L<line>` URL, or to the document if
+/// the heading doesn't exist.
+pub(crate) fn resolve(ctx: &Context, mut uri: Url, anchor: &str) -> Url {
+    let line = ctx.document_text(&uri).and_then(|text| {
+        let analysis = Analysis::new(&text, ctx.config().slug);
+        let heading = analysis.heading(anchor)?;
+        Some(
+            LineIndex::new(&text, ctx.position_encoding())
+                .line_col(heading.range.start())
+                .line,
+        )
+    });
+    uri.set_fragment(line.map(|line| format!("L{}", line + 1)).as_deref());
+    uri
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    #[test]
+    fn test_document_links() {
+        let text = "\
+[guide](../guide.md#setup), [top](#top), ![logo](img/logo.png), [ref], [nope][], [root](/a.md)
+<https://example.com> and <me@example.com>
+
+[ref]: https://rust-lang.org
+";
+        let uri = Url::parse("file:///repo/docs/README.md").unwrap();
+        let analysis = Analysis::new(text, SlugStyle::default());
+
+        let links = document_links(&uri, &analysis, Some(Path::new("/repo")))
+            .into_iter()
+            .map(|link| {
+                let target = match link.target {
+                    Target::Url(url) => url.to_string(),
+                    Target::Heading(url, anchor) => format!("{url} @ {anchor}"),
+                };
+                (&text[link.range], target)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            [
+                (
+                    "../guide.md#setup",
+                    "file:///repo/guide.md @ setup".to_owned()
+                ),
+                ("#top", "file:///repo/docs/README.md @ top".to_owned()),
+                ("img/logo.png", "file:///repo/docs/img/logo.png".to_owned()),
+                ("ref", "https://rust-lang.org/".to_owned()),
+                ("/a.md", "file:///repo/a.md".to_owned()),
+                ("https://example.com", "https://example.com/".to_owned()),
+                ("me@example.com", "mailto:me@example.com".to_owned()),
+                ("https://rust-lang.org", "https://rust-lang.org/".to_owned()),
+            ]
+        );
+    }
+}
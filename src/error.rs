@@ -8,9 +8,15 @@ pub enum Error {
     DocumentAlreadyExists,
 }
 
-impl From<Error> for lsp_server::Response {
-    fn from(_e: Error) -> Self {
-        todo!();
+impl Error {
+    /// Returns the response to the request with the id that failed.
+    #[must_use]
+    pub fn into_response(self, id: lsp_server::RequestId) -> lsp_server::Response {
+        lsp_server::Response::new_err(
+            id,
+            lsp_server::ErrorCode::InvalidParams as i32,
+            self.to_string(),
+        )
     }
 }
 
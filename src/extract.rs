@@ -0,0 +1,331 @@
+//! Moving a section into a document of its own.
+
+use hashbrown::HashMap;
+use text_edit::{Indel, TextEdit, TextRange, TextSize};
+
+use crate::{
+    analysis::{local_destination, Analysis, Heading},
+    completion::append_definition,
+    slug::SlugStyle,
+};
+
+/// The result of extracting a section.
+#[derive(Clone, Debug)]
+pub(crate) struct Extraction {
+    /// The contents of the new document.
+    pub(crate) contents: String,
+    /// Replaces the section with a link to the new document, and redirects
+    /// the links to the moved headings.
+    pub(crate) edit: TextEdit,
+    /// The anchors of the moved headings, and their anchors in the new
+    /// document, starting with the extracted heading.
+    pub(crate) anchors: Vec<(String, String)>,
+}
+
+/// Returns the file name for a section, which doesn't collide with any of the
+/// names that are taken.
+pub(crate) fn file_name(heading: &Heading, is_taken: impl Fn(&str) -> bool) -> String {
+    let stem = if heading.anchor.is_empty() {
+        "section"
+    } else {
+        &heading.anchor
+    };
+    let mut name = format!("{stem}.md");
+    let mut n = 0;
+    while is_taken(&name) {
+        n += 1;
+        name = format!("{stem}-{n}.md");
+    }
+    name
+}
+
+/// Moves the section of a heading into a new document next to the original
+/// one, with the heading at level 1 and its subheadings shifted along.
+///
+/// `original` and `new` are the file names of the documents. Since both are
+/// in the same directory, relative paths stay valid, and only links to
+/// anchors need to be redirected.
+///
+/// Returns `None` if removing the section would renumber the anchors of
+/// duplicate headings that stay behind, which would break the links to them.
+#[allow(clippy::string_slice)]
+pub(crate) fn extract(
+    text: &str,
+    analysis: &Analysis,
+    heading: &Heading,
+    slug: SlugStyle,
+    original: &str,
+    new: &str,
+) -> Option<Extraction> {
+    if renumbers_anchors(text, analysis, heading, slug) {
+        return None;
+    }
+    let section = analysis.section(heading, TextSize::of(text));
+    let moved = analysis
+        .headings
+        .iter()
+        .filter(|other| section.contains_range(other.range))
+        .collect::<Vec<_>>();
+    let shift = heading.level - 1;
+
+    // Offsets in the new document are relative to the section.
+    let relative = |range: TextRange| range - section.start();
+    let mut levels = Vec::new();
+    for heading in &moved {
+        levels.extend(set_level(text, heading, heading.level - shift));
+    }
+    let levels = levels
+        .into_iter()
+        .map(|indel| Indel::replace(relative(indel.delete), indel.insert))
+        .collect::<Vec<_>>();
+
+    let mut contents = text[section].to_owned();
+    indels(levels.clone()).apply(&mut contents);
+    let new_analysis = Analysis::new(&contents, slug);
+    let anchors = moved
+        .iter()
+        .zip(&new_analysis.headings)
+        .map(|(old, new)| (old.anchor.clone(), new.anchor.clone()))
+        .collect::<Vec<_>>();
+    let new_anchor = anchors.iter().cloned().collect::<HashMap<_, _>>();
+
+    // Links in the moved section to headings that stay behind point to the
+    // original document.
+    let redirect_out = |destination: &str| {
+        let (path, anchor) = local_destination(destination)?;
+        let anchor = anchor.filter(|_| path.is_empty())?;
+        Some(match new_anchor.get(anchor) {
+            Some(anchor) => format!("#{anchor}"),
+            None => format!("{original}#{anchor}"),
+        })
+    };
+    // Links elsewhere to moved headings point to the new document.
+    let redirect_in = |destination: &str| {
+        let (path, anchor) = local_destination(destination)?;
+        let anchor = anchor.filter(|_| path.is_empty())?;
+        let new_anchor = new_anchor.get(anchor)?;
+        Some(if anchor == heading.anchor {
+            new.to_owned()
+        } else {
+            format!("{new}#{new_anchor}")
+        })
+    };
+
+    let mut moved_edits = levels;
+    let mut edit = TextEdit::builder();
+    let destinations = analysis
+        .links
+        .iter()
+        .filter(|link| link.label.is_none())
+        .map(|link| (link.range, &link.destination, link.destination_range))
+        .chain(analysis.definitions.iter().map(|definition| {
+            (
+                definition.range,
+                &definition.destination,
+                definition.destination_range,
+            )
+        }));
+    for (range, destination, destination_range) in destinations {
+        let Some(destination_range) = destination_range else {
+            continue;
+        };
+        if section.contains_range(range) {
+            if let Some(destination) = redirect_out(destination) {
+                moved_edits.push(Indel::replace(relative(destination_range), destination));
+            }
+        } else if let Some(destination) = redirect_in(destination) {
+            edit.replace(destination_range, destination);
+        }
+    }
+
+    let mut contents = text[section].to_owned();
+    indels(moved_edits).apply(&mut contents);
+    contents.truncate(contents.trim_end().len());
+    contents.push('\n');
+    for definition in copied_definitions(text, analysis, section, redirect_out) {
+        let indel = append_definition(&contents, &definition);
+        indel.apply(&mut contents);
+    }
+
+    let title = heading.text.replace('[', "\\[").replace(']', "\\]");
+    let separator = if section.end() == TextSize::of(text) {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    edit.replace(section, format!("[{title}]({new}){separator}"));
+
+    Some(Extraction {
+        contents,
+        edit: edit.finish(),
+        anchors,
+    })
+}
+
+/// Returns whether removing the section of a heading changes the anchors of
+/// the headings outside of it, which happens to the `-1`, `-2`, … anchors of
+/// duplicate headings.
+#[allow(clippy::string_slice)]
+pub(crate) fn renumbers_anchors(
+    text: &str,
+    analysis: &Analysis,
+    heading: &Heading,
+    slug: SlugStyle,
+) -> bool {
+    let section = analysis.section(heading, TextSize::of(text));
+    let remaining = format!(
+        "{}{}",
+        &text[..usize::from(section.start())],
+        &text[usize::from(section.end())..]
+    );
+    let kept = analysis
+        .headings
+        .iter()
+        .filter(|heading| !section.contains_range(heading.range))
+        .map(|heading| &heading.anchor);
+    !kept.eq(Analysis::new(&remaining, slug)
+        .headings
+        .iter()
+        .map(|heading| &heading.anchor))
+}
+
+/// Copies the definitions that stay behind but are used by moved links.
+#[allow(clippy::string_slice)]
+fn copied_definitions(
+    text: &str,
+    analysis: &Analysis,
+    section: TextRange,
+    redirect: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    let mut copied = Vec::<(&str, String)>::new();
+    for link in &analysis.links {
+        let Some(label) = &link.label else { continue };
+        let Some(definition) = analysis.definition(&label.normalized) else {
+            continue;
+        };
+        if !section.contains_range(link.range)
+            || section.contains_range(definition.range)
+            || copied.iter().any(|(copied, _)| *copied == label.normalized)
+        {
+            continue;
+        }
+        let mut copy = text[definition.range].trim_end().to_owned();
+        if let (Some(range), Some(destination)) = (
+            definition.destination_range,
+            redirect(&definition.destination),
+        ) {
+            let range = range - definition.range.start();
+            copy.replace_range(std::ops::Range::<usize>::from(range), &destination);
+        }
+        copied.push((&label.normalized, copy));
+    }
+    copied.into_iter().map(|(_, copy)| copy).collect()
+}
+
+fn indels(indels: Vec<Indel>) -> TextEdit {
+    let mut edit = TextEdit::builder();
+    for indel in indels {
+        edit.replace(indel.delete, indel.insert);
+    }
+    edit.finish()
+}
+
+/// Changes the level of a heading, turning setext headings into ATX ones
+/// since they only have two levels.
+#[allow(clippy::string_slice)]
+pub(crate) fn set_level(text: &str, heading: &Heading, level: usize) -> Vec<Indel> {
+    if heading.level == level {
+        return Vec::new();
+    }
+    let source = &text[heading.range];
+    let hashes = "#".repeat(level);
+    let indent = source.len() - source.trim_start_matches(' ').len();
+    let start = heading.range.start() + TextSize::try_from(indent).expect("offset fits");
+
+    let marker_len = source[indent..].bytes().take_while(|&b| b == b'#').count();
+    if marker_len > 0 {
+        let marker = TextRange::at(start, TextSize::try_from(marker_len).expect("length fits"));
+        return vec![Indel::replace(marker, hashes)];
+    }
+
+    // The underline is the last line of the heading.
+    let content_end = source.trim_end().rfind('\n').unwrap_or_default();
+    let underline = TextRange::new(
+        heading.range.start() + TextSize::try_from(content_end).expect("offset fits"),
+        heading.range.start() + TextSize::of(source.trim_end()),
+    );
+    vec![
+        Indel::insert(start, format!("{hashes} ")),
+        Indel::delete(underline),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let text = "# Guide\n\nSee [setup](#setup) and [usage][].\n\n\
+                    Setup\n-----\n\nAfter [intro](#guide), [install](#install) and [run][].\n\n\
+                    ### Install\n\n[Docs](docs/a.md#x)\n\n\
+                    ## Usage\n\n[usage]: #usage\n[run]: #install 'Run'\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let extraction = extract(
+            text,
+            &analysis,
+            &analysis.headings[1],
+            SlugStyle::default(),
+            "guide.md",
+            "setup.md",
+        )
+        .unwrap();
+        assert_eq!(
+            extraction.contents,
+            "# Setup\n\nAfter [intro](guide.md#guide), [install](#install) and [run][].\n\n\
+             ## Install\n\n[Docs](docs/a.md#x)\n\n[run]: #install 'Run'\n"
+        );
+        let mut text = text.to_owned();
+        extraction.edit.apply(&mut text);
+        assert_eq!(
+            text,
+            "# Guide\n\nSee [setup](setup.md) and [usage][].\n\n[Setup](setup.md)\n\n\
+             ## Usage\n\n[usage]: #usage\n[run]: setup.md#install 'Run'\n"
+        );
+        assert_eq!(
+            extraction.anchors,
+            [
+                ("setup".to_owned(), "setup".to_owned()),
+                ("install".to_owned(), "install".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_anchors() {
+        let text = "# A\n\n## B\n\n## C\n\n## B\n\n[b](#b-1)\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let extracted = |heading: &Heading| {
+            extract(
+                text,
+                &analysis,
+                heading,
+                SlugStyle::default(),
+                "a.md",
+                "b.md",
+            )
+        };
+        // The second `## B` would become `#b`.
+        assert!(extracted(&analysis.headings[1]).is_none());
+        assert!(extracted(&analysis.headings[2]).is_some());
+        assert!(extracted(&analysis.headings[3]).is_some());
+    }
+
+    #[test]
+    fn test_file_name() {
+        let analysis = Analysis::new("## Setup\n", SlugStyle::default());
+        let heading = &analysis.headings[0];
+        assert_eq!(file_name(heading, |_| false), "setup.md");
+        assert_eq!(file_name(heading, |name| name == "setup.md"), "setup-1.md");
+    }
+}
@@ -1,27 +1,58 @@
+use std::{fmt::Write, iter::Peekable, ops::Range};
+
 use dissimilar::Chunk;
-use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, Tag};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, OffsetIter, Tag};
 use text_edit::{TextEdit, TextRange, TextSize};
 
-#[allow(clippy::too_many_lines)]
+#[allow(clippy::too_many_lines, clippy::string_slice)]
 pub(crate) fn fmt(text: &str) -> TextEdit {
     let mut output = String::new();
 
-    let mut parser = pulldown_cmark::Parser::new(text).peekable();
+    // Front matter isn't Markdown, so it's kept verbatim.
+    let front_matter_end =
+        crate::analysis::front_matter(text).map_or(0, |range| usize::from(range.end()));
+    let (front_matter, body) = text.split_at(front_matter_end);
+
+    let mut parser =
+        pulldown_cmark::Parser::new_ext(body, pulldown_cmark::Options::ENABLE_FOOTNOTES)
+            .into_offset_iter()
+            .peekable();
 
     let mut in_paragraph = false;
     let mut paragraph_text = String::new();
     let mut list_num = None;
 
     let mut nested_level = 0;
-    let mut is_item_start = false;
+    let mut is_footnote_start = false;
+    // The width of what precedes the paragraph on its first line.
+    let mut first_line_prefix = 0;
+    // Whether the previous event was a line of an HTML block.
+    let mut in_html_block = false;
 
-    while let Some(event) = parser.next() {
+    while let Some((event, range)) = parser.next() {
+        let is_html_line = matches!(event, Event::Html(_)) && !in_paragraph;
+        if in_html_block && !is_html_line {
+            in_html_block = false;
+        }
+        // Inline content of paragraphs is wrapped, that of headings isn't.
+        let inline = if in_paragraph {
+            &mut paragraph_text
+        } else {
+            &mut output
+        };
         match event {
             Event::Start(tag) => match tag {
                 Tag::Paragraph => {
                     assert!(!in_paragraph);
                     in_paragraph = true;
-                    output.push('\n');
+                    if is_footnote_start {
+                        // The first paragraph continues the `[^label]:` line.
+                        output.push(' ');
+                        first_line_prefix = output.len() - output.rfind('\n').map_or(0, |i| i + 1);
+                        is_footnote_start = false;
+                    } else {
+                        output.push('\n');
+                    }
                 }
                 Tag::Heading(level, _identifier, _classes) => {
                     assert!(!in_paragraph);
@@ -37,7 +68,6 @@ pub(crate) fn fmt(text: &str) -> TextEdit {
                     output.push_str(tag);
                     output.push(' ');
                 }
-                Tag::BlockQuote => todo!(),
                 Tag::CodeBlock(kind) => {
                     assert!(!in_paragraph);
                     let language_identifier = match kind {
@@ -49,55 +79,61 @@ pub(crate) fn fmt(text: &str) -> TextEdit {
                     output.push('\n');
                 }
                 Tag::List(starting_num) => {
-                    println!("entering list");
-                    tracing::error!("hello");
                     nested_level += 1;
                     list_num = starting_num;
                     output.push('\n');
                 }
                 Tag::Item => {
-                    match list_num {
-                        Some(num) => {
-                            println!("entering item");
-                            output.push_str(&format!("{num}. "));
-                            list_num = list_num.map(|num| num + 1);
-                        }
-                        None => {
-                            println!("entering item");
-                            output.push_str("- ");
-                        }
+                    if let Some(num) = list_num {
+                        write!(output, "{num}. ").unwrap();
+                        list_num = list_num.map(|num| num + 1);
+                    } else {
+                        output.push_str("- ");
                     }
-                    is_item_start = true;
                 }
-                Tag::FootnoteDefinition(_def) => todo!(),
-                Tag::Table(_table) => todo!(),
-                Tag::TableHead => todo!(),
-                Tag::TableRow => todo!(),
-                Tag::TableCell => todo!(),
-                Tag::Emphasis => {
-                    assert!(in_paragraph);
-                    paragraph_text.push('*');
+                Tag::FootnoteDefinition(label) => {
+                    assert!(!in_paragraph);
+                    // Continuation lines are indented by four spaces.
+                    nested_level += 2;
+                    write!(output, "\n[^{label}]:").unwrap();
+                    is_footnote_start = true;
                 }
-                Tag::Strong => {
-                    assert!(in_paragraph);
-                    paragraph_text.push_str("**");
+                Tag::Emphasis => inline.push('*'),
+                Tag::Strong => inline.push_str("**"),
+                // Blocks that aren't formatted yet are kept verbatim.
+                Tag::BlockQuote | Tag::Table(_) => {
+                    // A list item starts on the line of its marker.
+                    if !output.ends_with(' ') {
+                        output.push('\n');
+                    }
+                    output.push_str(body[range.clone()].trim_end());
+                    skip(&mut parser, &range);
+                    // The end of a list item ends its line.
+                    if !matches!(parser.peek(), Some((Event::End(Tag::Item), _))) {
+                        output.push('\n');
+                    }
                 }
-                Tag::Strikethrough => todo!(),
-                Tag::Link(_ty, _destination, _title) => todo!(),
-                Tag::Image(_ty, _destination, _title) => todo!(),
+                // So are inline elements, which the paragraph may still wrap
+                // at their spaces.
+                Tag::Link(..) | Tag::Image(..) | Tag::Strikethrough => {
+                    inline.push_str(&body[range.clone()]);
+                    skip(&mut parser, &range);
+                }
+                // Skipped with their table.
+                Tag::TableHead | Tag::TableRow | Tag::TableCell => {}
             },
             Event::End(tag) => {
                 match tag {
                     Tag::Paragraph => {
                         assert!(in_paragraph);
                         in_paragraph = false;
-
-                        let subsequent_indent = "  ".repeat(nested_level);
-                        let mut options = textwrap::Options::new(80);
-                        options.subsequent_indent = &subsequent_indent;
-                        let wrapped_text = textwrap::fill(&paragraph_text, options);
-
-                        output.push_str(&wrapped_text);
+                        fill(
+                            &mut output,
+                            &paragraph_text,
+                            first_line_prefix,
+                            nested_level,
+                        );
+                        first_line_prefix = 0;
                         output.push('\n');
                         // if parser.peek().is_some() {
                         //     output.push('\n');
@@ -109,7 +145,6 @@ pub(crate) fn fmt(text: &str) -> TextEdit {
                         assert!(!in_paragraph);
                         output.push('\n');
                     }
-                    Tag::BlockQuote => todo!(),
                     Tag::CodeBlock(_kind) => {
                         assert!(!in_paragraph);
                         output.push_str("```\n");
@@ -118,72 +153,112 @@ pub(crate) fn fmt(text: &str) -> TextEdit {
                         // TODO
                         list_num = None;
                         nested_level -= 1;
-                        println!("exiting list: {nested_level}");
                         if nested_level == 0 {
                             output.push('\n');
                         }
                     }
-                    Tag::Item => {
-                        println!("exiting item");
-                        match parser.peek() {
-                            Some(Event::End(Tag::List(_))) => {}
-                            _ => output.push('\n'),
-                        }
-                    }
-                    Tag::FootnoteDefinition(_def) => todo!(),
-                    Tag::Table(_table) => todo!(),
-                    Tag::TableHead => todo!(),
-                    Tag::TableRow => todo!(),
-                    Tag::TableCell => todo!(),
-                    Tag::Emphasis => {
-                        assert!(in_paragraph);
-                        paragraph_text.push('*');
-                    }
-                    Tag::Strong => {
-                        assert!(in_paragraph);
-                        paragraph_text.push_str("**");
+                    Tag::Item => match parser.peek() {
+                        Some((Event::End(Tag::List(_)), _)) => {}
+                        _ => output.push('\n'),
+                    },
+                    Tag::FootnoteDefinition(_label) => {
+                        nested_level -= 2;
+                        is_footnote_start = false;
                     }
-                    Tag::Strikethrough => todo!(),
-                    Tag::Link(_ty, _destination, _title) => todo!(),
-                    Tag::Image(_ty, _destination, _title) => todo!(),
-                };
-                is_item_start = false;
+                    Tag::Emphasis => inline.push('*'),
+                    Tag::Strong => inline.push_str("**"),
+                    // Skipped with their start tags.
+                    Tag::BlockQuote
+                    | Tag::Table(_)
+                    | Tag::TableHead
+                    | Tag::TableRow
+                    | Tag::TableCell
+                    | Tag::Link(..)
+                    | Tag::Image(..)
+                    | Tag::Strikethrough => {}
+                }
             }
-            Event::Text(text) => {
-                if in_paragraph {
-                    println!("pushing paragraph: {text:?}");
-                    paragraph_text.push_str(&text);
+            Event::Text(text) => inline.push_str(&text),
+            Event::Code(code) => write!(inline, "`{code}`").unwrap(),
+            Event::Html(html) => {
+                if is_html_line {
+                    // Each line of an HTML block is an event.
+                    if !in_html_block {
+                        output.push('\n');
+                        in_html_block = true;
+                    }
+                    output.push_str(&html);
                 } else {
-                    println!("pushing output: {text:?}");
-                    output.push_str(&text);
+                    paragraph_text.push_str(&html);
                 }
-                is_item_start = false;
-            }
-            Event::Code(code) => {
-                assert!(in_paragraph);
-                paragraph_text.push_str(&format!("`{code}`"));
-                is_item_start = false;
             }
-            Event::Html(_html) => todo!(),
-            Event::FootnoteReference(_fr) => todo!(),
+            Event::FootnoteReference(label) => write!(inline, "[^{label}]").unwrap(),
             Event::SoftBreak => {
                 if in_paragraph {
                     paragraph_text.push(' ');
                 }
             }
-            Event::HardBreak => todo!(),
-            Event::Rule => todo!(),
-            Event::TaskListMarker(_checked) => todo!(),
+            Event::HardBreak => {
+                // The lines around a hard break are wrapped separately.
+                fill(
+                    &mut output,
+                    &paragraph_text,
+                    first_line_prefix,
+                    nested_level,
+                );
+                paragraph_text = String::new();
+                let marker = if body[range].starts_with('\\') {
+                    "\\"
+                } else {
+                    "  "
+                };
+                let indent = "  ".repeat(nested_level);
+                write!(output, "{marker}\n{indent}").unwrap();
+                first_line_prefix = indent.len();
+            }
+            Event::Rule => write!(output, "\n{}\n", body[range].trim_end()).unwrap(),
+            Event::TaskListMarker(checked) => {
+                inline.push_str(if checked { "[x] " } else { "[ ] " });
+            }
         }
-        // println!("{}", output.trim_start());
     }
 
-    panic!("{}", output.trim_start());
+    // The blank line before the first block only separates it from the front
+    // matter.
+    let output = if front_matter.is_empty() {
+        output.trim_start().to_owned()
+    } else {
+        format!("{front_matter}{output}")
+    };
+    diff(text, &output)
+}
 
-    // diff(text, &output)
+/// Skips the events of an element up to its end, which has the same range as
+/// its start.
+fn skip(parser: &mut Peekable<OffsetIter<'_, '_>>, range: &Range<usize>) {
+    for (event, inner) in parser {
+        if matches!(event, Event::End(_)) && inner == *range {
+            break;
+        }
+    }
+}
+
+/// Wraps the text of a paragraph, of which `first_line_prefix` columns of the
+/// first line are already in the output.
+fn fill(output: &mut String, text: &str, first_line_prefix: usize, nested_level: usize) {
+    let subsequent_indent = "  ".repeat(nested_level);
+    let initial_indent = " ".repeat(first_line_prefix);
+    let mut options = textwrap::Options::new(80);
+    options.initial_indent = &initial_indent;
+    options.subsequent_indent = &subsequent_indent;
+    let wrapped_text = textwrap::fill(text, options);
+    output.push_str(
+        wrapped_text
+            .strip_prefix(initial_indent.as_str())
+            .unwrap_or(&wrapped_text),
+    );
 }
 
-#[allow(unused)]
 pub(crate) fn diff(left: &str, right: &str) -> TextEdit {
     let chunks = dissimilar::diff(left, right);
     let mut builder = TextEdit::builder();
@@ -237,4 +312,61 @@ by the seashore
 ";
         panic!("{:?}", fmt(md));
     }
+
+    fn formatted(text: &str) -> String {
+        let mut text = text.to_owned();
+        fmt(&text).apply(&mut text);
+        text
+    }
+
+    #[test]
+    fn test_front_matter_fmt() {
+        let yaml = "---\ntitle:   A\ntags: [ a,b ]\n---\n";
+        let text = format!("{yaml}# A\nsome\ntext\n");
+        assert_eq!(formatted(&text), format!("{yaml}\n# A\n\nsome text\n"));
+
+        let toml = "+++\ntitle =   'A'\n[extra]\n  b=1\n+++\n";
+        let text = format!("{toml}\n\nsome\ntext\n");
+        assert_eq!(formatted(&text), format!("{toml}\nsome text\n"));
+
+        let text = format!("{yaml}\n# A\n\nsome text\n");
+        assert!(fmt(&text).is_empty());
+    }
+
+    #[test]
+    fn test_verbatim_fmt() {
+        for text in [
+            "# A\n\nSee [x](y.md).\n",
+            "> quote\n",
+            "a <b>c</b>\n",
+            "text\n\n---\n\nmore\n",
+            "# The `fmt` *fn* [x](y)\n",
+            "<div>\nhi\n</div>\n\ntext\n",
+            "a  \nb\\\nc\n",
+            "- > q\n  > r\n- b\n",
+            "![img](a.png) <!-- c -->\n",
+        ] {
+            assert!(fmt(text).is_empty(), "{text:?}");
+        }
+        assert_eq!(
+            formatted("> a\n>   b\n\nsome\n[link](y.md)\n\n***\n"),
+            "> a\n>   b\n\nsome [link](y.md)\n\n***\n"
+        );
+    }
+
+    #[test]
+    fn test_footnote_fmt() {
+        let words = |count| vec!["word"; count].join(" ");
+        let text = format!(
+            "Text[^1] and\nmore[^note].\n\n[^1]:   First.\n\n[^note]: {}\nend.\n",
+            words(20)
+        );
+        let expected = format!(
+            "Text[^1] and more[^note].\n\n[^1]: First.\n\n[^note]: {}\n    {} end.\n",
+            words(14),
+            words(6),
+        );
+        assert_eq!(formatted(&text), expected);
+        assert!(fmt(&expected).is_empty());
+    }
 }
@@ -0,0 +1,208 @@
+//! YAML and TOML front matter.
+//!
+//! Only what front matter uses in practice is parsed: block mappings and
+//! sequences, flow collections, and plain, quoted and block scalars in YAML,
+//! and tables, arrays of tables, inline tables and arrays in TOML. YAML
+//! anchors, tags and multiple documents aren't supported.
+
+mod toml;
+mod yaml;
+
+use text_edit::{TextRange, TextSize};
+
+use crate::analysis;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Between `---` lines.
+    Yaml,
+    /// Between `+++` lines.
+    Toml,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Node {
+    pub(crate) range: TextRange,
+    pub(crate) value: Value,
+}
+
+impl Node {
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        match &self.value {
+            Value::Scalar(value) => value.clone(),
+            Value::Array(items) => items.iter().map(Node::to_json).collect(),
+            Value::Object(entries) => entries
+                .iter()
+                .map(|entry| (entry.key.clone(), entry.value.to_json()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    /// A string, number, boolean or null. Dates are strings.
+    Scalar(serde_json::Value),
+    Array(Vec<Node>),
+    Object(Vec<Entry>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Entry {
+    pub(crate) key: String,
+    pub(crate) key_range: TextRange,
+    pub(crate) value: Node,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Error {
+    pub(crate) range: TextRange,
+    pub(crate) message: String,
+}
+
+impl Error {
+    fn new(range: TextRange, message: impl Into<String>) -> Self {
+        Self {
+            range,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FrontMatter {
+    pub(crate) format: Format,
+    /// The range of the front matter, including the fences.
+    pub(crate) range: TextRange,
+    /// The top-level entries, up to the first error.
+    pub(crate) entries: Vec<Entry>,
+    pub(crate) error: Option<Error>,
+}
+
+/// Parses the front matter at the start of a document, if it has one.
+pub(crate) fn parse(text: &str) -> Option<FrontMatter> {
+    let range = analysis::front_matter(text)?;
+    let format = format(text);
+    let content = content(text, range);
+    let mut entries = Vec::new();
+    let result = match format {
+        Format::Yaml => yaml::parse(text, content, &mut entries),
+        Format::Toml => toml::parse(text, content, &mut entries),
+    };
+    Some(FrontMatter {
+        format,
+        range,
+        entries,
+        error: result.err(),
+    })
+}
+
+pub(crate) fn format(text: &str) -> Format {
+    if text.starts_with("+++") {
+        Format::Toml
+    } else {
+        Format::Yaml
+    }
+}
+
+/// Returns the range between the lines of the fences of the front matter.
+#[allow(clippy::string_slice)]
+pub(crate) fn content(text: &str, front_matter: TextRange) -> TextRange {
+    let fenced = text[front_matter].trim_end_matches(['\n', '\r']);
+    let start = fenced
+        .find('\n')
+        .map_or(fenced.len(), |newline| newline + 1);
+    let end = fenced.rfind('\n').map_or(start, |newline| newline + 1);
+    analysis::text_range(start..end.max(start))
+}
+
+/// Converts the offset of a byte in a string to a text size.
+fn offset(offset: usize) -> TextSize {
+    TextSize::try_from(offset).expect("offset fits")
+}
+
+/// Returns the type of a scalar for plain YAML scalars and bare TOML values,
+/// which are numbers, booleans or null, or else strings.
+fn number(text: &str) -> Option<serde_json::Value> {
+    let digits = text.replace('_', "");
+    if let Ok(int) = digits.parse::<i64>() {
+        return Some(int.into());
+    }
+    let is_float = digits
+        .trim_start_matches(['+', '-'])
+        .starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && digits.contains(|c: char| c.is_ascii_digit());
+    digits
+        .parse::<f64>()
+        .ok()
+        .filter(|_| is_float)
+        .and_then(serde_json::Number::from_f64)
+        .map(serde_json::Value::Number)
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+
+    /// Formats the entries as `key: value` lines, with the source text of the
+    /// values.
+    pub(super) fn outline(text: &str, entries: &[Entry], depth: usize) -> String {
+        let mut outline = String::new();
+        for entry in entries {
+            let indent = "  ".repeat(depth);
+            let key = &text[entry.key_range];
+            match &entry.value.value {
+                Value::Object(children) => {
+                    writeln!(outline, "{indent}{key}:").unwrap();
+                    outline.push_str(&self::outline(text, children, depth + 1));
+                }
+                Value::Array(items) => {
+                    let items = items
+                        .iter()
+                        .map(|item| match &item.value {
+                            Value::Scalar(value) => value.to_string(),
+                            Value::Array(_) => "[..]".to_owned(),
+                            Value::Object(entries) => format!("{{{}}}", entries.len()),
+                        })
+                        .collect::<Vec<_>>();
+                    writeln!(outline, "{indent}{key}: [{}]", items.join(", ")).unwrap();
+                }
+                Value::Scalar(value) => writeln!(outline, "{indent}{key}: {value}").unwrap(),
+            }
+        }
+        outline
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = "---\ntitle: A\n---\n\n# A\n";
+        let front_matter = parse(text).unwrap();
+        assert_eq!(front_matter.format, Format::Yaml);
+        assert_eq!(front_matter.range, TextRange::new(0.into(), 17.into()));
+        assert_eq!(outline(text, &front_matter.entries, 0), "title: \"A\"\n");
+
+        let text = "+++\ntitle = 'A'\n+++\n";
+        let front_matter = parse(text).unwrap();
+        assert_eq!(front_matter.format, Format::Toml);
+        assert_eq!(outline(text, &front_matter.entries, 0), "title: \"A\"\n");
+
+        let text = "---\na: [1, {b: true}]\n---\n";
+        let node = &parse(text).unwrap().entries[0].value;
+        assert_eq!(node.to_json(), serde_json::json!([1, { "b": true }]));
+
+        assert_eq!(parse("---\n---\n").unwrap().entries, []);
+        assert!(parse("# A\n---\n").is_none());
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(number("1_000"), Some(1000.into()));
+        assert_eq!(number("-1.5e3"), Some((-1500.0).into()));
+        assert_eq!(number("1.2.3"), None);
+        assert_eq!(number("inf"), None);
+        assert_eq!(number("e5"), None);
+    }
+}
@@ -0,0 +1,575 @@
+//! The subset of TOML used in front matter.
+
+use serde_json::Value as Json;
+use text_edit::TextRange;
+
+use super::{number, offset, Entry, Error, Node, Value};
+
+/// A dotted key, as its parts and their ranges.
+type Key = Vec<(String, TextRange)>;
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    /// The end of the front matter content.
+    end: usize,
+}
+
+/// Parses the top-level table, pushing its entries as they're parsed.
+pub(super) fn parse(text: &str, content: TextRange, entries: &mut Vec<Entry>) -> Result<(), Error> {
+    let mut parser = Parser {
+        text,
+        pos: usize::from(content.start()),
+        end: usize::from(content.end()),
+    };
+    // The table that key/value pairs go into.
+    let mut table = Key::new();
+    loop {
+        parser.skip_blank();
+        let start = parser.pos;
+        match parser.peek() {
+            None => return Ok(()),
+            Some('[') => {
+                let is_array = parser.eat("[[");
+                if !is_array {
+                    parser.pos += 1;
+                }
+                let key = parser.key()?;
+                let close = if is_array { "]]" } else { "]" };
+                parser.skip_space();
+                if !parser.eat(close) {
+                    return Err(parser.expected(&format!("`{close}`")));
+                }
+                let header = range(start, parser.pos);
+                parser.line_end()?;
+                if is_array {
+                    let (last, parent) = key.split_last().expect("keys aren't empty");
+                    let entries = table_entries(entries, parent, header)?;
+                    array_of_tables(entries, last, header)?;
+                } else {
+                    table_entries(entries, &key, header)?;
+                }
+                table = key;
+            }
+            Some(_) => {
+                let key = parser.key()?;
+                let value = parser.key_value()?;
+                parser.line_end()?;
+                let (last, parent) = key.split_last().expect("keys aren't empty");
+                let path = table.iter().chain(parent).cloned().collect::<Vec<_>>();
+                let cover = last.1.cover(value.range);
+                insert(table_entries(entries, &path, cover)?, last, value)?;
+            }
+        }
+    }
+}
+
+/// Returns the entries of a table, creating the tables on the way to it, and
+/// extends their ranges to cover a range.
+///
+/// Going through an array of tables goes to its last table.
+fn table_entries<'a>(
+    mut entries: &'a mut Vec<Entry>,
+    key: &[(String, TextRange)],
+    cover: TextRange,
+) -> Result<&'a mut Vec<Entry>, Error> {
+    for (part, part_range) in key {
+        let index = if let Some(index) = entries.iter().position(|entry| entry.key == *part) {
+            index
+        } else {
+            entries.push(Entry {
+                key: part.clone(),
+                key_range: *part_range,
+                value: Node {
+                    range: *part_range,
+                    value: Value::Object(Vec::new()),
+                },
+            });
+            entries.len() - 1
+        };
+        let node = &mut entries[index].value;
+        node.range = node.range.cover(cover);
+        entries = match &mut node.value {
+            Value::Object(children) => children,
+            Value::Array(items) => match items.last_mut() {
+                Some(Node {
+                    range,
+                    value: Value::Object(children),
+                }) => {
+                    *range = range.cover(cover);
+                    children
+                }
+                _ => return Err(already_defined(part, *part_range)),
+            },
+            Value::Scalar(_) => return Err(already_defined(part, *part_range)),
+        };
+    }
+    Ok(entries)
+}
+
+/// Adds a table to the array of tables in a table.
+fn array_of_tables(
+    entries: &mut Vec<Entry>,
+    (key, key_range): &(String, TextRange),
+    header: TextRange,
+) -> Result<(), Error> {
+    let table = Node {
+        range: header,
+        value: Value::Object(Vec::new()),
+    };
+    match entries.iter_mut().find(|entry| entry.key == *key) {
+        Some(Entry {
+            value:
+                Node {
+                    range,
+                    value: Value::Array(items),
+                },
+            ..
+        }) => {
+            *range = range.cover(header);
+            items.push(table);
+        }
+        Some(_) => return Err(already_defined(key, *key_range)),
+        None => entries.push(Entry {
+            key: key.clone(),
+            key_range: *key_range,
+            value: Node {
+                range: header,
+                value: Value::Array(vec![table]),
+            },
+        }),
+    }
+    Ok(())
+}
+
+fn insert(
+    entries: &mut Vec<Entry>,
+    (key, key_range): &(String, TextRange),
+    value: Node,
+) -> Result<(), Error> {
+    if entries.iter().any(|entry| entry.key == *key) {
+        return Err(Error::new(*key_range, format!("duplicate key `{key}`")));
+    }
+    entries.push(Entry {
+        key: key.clone(),
+        key_range: *key_range,
+        value,
+    });
+    Ok(())
+}
+
+fn already_defined(key: &str, range: TextRange) -> Error {
+    Error::new(range, format!("`{key}` is already defined as a value"))
+}
+
+/// Returns the type of a bare value, which isn't a string, array or table.
+#[allow(clippy::string_slice)]
+fn bare(value: &str) -> Option<Json> {
+    let bytes = value.as_bytes();
+    let is_date = bytes.len() >= 5 && bytes[..4].iter().all(u8::is_ascii_digit) && bytes[4] == b'-';
+    let is_time = bytes.len() >= 3 && bytes[..2].iter().all(u8::is_ascii_digit) && bytes[2] == b':';
+    if is_date || is_time {
+        return Some(value.into());
+    }
+    let radix = match value.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+    match value {
+        "true" => Some(true.into()),
+        "false" => Some(false.into()),
+        "inf" | "+inf" | "-inf" | "nan" | "+nan" | "-nan" => Some(value.into()),
+        _ if radix != 10 => i64::from_str_radix(&value[2..].replace('_', ""), radix)
+            .ok()
+            .map(Json::from),
+        _ => number(value),
+    }
+}
+
+fn range(start: usize, end: usize) -> TextRange {
+    TextRange::new(offset(start), offset(end))
+}
+
+#[allow(clippy::string_slice)]
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..self.end]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    /// Returns an error at the current position, up to the end of the line.
+    fn expected(&self, what: &str) -> Error {
+        let rest = self.rest();
+        let len = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        Error::new(range(self.pos, self.pos + len), format!("expected {what}"))
+    }
+
+    /// Skips spaces and tabs.
+    fn skip_space(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    /// Skips whitespace, line breaks and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                return;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    /// Expects the end of a line, after an optional comment.
+    fn line_end(&mut self) -> Result<(), Error> {
+        self.skip_space();
+        if self.peek() == Some('#') {
+            let rest = self.rest();
+            self.pos += rest.find('\n').unwrap_or(rest.len());
+        }
+        match self.peek() {
+            None | Some('\n' | '\r') => Ok(()),
+            Some(_) => Err(self.expected("a line break after the value")),
+        }
+    }
+
+    /// Parses a dotted key.
+    fn key(&mut self) -> Result<Key, Error> {
+        let mut key = Key::new();
+        loop {
+            self.skip_space();
+            let start = self.pos;
+            let part = if let Some('"' | '\'') = self.peek() {
+                self.string()?
+            } else {
+                let rest = self.rest();
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(self.expected("a key"));
+                }
+                self.pos += len;
+                rest[..len].to_owned()
+            };
+            key.push((part, range(start, self.pos)));
+            self.skip_space();
+            if !self.eat(".") {
+                return Ok(key);
+            }
+        }
+    }
+
+    /// Parses the `= value` after a key.
+    fn key_value(&mut self) -> Result<Node, Error> {
+        self.skip_space();
+        if !self.eat("=") {
+            return Err(self.expected("`=` after the key"));
+        }
+        self.skip_space();
+        self.value()
+    }
+
+    fn value(&mut self) -> Result<Node, Error> {
+        let start = self.pos;
+        let value = match self.peek() {
+            Some('"' | '\'') => Value::Scalar(self.string()?.into()),
+            Some('[') => self.array()?,
+            Some('{') => self.inline_table()?,
+            _ => {
+                let rest = self.rest();
+                let mut len = rest
+                    .find(|c: char| c.is_whitespace() || matches!(c, ',' | ']' | '}' | '#'))
+                    .unwrap_or(rest.len());
+                // A date may be followed by a time after a space.
+                let time = rest[len..].strip_prefix(' ').filter(|time| {
+                    rest.as_bytes().get(4) == Some(&b'-') && time.as_bytes().get(2) == Some(&b':')
+                });
+                if let Some(time) = time {
+                    len += 1 + time.find(char::is_whitespace).unwrap_or(time.len());
+                }
+                let value = &rest[..len];
+                if value.is_empty() {
+                    return Err(self.expected("a value"));
+                }
+                let Some(value) = bare(value) else {
+                    return Err(Error::new(
+                        range(start, start + len),
+                        format!("invalid value `{value}`, strings should be quoted"),
+                    ));
+                };
+                self.pos += len;
+                Value::Scalar(value)
+            }
+        };
+        Ok(Node {
+            range: range(start, self.pos),
+            value,
+        })
+    }
+
+    fn array(&mut self) -> Result<Value, Error> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(Value::Array(items));
+            }
+            if self.peek().is_none() {
+                return Err(Error::new(
+                    range(start, start + 1),
+                    "unterminated array, expected `]`",
+                ));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            if !self.eat(",") && self.peek().is_some_and(|c| c != ']') {
+                return Err(self.expected("`,` or `]`"));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut entries = Vec::new();
+        self.skip_space();
+        if self.eat("}") {
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            let key = self.key()?;
+            let value = self.key_value()?;
+            let (last, parent) = key.split_last().expect("keys aren't empty");
+            let cover = last.1.cover(value.range);
+            insert(table_entries(&mut entries, parent, cover)?, last, value)?;
+            self.skip_space();
+            if self.eat("}") {
+                return Ok(Value::Object(entries));
+            }
+            if !self.eat(",") {
+                return Err(self.expected("`,` or `}`"));
+            }
+        }
+    }
+
+    /// Parses a basic or literal string, which may be multi-line.
+    fn string(&mut self) -> Result<String, Error> {
+        let start = self.pos;
+        let quote = if self.peek() == Some('"') { '"' } else { '\'' };
+        let delimiter = if quote == '"' { "\"\"\"" } else { "'''" };
+        let multiline = self.eat(delimiter);
+        if multiline {
+            // A line break right after the delimiter isn't part of the string.
+            let _ = self.eat("\n") || self.eat("\r\n");
+        } else {
+            self.pos += 1;
+        }
+
+        let mut value = String::new();
+        loop {
+            let rest = self.rest();
+            let Some(c) = rest.chars().next() else { break };
+            if multiline && rest.starts_with(delimiter) {
+                // Up to two quotes can come right before the delimiter.
+                let quotes = rest[3..]
+                    .chars()
+                    .take_while(|&c| c == quote)
+                    .take(2)
+                    .count();
+                value.extend(std::iter::repeat_n(quote, quotes));
+                self.pos += 3 + quotes;
+                return Ok(value);
+            }
+            if !multiline && c == quote {
+                self.pos += 1;
+                return Ok(value);
+            }
+            if !multiline && c == '\n' {
+                break;
+            }
+            if c == '\\' && quote == '"' {
+                self.escape(&mut value, multiline)?;
+                continue;
+            }
+            value.push(c);
+            self.pos += c.len_utf8();
+        }
+        let len = self.text[start..self.end]
+            .find('\n')
+            .unwrap_or(self.end - start);
+        Err(Error::new(
+            range(start, start + len.max(1)),
+            "unterminated string",
+        ))
+    }
+
+    /// Parses an escape sequence in a basic string.
+    fn escape(&mut self, value: &mut String, multiline: bool) -> Result<(), Error> {
+        let start = self.pos;
+        let rest = &self.rest()[1..];
+        // A backslash at the end of a line trims the whitespace after it.
+        if multiline
+            && rest
+                .trim_start_matches([' ', '\t'])
+                .starts_with(['\n', '\r'])
+        {
+            self.pos += 1 + rest.len() - rest.trim_start().len();
+            return Ok(());
+        }
+        let (c, len) = match rest.chars().next() {
+            Some('b') => ('\u{8}', 1),
+            Some('t') => ('\t', 1),
+            Some('n') => ('\n', 1),
+            Some('f') => ('\u{c}', 1),
+            Some('r') => ('\r', 1),
+            Some(c @ ('"' | '\\')) => (c, 1),
+            Some(kind @ ('u' | 'U')) => {
+                let len = if kind == 'u' { 4 } else { 8 };
+                let c = rest
+                    .get(1..=len)
+                    .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32);
+                match c {
+                    Some(c) => (c, 1 + len),
+                    None => ('\0', 0),
+                }
+            }
+            _ => ('\0', 0),
+        };
+        if len == 0 {
+            return Err(Error::new(
+                range(start, start + 2),
+                "invalid escape sequence",
+            ));
+        }
+        value.push(c);
+        self.pos += 1 + len;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::front_matter::tests::outline;
+
+    fn parse(toml: &str) -> Result<String, String> {
+        let text = format!("+++\n{toml}+++\n");
+        let content = range(4, 4 + toml.len());
+        let mut entries = Vec::new();
+        match super::parse(&text, content, &mut entries) {
+            Ok(()) => Ok(outline(&text, &entries, 0)),
+            Err(error) => Err(error.message),
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(
+            parse(
+                "title = \"Hello \\\"world\\\" \\u00e9\" # comment\n\
+                 literal = 'C:\\path'\n\
+                 draft = false\n\
+                 weight = 1_000\n\
+                 hex = 0xff\n\
+                 ratio = 0.5\n\
+                 date = 1979-05-27 07:32:00Z\n\
+                 multiline = \"\"\"\n\
+                 a \\\n  b\"\"\"\n\
+                 tags = [\n  'a', # first\n  'b',\n]\n\
+                 author = { name = 'A', links.site = 'x' }\n"
+            ),
+            Ok("title: \"Hello \\\"world\\\" é\"\n\
+                literal: \"C:\\\\path\"\n\
+                draft: false\n\
+                weight: 1000\n\
+                hex: 255\n\
+                ratio: 0.5\n\
+                date: \"1979-05-27 07:32:00Z\"\n\
+                multiline: \"a b\"\n\
+                tags: [\"a\", \"b\"]\n\
+                author:\n  \
+                  name: \"A\"\n  \
+                  links:\n    \
+                    site: \"x\"\n"
+                .to_owned())
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(
+            parse(
+                "title = 'A'\n\
+                 site.name = 'S'\n\
+                 [params]\n\
+                 color = 'red'\n\
+                 [params.\"nested key\"]\n\
+                 a = 1\n\
+                 [[menu]]\n\
+                 name = 'one'\n\
+                 [[menu]]\n\
+                 name = 'two'\n"
+            ),
+            Ok("title: \"A\"\n\
+                site:\n  \
+                  name: \"S\"\n\
+                params:\n  \
+                  color: \"red\"\n  \
+                  \"nested key\":\n    \
+                    a: 1\n\
+                menu: [{1}, {1}]\n"
+                .to_owned())
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse("a = 1\na = 2\n"), Err("duplicate key `a`".to_owned()));
+        assert_eq!(
+            parse("a = 1\n[a]\n"),
+            Err("`a` is already defined as a value".to_owned())
+        );
+        assert_eq!(
+            parse("title = Hello\n"),
+            Err("invalid value `Hello`, strings should be quoted".to_owned())
+        );
+        assert_eq!(
+            parse("title: 'A'\n"),
+            Err("expected `=` after the key".to_owned())
+        );
+        assert_eq!(parse("a = 'b\n"), Err("unterminated string".to_owned()));
+        assert_eq!(
+            parse("a = [1, 2\n"),
+            Err("unterminated array, expected `]`".to_owned())
+        );
+        assert_eq!(
+            parse("a = 1 2\n"),
+            Err("expected a line break after the value".to_owned())
+        );
+        assert_eq!(parse("[a\n"), Err("expected `]`".to_owned()));
+        assert_eq!(
+            parse("a = \"\\q\"\n"),
+            Err("invalid escape sequence".to_owned())
+        );
+    }
+}
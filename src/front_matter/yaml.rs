@@ -0,0 +1,716 @@
+//! The subset of YAML used in front matter.
+
+use serde_json::Value as Json;
+use text_edit::TextRange;
+
+use super::{number, offset, Entry, Error, Node, Value};
+
+/// A line that isn't blank or a comment.
+#[derive(Clone, Copy)]
+struct Line {
+    /// The offset after the indentation.
+    start: usize,
+    /// The offset of the line break.
+    end: usize,
+    indent: usize,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    /// The end of the front matter content.
+    end: usize,
+    lines: Vec<Line>,
+    /// The next line to parse.
+    index: usize,
+}
+
+/// Parses the top-level mapping, pushing its entries as they're parsed.
+pub(super) fn parse(text: &str, content: TextRange, entries: &mut Vec<Entry>) -> Result<(), Error> {
+    let mut parser = Parser::new(text, content)?;
+    let Some(first) = parser.lines.first().copied() else {
+        return Ok(());
+    };
+    if is_item(parser.line(first)) || mapping_key(parser.line(first)).is_none() {
+        return Err(Error::new(
+            range(first.start, first.end),
+            "front matter should be a mapping of keys to values",
+        ));
+    }
+    parser.mapping(first.indent, entries)?;
+    match parser.lines.get(parser.index) {
+        Some(&line) => Err(unexpected_indentation(line)),
+        None => Ok(()),
+    }
+}
+
+/// Returns whether a line is a sequence item.
+fn is_item(line: &str) -> bool {
+    line == "-" || line.starts_with("- ")
+}
+
+/// Returns the offset of the colon after the key, if the line is an entry of
+/// a mapping.
+#[allow(clippy::string_slice)]
+fn mapping_key(line: &str) -> Option<usize> {
+    let key_end = match line.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let mut escaped = false;
+            let close = line[1..].find(|c: char| {
+                let found = c == quote && !escaped;
+                escaped = quote == '"' && c == '\\' && !escaped;
+                found
+            })?;
+            close + 2
+        }
+        '[' | '{' | '#' => return None,
+        _ => 0,
+    };
+    let colon = line[key_end..]
+        .match_indices(':')
+        .map(|(colon, _)| key_end + colon)
+        .find(|&colon| {
+            line[colon + 1..].is_empty() || line[colon + 1..].starts_with([' ', '\t'])
+        })?;
+    // A comment before the colon means the line has no key.
+    let is_key = if key_end > 0 {
+        line[key_end..colon].trim().is_empty()
+    } else {
+        !line[..colon].contains(" #")
+    };
+    is_key.then_some(colon)
+}
+
+/// Returns the type of a plain scalar.
+fn plain(text: &str) -> Json {
+    match text {
+        "~" | "null" | "Null" | "NULL" => Json::Null,
+        "true" | "True" | "TRUE" => Json::Bool(true),
+        "false" | "False" | "FALSE" => Json::Bool(false),
+        _ => number(text).unwrap_or_else(|| text.into()),
+    }
+}
+
+/// Returns the length of a plain scalar on a line, up to a comment.
+fn without_comment(line: &str) -> usize {
+    line.find(" #")
+        .or_else(|| line.find("\t#"))
+        .unwrap_or(line.len())
+}
+
+#[allow(clippy::string_slice)]
+impl<'a> Parser<'a> {
+    fn new(text: &'a str, content: TextRange) -> Result<Self, Error> {
+        let mut lines = Vec::new();
+        let mut start = usize::from(content.start());
+        for line in text[content].split_inclusive('\n') {
+            let end = start + line.trim_end_matches(['\n', '\r']).len();
+            let indented = line.trim_start_matches(' ');
+            let indent = line.len() - indented.len();
+            if indented.starts_with('\t') && !indented.trim().is_empty() {
+                let tab = start + indent;
+                return Err(Error::new(
+                    range(tab, tab + 1),
+                    "tabs can't be used for indentation",
+                ));
+            }
+            let trimmed = indented.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                lines.push(Line {
+                    start: start + indent,
+                    end,
+                    indent,
+                });
+            }
+            start += line.len();
+        }
+        Ok(Self {
+            text,
+            end: usize::from(content.end()),
+            lines,
+            index: 0,
+        })
+    }
+
+    fn line(&self, line: Line) -> &'a str {
+        &self.text[line.start..line.end]
+    }
+
+    /// Returns the end of the last line parsed.
+    fn parsed_end(&self) -> usize {
+        self.lines[self.index - 1].end
+    }
+
+    /// Parses a mapping, sequence or scalar that starts on the next line.
+    fn block(&mut self, indent: usize) -> Result<Node, Error> {
+        let line = self.lines[self.index];
+        if is_item(self.line(line)) {
+            return self.sequence(indent);
+        }
+        if mapping_key(self.line(line)).is_none() {
+            return self.inline(line.start, indent);
+        }
+        let mut entries = Vec::new();
+        self.mapping(indent, &mut entries)?;
+        Ok(Node {
+            range: range(line.start, self.parsed_end()),
+            value: Value::Object(entries),
+        })
+    }
+
+    fn mapping(&mut self, indent: usize, entries: &mut Vec<Entry>) -> Result<(), Error> {
+        while let Some(line) = self.lines.get(self.index).copied() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return Err(unexpected_indentation(line));
+            }
+            let text = self.line(line);
+            let Some(colon) = mapping_key(text).filter(|_| !is_item(text)) else {
+                return Err(Error::new(
+                    range(line.start, line.end),
+                    "expected `key: value`",
+                ));
+            };
+            let key_range = range(line.start, line.start + text[..colon].trim_end().len());
+            let key = if text.starts_with(['"', '\'']) {
+                self.quoted(line.start)?.0
+            } else {
+                text[..colon].trim_end().to_owned()
+            };
+            if key.is_empty() {
+                return Err(Error::new(key_range, "expected a key before `:`"));
+            }
+            if entries.iter().any(|entry| entry.key == key) {
+                return Err(Error::new(key_range, format!("duplicate key `{key}`")));
+            }
+            let value = self.value(line, line.start + colon + 1, indent)?;
+            entries.push(Entry {
+                key,
+                key_range,
+                value,
+            });
+        }
+        Ok(())
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Node, Error> {
+        let start = self.lines[self.index].start;
+        let mut items = Vec::new();
+        while let Some(line) = self.lines.get(self.index).copied() {
+            let text = self.line(line);
+            if line.indent != indent || !is_item(text) {
+                if line.indent > indent {
+                    return Err(unexpected_indentation(line));
+                }
+                break;
+            }
+            let item = text[1..].trim_start();
+            if item.is_empty() || item.starts_with('#') {
+                items.push(self.value(line, line.start + 1, indent)?);
+                continue;
+            }
+            // Parse the item as if it started on a line of its own, which
+            // makes the keys of a mapping line up with the next lines.
+            let item_indent = indent + text.len() - item.len();
+            self.lines[self.index] = Line {
+                start: line.end - item.len(),
+                end: line.end,
+                indent: item_indent,
+            };
+            items.push(self.block(item_indent)?);
+        }
+        Ok(Node {
+            range: range(start, self.parsed_end()),
+            value: Value::Array(items),
+        })
+    }
+
+    /// Parses the value after a key or a sequence item marker, which may be on
+    /// the next lines.
+    fn value(&mut self, line: Line, start: usize, indent: usize) -> Result<Node, Error> {
+        let rest = &self.text[start..line.end];
+        let start = line.end - rest.trim_start().len();
+        let rest = rest.trim();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            if rest.starts_with(['|', '>']) {
+                return self.block_scalar(start, indent);
+            }
+            return self.inline(start, indent);
+        }
+        self.index += 1;
+        match self.lines.get(self.index) {
+            Some(next) if next.indent > indent => self.block(next.indent),
+            Some(next) if next.indent == indent && is_item(self.line(*next)) => {
+                self.sequence(indent)
+            }
+            _ => Ok(Node {
+                range: range(start, start),
+                value: Value::Scalar(Json::Null),
+            }),
+        }
+    }
+
+    /// Parses a scalar or flow collection that starts on the current line.
+    fn inline(&mut self, start: usize, indent: usize) -> Result<Node, Error> {
+        let rest = &self.text[start..self.lines[self.index].end];
+        if rest.starts_with(['&', '*', '!']) {
+            return Err(Error::new(
+                range(start, start + 1),
+                "anchors, aliases and tags aren't supported",
+            ));
+        }
+        if rest.starts_with(['@', '`']) {
+            return Err(Error::new(
+                range(start, start + 1),
+                format!("a plain value can't start with `{}`", &rest[..1]),
+            ));
+        }
+        if !rest.starts_with(['"', '\'', '[', '{']) {
+            return self.plain(start, indent);
+        }
+
+        let (node, end) = self.flow(start)?;
+        while self
+            .lines
+            .get(self.index)
+            .is_some_and(|line| line.start < end)
+        {
+            self.index += 1;
+        }
+        let line_end = self.lines[self.index - 1].end;
+        let after = self.text[end..line_end].trim();
+        if !after.is_empty() && !after.starts_with('#') {
+            let after_start = line_end - self.text[end..line_end].trim_start().len();
+            return Err(Error::new(
+                range(after_start, line_end),
+                "expected a line break after the value",
+            ));
+        }
+        Ok(node)
+    }
+
+    /// Parses a plain scalar, which continues on more indented lines.
+    fn plain(&mut self, start: usize, indent: usize) -> Result<Node, Error> {
+        let line = self.lines[self.index];
+        let first = &self.text[start..line.end];
+        let first = first[..without_comment(first)].trim_end();
+        if let Some(colon) = first.find(": ") {
+            return Err(Error::new(
+                range(start + colon, start + colon + 1),
+                "`: ` isn't allowed in a plain value, which should be quoted",
+            ));
+        }
+        let mut value = first.to_owned();
+        let mut end = start + first.len();
+        self.index += 1;
+        while let Some(next) = self.lines.get(self.index).copied() {
+            if next.indent <= indent {
+                break;
+            }
+            let text = self.line(next);
+            if mapping_key(text).is_some() {
+                return Err(unexpected_indentation(next));
+            }
+            let text = text[..without_comment(text)].trim_end();
+            value.push(' ');
+            value.push_str(text);
+            end = next.start + text.len();
+            self.index += 1;
+        }
+        Ok(Node {
+            range: range(start, end),
+            value: Value::Scalar(plain(&value)),
+        })
+    }
+
+    /// Parses a `|` literal or `>` folded block scalar.
+    fn block_scalar(&mut self, start: usize, indent: usize) -> Result<Node, Error> {
+        let line = self.lines[self.index];
+        let header = &self.text[start..line.end];
+        let header = header[..without_comment(header)].trim_end();
+        let indicators = &header[1..];
+        let chomping = indicators.chars().find(|&c| c == '+' || c == '-');
+        let explicit_indent = indicators.chars().find_map(|c| c.to_digit(10));
+        if indicators.len() > 2
+            || !indicators
+                .chars()
+                .all(|c| matches!(c, '+' | '-' | '1'..='9'))
+        {
+            return Err(Error::new(
+                range(start, start + header.len()),
+                "invalid block scalar header",
+            ));
+        }
+
+        self.index += 1;
+        let first = self.index;
+        while self
+            .lines
+            .get(self.index)
+            .is_some_and(|next| next.indent > indent)
+        {
+            self.index += 1;
+        }
+        if first == self.index {
+            return Ok(Node {
+                range: range(start, start + header.len()),
+                value: Value::Scalar("".into()),
+            });
+        }
+
+        let first = self.lines[first];
+        let block_indent = explicit_indent.map_or(first.indent, |digit| indent + digit as usize);
+        let lines = self.text[first.start - first.indent..self.parsed_end()]
+            .lines()
+            .map(|line| {
+                line.get(block_indent..)
+                    .unwrap_or_else(|| line.trim_start())
+            })
+            .collect::<Vec<_>>();
+        let literal = header.starts_with('|');
+        let mut value = String::new();
+        for (index, line) in lines.iter().enumerate() {
+            // Folded scalars join lines with spaces, and blank lines with
+            // line breaks.
+            if index > 0 && (literal || line.is_empty()) {
+                value.push('\n');
+            } else if index > 0 && !lines[index - 1].is_empty() {
+                value.push(' ');
+            }
+            value.push_str(line);
+        }
+        if chomping != Some('-') {
+            value.push('\n');
+        }
+        Ok(Node {
+            range: range(start, self.parsed_end()),
+            value: Value::Scalar(value.into()),
+        })
+    }
+
+    /// Parses a quoted scalar or flow collection, which may span lines.
+    /// Returns the node and the offset after it.
+    fn flow(&self, start: usize) -> Result<(Node, usize), Error> {
+        let start = self.skip_space(start);
+        let Some(open) = self.text[start..self.end].chars().next() else {
+            return Err(Error::new(range(start, start), "expected a value"));
+        };
+        if matches!(open, '"' | '\'') {
+            let (value, end) = self.quoted(start)?;
+            let node = Node {
+                range: range(start, end),
+                value: Value::Scalar(value.into()),
+            };
+            return Ok((node, end));
+        }
+        if !matches!(open, '[' | '{') {
+            return self.flow_plain(start);
+        }
+
+        let close = if open == '[' { ']' } else { '}' };
+        let mut items = Vec::new();
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut pos = start + 1;
+        loop {
+            pos = self.skip_space(pos);
+            let next = self.text[pos..self.end].chars().next();
+            if next == Some(close) {
+                break;
+            }
+            if next.is_none() {
+                let kind = if open == '[' { "sequence" } else { "mapping" };
+                return Err(Error::new(
+                    range(start, start + 1),
+                    format!("unterminated flow {kind}, expected `{close}`"),
+                ));
+            }
+            if open == '[' {
+                let (item, end) = self.flow(pos)?;
+                items.push(item);
+                pos = end;
+            } else {
+                let (key, end) = self.flow(pos)?;
+                let key_range = key.range;
+                let key = match key.value {
+                    Value::Scalar(Json::String(key)) => key,
+                    Value::Scalar(key) => key.to_string(),
+                    Value::Array(_) | Value::Object(_) => {
+                        return Err(Error::new(key_range, "keys should be scalars"));
+                    }
+                };
+                pos = self.skip_space(end);
+                if !self.text[pos..self.end].starts_with(':') {
+                    return Err(Error::new(range(pos, pos), "expected `:` after the key"));
+                }
+                if entries.iter().any(|entry| entry.key == key) {
+                    return Err(Error::new(key_range, format!("duplicate key `{key}`")));
+                }
+                let (value, end) = self.flow(pos + 1)?;
+                entries.push(Entry {
+                    key,
+                    key_range,
+                    value,
+                });
+                pos = end;
+            }
+            pos = self.skip_space(pos);
+            match self.text[pos..self.end].chars().next() {
+                Some(',') => pos += 1,
+                Some(next) if next == close => break,
+                // Reported as unterminated.
+                None => {}
+                Some(_) => {
+                    return Err(Error::new(
+                        range(pos, pos),
+                        format!("expected `,` or `{close}`"),
+                    ));
+                }
+            }
+        }
+        let value = if open == '[' {
+            Value::Array(items)
+        } else {
+            Value::Object(entries)
+        };
+        let node = Node {
+            range: range(start, pos + 1),
+            value,
+        };
+        Ok((node, pos + 1))
+    }
+
+    /// Parses a plain scalar inside a flow collection.
+    fn flow_plain(&self, start: usize) -> Result<(Node, usize), Error> {
+        let rest = &self.text[start..self.end];
+        let mut len = rest
+            .find([',', '[', ']', '{', '}', '\n', '\r'])
+            .unwrap_or(rest.len());
+        len = len.min(without_comment(rest));
+        // A colon followed by a space ends a key.
+        if let Some(colon) = rest[..len].find(": ") {
+            len = colon;
+        }
+        if rest[..len].ends_with(':') {
+            len -= 1;
+        }
+        let value = rest[..len].trim_end();
+        if value.is_empty() {
+            return Err(Error::new(range(start, start), "expected a value"));
+        }
+        let node = Node {
+            range: range(start, start + value.len()),
+            value: Value::Scalar(plain(value)),
+        };
+        Ok((node, start + value.len()))
+    }
+
+    /// Skips whitespace, line breaks and comments.
+    fn skip_space(&self, mut pos: usize) -> usize {
+        loop {
+            let rest = &self.text[pos..self.end];
+            let trimmed = rest.trim_start();
+            pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                return pos;
+            }
+            pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    /// Parses a single or double quoted scalar. Returns its value and the
+    /// offset after the closing quote.
+    fn quoted(&self, start: usize) -> Result<(String, usize), Error> {
+        let rest = &self.text[start..self.end];
+        let quote = rest.chars().next().expect("starts with a quote");
+        let mut value = String::new();
+        let mut chars = rest.char_indices().skip(1).peekable();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '\'' if quote == '\'' => {
+                    if chars.next_if(|&(_, c)| c == '\'').is_none() {
+                        return Ok((value, start + index + 1));
+                    }
+                    value.push('\'');
+                }
+                '"' if quote == '"' => return Ok((value, start + index + 1)),
+                '\\' if quote == '"' => {
+                    let escaped = match chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, '0')) => '\0',
+                        Some((_, c @ ('"' | '\\' | '/' | ' '))) => c,
+                        Some((_, kind @ ('x' | 'u' | 'U'))) => {
+                            let len = match kind {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let hex = rest
+                                .get(index + 2..index + 2 + len)
+                                .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+                            let Some(c) = hex
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32)
+                            else {
+                                return Err(Error::new(
+                                    range(start + index, start + index + 2),
+                                    "invalid escape sequence",
+                                ));
+                            };
+                            for _ in 0..len {
+                                chars.next();
+                            }
+                            c
+                        }
+                        _ => {
+                            return Err(Error::new(
+                                range(start + index, start + index + 2),
+                                "invalid escape sequence",
+                            ));
+                        }
+                    };
+                    value.push(escaped);
+                }
+                // Line breaks in quoted scalars fold into spaces.
+                '\n' => {
+                    let trimmed = value.trim_end().len();
+                    value.truncate(trimmed);
+                    value.push(' ');
+                    while chars.next_if(|&(_, c)| c == ' ' || c == '\t').is_some() {}
+                }
+                _ => value.push(c),
+            }
+        }
+        let line_end = start + rest.find('\n').unwrap_or(rest.len());
+        Err(Error::new(
+            range(start, line_end.max(start + 1)),
+            "unterminated string",
+        ))
+    }
+}
+
+fn unexpected_indentation(line: Line) -> Error {
+    Error::new(range(line.start, line.end), "unexpected indentation")
+}
+
+fn range(start: usize, end: usize) -> TextRange {
+    TextRange::new(offset(start), offset(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::front_matter::tests::outline;
+
+    fn parse(yaml: &str) -> Result<String, String> {
+        let text = format!("---\n{yaml}---\n");
+        let content = range(4, 4 + yaml.len());
+        let mut entries = Vec::new();
+        match super::parse(&text, content, &mut entries) {
+            Ok(()) => Ok(outline(&text, &entries, 0)),
+            Err(error) => Err(error.message),
+        }
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(
+            parse(
+                "title: Hello, world # comment\n\
+                 draft: false\n\
+                 weight: 10\n\
+                 ratio: 0.5\n\
+                 date: 2024-01-02\n\
+                 empty:\n\
+                 quoted: \"a \\\"b\\\" \\u00e9\"\n\
+                 single: 'it''s'\n\
+                 'quoted key': 1\n"
+            ),
+            Ok("title: \"Hello, world\"\n\
+                draft: false\n\
+                weight: 10\n\
+                ratio: 0.5\n\
+                date: \"2024-01-02\"\n\
+                empty: null\n\
+                quoted: \"a \\\"b\\\" é\"\n\
+                single: \"it's\"\n\
+                'quoted key': 1\n"
+                .to_owned())
+        );
+        assert_eq!(
+            parse("description: A long\n  description\nnext: 1\n"),
+            Ok("description: \"A long description\"\nnext: 1\n".to_owned())
+        );
+        assert_eq!(
+            parse("literal: |\n  a\n  b\nfolded: >-\n  a\n  b\n"),
+            Ok("literal: \"a\\nb\\n\"\nfolded: \"a b\"\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        assert_eq!(
+            parse(
+                "tags:\n\
+                 - a\n\
+                 - b\n\
+                 author:\n  \
+                   name: A\n  \
+                   links:\n    \
+                     - url: x\n      \
+                       title: X\n\
+                 flow: [1, 'two', {three: 3}]\n\
+                 multiline: [\n  a,\n  b,\n]\n"
+            ),
+            Ok("tags: [\"a\", \"b\"]\n\
+                author:\n  \
+                  name: \"A\"\n  \
+                  links: [{2}]\n\
+                flow: [1, \"two\", {1}]\n\
+                multiline: [\"a\", \"b\"]\n"
+                .to_owned())
+        );
+        assert_eq!(
+            parse("a:\n  - 1\n  -\n    - 2\n"),
+            Ok("a: [1, [..]]\n".to_owned())
+        );
+        assert_eq!(parse("# only a comment\n"), Ok(String::new()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("title: a: b\n"),
+            Err("`: ` isn't allowed in a plain value, which should be quoted".to_owned())
+        );
+        assert_eq!(parse("a: 1\na: 2\n"), Err("duplicate key `a`".to_owned()));
+        assert_eq!(
+            parse("a: 1\n  b: 2\n"),
+            Err("unexpected indentation".to_owned())
+        );
+        assert_eq!(parse("a: 1\nb\n"), Err("expected `key: value`".to_owned()));
+        assert_eq!(
+            parse("a:\n\tb: 1\n"),
+            Err("tabs can't be used for indentation".to_owned())
+        );
+        assert_eq!(parse("a: \"b\n"), Err("unterminated string".to_owned()));
+        assert_eq!(
+            parse("a: [b, c\n"),
+            Err("unterminated flow sequence, expected `]`".to_owned())
+        );
+        assert_eq!(
+            parse("a: 'b' c\n"),
+            Err("expected a line break after the value".to_owned())
+        );
+        assert_eq!(
+            parse("- a\n"),
+            Err("front matter should be a mapping of keys to values".to_owned())
+        );
+        assert_eq!(
+            parse("a: *b\n"),
+            Err("anchors, aliases and tags aren't supported".to_owned())
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Glob pattern matching for `.gitignore` files and configuration.
+//!
+//! Supports `*` and `?`, which don't match `/`, `**`, which matches any
+//! number of path components, and `[...]` character classes.
+
+/// Returns whether `path`, with `/` separated components, matches `pattern`.
+pub(crate) fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let path = path.chars().collect::<Vec<_>>();
+    matches_from(&pattern, &path)
+}
+
+fn matches_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*'] => true,
+        ['*', '*', rest @ ..] => {
+            // `**/` also matches no directories at all.
+            let rest = rest.strip_prefix(&['/']).unwrap_or(rest);
+            (0..=path.len())
+                .filter(|&i| i == 0 || path[i - 1] == '/')
+                .any(|i| matches_from(rest, &path[i..]))
+        }
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| matches_from(rest, &path[i..])),
+        ['?', rest @ ..] => match path {
+            [c, path @ ..] if *c != '/' => matches_from(rest, path),
+            _ => false,
+        },
+        ['[', rest @ ..] => match (class(rest), path) {
+            (Some((matcher, rest)), [c, path @ ..]) => {
+                *c != '/' && matcher(*c) && matches_from(rest, path)
+            }
+            // An unterminated class is a literal `[`.
+            (None, ['[', path @ ..]) => matches_from(rest, path),
+            _ => false,
+        },
+        ['\\', c, rest @ ..] | [c, rest @ ..] => match path {
+            [p, path @ ..] if p == c => matches_from(rest, path),
+            _ => false,
+        },
+    }
+}
+
+/// Parses a character class, without the opening `[`, returning a matcher and
+/// the rest of the pattern.
+fn class(pattern: &[char]) -> Option<(impl Fn(char) -> bool + '_, &[char])> {
+    let (negated, pattern) = match pattern {
+        ['!' | '^', rest @ ..] => (true, rest),
+        _ => (false, pattern),
+    };
+    // A `]` directly after the opening bracket is part of the class.
+    let end = pattern
+        .iter()
+        .skip(1)
+        .position(|&c| c == ']')
+        .map(|i| i + 1)?;
+    let members = &pattern[..end];
+
+    let matcher = move |c: char| {
+        let mut i = 0;
+        let mut found = false;
+        while i < members.len() {
+            if i + 2 < members.len() && members[i + 1] == '-' {
+                found |= (members[i]..=members[i + 2]).contains(&c);
+                i += 3;
+            } else {
+                found |= members[i] == c;
+                i += 1;
+            }
+        }
+        found != negated
+    };
+    Some((matcher, &pattern[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("*.md", "README.md"));
+        assert!(!matches("*.md", "docs/README.md"));
+        assert!(matches("docs/?.md", "docs/a.md"));
+        assert!(!matches("docs/?.md", "docs/ab.md"));
+        assert!(matches("a\\*", "a*"));
+        assert!(!matches("a\\*", "ab"));
+    }
+
+    #[test]
+    fn test_globstar() {
+        assert!(matches("content/blog/**", "content/blog/2022/post.md"));
+        assert!(matches("**/*.md", "README.md"));
+        assert!(matches("**/*.md", "a/b/c.md"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(!matches("a/**/b", "a/xb"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(matches("[abc].md", "b.md"));
+        assert!(!matches("[!abc].md", "b.md"));
+        assert!(matches("file[0-9]", "file7"));
+        assert!(!matches("file[0-9]", "filex"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[", "["));
+    }
+}
@@ -1,11 +1,20 @@
-use crate::{index::LineIndex, Context, Result};
-use lsp_types::notification::{self, Notification};
+use crate::{
+    analysis::Analysis, config::Config, diagnostics, index::LineIndex, missing, proto, schema,
+    spell, Context, Result,
+};
+use lsp_types::{
+    notification::{self, Notification},
+    Url,
+};
 
 pub fn did_open_text_document(
     ctx: &mut Context,
     params: <notification::DidOpenTextDocument as Notification>::Params,
 ) -> Result<()> {
-    ctx.insert_file(params.text_document.uri, params.text_document.text)
+    let uri = params.text_document.uri;
+    ctx.insert_file(uri.clone(), params.text_document.text)?;
+    ctx.update_link_index(&uri);
+    publish_diagnostics(ctx, uri)
 }
 
 pub fn did_change_text_document(
@@ -15,7 +24,53 @@ pub fn did_change_text_document(
     let uri = params.text_document.uri;
     assert_eq!(params.content_changes.len(), 1);
     let text = params.content_changes.into_iter().next().unwrap().text;
+    let encoding = ctx.position_encoding();
     let contents = ctx.get_mut_contents(&uri)?;
-    *contents = (LineIndex::new(&text), text);
+    *contents = (LineIndex::new(&text, encoding), text);
+    ctx.update_link_index(&uri);
+    publish_diagnostics(ctx, uri)
+}
+
+pub fn did_change_configuration(
+    ctx: &mut Context,
+    params: <notification::DidChangeConfiguration as Notification>::Params,
+) -> Result<()> {
+    let config = Config::from_settings(params.settings);
+    if &config == ctx.config() {
+        return Ok(());
+    }
+    ctx.set_config(config);
+    // Anchors may have changed, which affects diagnostics.
+    for uri in ctx.open_documents() {
+        publish_diagnostics(ctx, uri)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn publish_diagnostics(ctx: &mut Context, uri: Url) -> Result<()> {
+    ctx.refresh_spell_checker();
+    ctx.refresh_schemas();
+    let schemas = schema::schemas(ctx, &uri);
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+    let mut diagnostics = diagnostics::diagnostics(contents, &analysis, ctx.config());
+    diagnostics.extend(schema::diagnostics(contents, &schemas));
+    diagnostics.extend(missing::diagnostics(
+        &uri,
+        &analysis,
+        ctx.root(),
+        ctx.config().slug,
+        |target| ctx.document_text(target),
+    ));
+    if let Some(checker) = ctx.spell_checker() {
+        diagnostics.extend(spell::diagnostics(contents, checker));
+    }
+    let diagnostics = diagnostics
+        .into_iter()
+        .map(|diagnostic| proto::diagnostic(line_index, &uri, diagnostic))
+        .collect();
+    ctx.send_notification::<notification::PublishDiagnostics>(
+        lsp_types::PublishDiagnosticsParams::new(uri, diagnostics, None),
+    );
     Ok(())
 }
@@ -1,5 +1,20 @@
-use crate::{proto, Context, Result};
-use lsp_types::request::{self, Request};
+use std::{borrow::Cow, path::Path};
+
+use crate::{
+    analysis::{local_destination, Analysis, Link},
+    diagnostics,
+    hover::is_markdown,
+    index::LineIndex,
+    proto,
+    spell::{self, SpellChecker},
+    Context, Result,
+};
+use hashbrown::HashMap;
+use lsp_types::{
+    request::{self, Request},
+    Url,
+};
+use text_edit::TextRange;
 
 pub fn format(
     ctx: &mut Context,
@@ -15,3 +30,884 @@ pub fn format(
         Ok(Some(vec))
     }
 }
+
+pub fn code_action(
+    ctx: &mut Context,
+    request: <request::CodeActionRequest as Request>::Params,
+) -> Result<Option<lsp_types::CodeActionResponse>> {
+    let uri = request.text_document.uri;
+    ctx.refresh_spell_checker();
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let range = proto::text_range(line_index, request.range);
+
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let mut actions = Vec::new();
+    for diagnostic in diagnostics::diagnostics(contents, &analysis, ctx.config()) {
+        if diagnostic.range.intersect(range).is_none() {
+            continue;
+        }
+        let fixes = diagnostic.fixes.clone();
+        let diagnostic = proto::diagnostic(line_index, &uri, diagnostic);
+        for fix in fixes {
+            let action = proto::quick_fix(line_index, &uri, fix, diagnostic.clone());
+            actions.push(lsp_types::CodeActionOrCommand::CodeAction(action));
+        }
+    }
+    let only = request.context.only.as_deref();
+    if let Some(checker) = ctx
+        .spell_checker()
+        .filter(|_| is_requested(only, &lsp_types::CodeActionKind::QUICKFIX))
+    {
+        // Suggestions are slow to find, so only the words the client asks
+        // about or the cursor is on get them.
+        let requested = request
+            .context
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                diagnostic.code == Some(lsp_types::NumberOrString::String(spell::CODE.to_owned()))
+            })
+            .map(|diagnostic| proto::text_range(line_index, diagnostic.range))
+            .collect::<Vec<_>>();
+        actions.extend(spelling_fixes(
+            line_index, &uri, contents, range, &requested, checker,
+        ));
+    }
+    for assist in crate::assist::assists(contents, &analysis, ctx.config(), range) {
+        let action = proto::code_action(line_index, &uri, assist);
+        actions.push(lsp_types::CodeActionOrCommand::CodeAction(action));
+    }
+    if analysis.headings.iter().any(|heading| {
+        heading.range.contains_inclusive(range.start())
+            && !crate::extract::renumbers_anchors(contents, &analysis, heading, ctx.config().slug)
+    }) {
+        let action = proto::extract_section(&uri, request.range.start);
+        actions.push(lsp_types::CodeActionOrCommand::CodeAction(action));
+    }
+    let missing =
+        crate::missing::diagnostics(&uri, &analysis, ctx.root(), ctx.config().slug, |target| {
+            ctx.document_text(target)
+        });
+    for diagnostic in missing {
+        if diagnostic.range.intersect(range).is_none() {
+            continue;
+        }
+        let Some(link) = analysis.link_at(diagnostic.range.start()) else {
+            continue;
+        };
+        if let Some(mut action) = create_link_target(ctx, &uri, contents, link) {
+            action.diagnostics = Some(vec![proto::diagnostic(line_index, &uri, diagnostic)]);
+            actions.push(lsp_types::CodeActionOrCommand::CodeAction(action));
+        }
+    }
+    if is_requested(only, &proto::FIX_ALL) {
+        let edit = diagnostics::fix_all(contents, ctx.config());
+        if !edit.is_empty() {
+            let action = proto::fix_all(line_index, &uri, edit);
+            actions.push(lsp_types::CodeActionOrCommand::CodeAction(action));
+        }
+    }
+    actions.retain(|action| match action {
+        lsp_types::CodeActionOrCommand::CodeAction(action) => action
+            .kind
+            .as_ref()
+            .is_none_or(|kind| is_requested(only, kind)),
+        lsp_types::CodeActionOrCommand::Command(_) => true,
+    });
+
+    if actions.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(actions))
+    }
+}
+
+/// Offers the suggestions for the unknown words at the start of a range or
+/// with a requested diagnostic, and adding them to the words file.
+#[allow(clippy::string_slice)]
+fn spelling_fixes(
+    line_index: &LineIndex,
+    uri: &Url,
+    text: &str,
+    range: TextRange,
+    requested: &[TextRange],
+    checker: &SpellChecker,
+) -> Vec<lsp_types::CodeActionOrCommand> {
+    let mut actions = Vec::new();
+    for diagnostic in spell::diagnostics(text, checker) {
+        if !diagnostic.range.contains_inclusive(range.start())
+            && !requested.contains(&diagnostic.range)
+        {
+            continue;
+        }
+        let word = text[diagnostic.range].to_owned();
+        let fixes = spell::fixes(&word, diagnostic.range, checker);
+        let diagnostic = proto::diagnostic(line_index, uri, diagnostic);
+        for fix in fixes {
+            let action = proto::quick_fix(line_index, uri, fix, diagnostic.clone());
+            actions.push(lsp_types::CodeActionOrCommand::CodeAction(action));
+        }
+        if let Some(file_name) = checker.words_file().and_then(Path::file_name) {
+            let action = proto::add_word(&word, &file_name.to_string_lossy(), diagnostic);
+            actions.push(lsp_types::CodeActionOrCommand::CodeAction(action));
+        }
+    }
+    actions
+}
+
+/// Returns whether a kind of code action is one the client asked for, which
+/// includes the kinds below them, e.g. `source.fixAll.mdls` below `source`.
+fn is_requested(
+    only: Option<&[lsp_types::CodeActionKind]>,
+    kind: &lsp_types::CodeActionKind,
+) -> bool {
+    only.is_none_or(|only| {
+        only.iter().any(|only| {
+            kind.as_str()
+                .strip_prefix(only.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        })
+    })
+}
+
+/// Creates the document a link points to if it doesn't exist, or else adds
+/// the heading its anchor refers to.
+fn create_link_target(
+    ctx: &Context,
+    uri: &Url,
+    text: &str,
+    link: &Link,
+) -> Option<lsp_types::CodeAction> {
+    let (path, anchor) = local_destination(&link.destination)?;
+    let slug = ctx.config().slug;
+
+    let (target, target_text) = if path.is_empty() {
+        (uri.clone(), Cow::Borrowed(text))
+    } else {
+        let mut target = crate::workspace::resolve(uri, path, ctx.root())?;
+        target.set_fragment(None);
+        if let Some(target_text) = ctx.document_text(&target) {
+            (target, target_text)
+        } else {
+            let file = target.to_file_path().ok()?;
+            if file.exists() || !is_markdown(&file) {
+                return None;
+            }
+            let contents = crate::missing::new_document(&link.text, path, anchor, slug);
+            let edit = lsp_types::WorkspaceEdit {
+                document_changes: Some(lsp_types::DocumentChanges::Operations(
+                    proto::create_file(target, contents).to_vec(),
+                )),
+                ..Default::default()
+            };
+            return Some(lsp_types::CodeAction {
+                title: format!("Create `{path}`"),
+                kind: Some(lsp_types::CodeActionKind::QUICKFIX),
+                edit: Some(edit),
+                ..Default::default()
+            });
+        }
+    };
+
+    let anchor = anchor.filter(|anchor| !anchor.is_empty())?;
+    let target_analysis = Analysis::new(&target_text, slug);
+    if target_analysis.heading(anchor).is_some() {
+        return None;
+    }
+    let indel =
+        crate::missing::append_heading(&target_text, &target_analysis, slug, &link.text, anchor);
+    let line_index = LineIndex::new(&target_text, ctx.position_encoding());
+    let edit = lsp_types::WorkspaceEdit::new(
+        std::iter::once((target, vec![proto::text_edit(&line_index, indel)])).collect(),
+    );
+    Some(lsp_types::CodeAction {
+        title: format!("Add heading for `#{anchor}`"),
+        kind: Some(lsp_types::CodeActionKind::QUICKFIX),
+        edit: Some(edit),
+        ..Default::default()
+    })
+}
+
+pub fn goto_definition(
+    ctx: &mut Context,
+    request: <request::GotoDefinition as Request>::Params,
+) -> Result<Option<lsp_types::GotoDefinitionResponse>> {
+    let uri = request.text_document_position_params.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let offset = proto::offset(line_index, request.text_document_position_params.position);
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let definition = analysis
+        .footnote_at(offset)
+        .and_then(|label| analysis.footnote_definition(&label.normalized));
+    Ok(definition.map(|definition| {
+        let range = proto::range(line_index, definition.label.range);
+        lsp_types::GotoDefinitionResponse::Scalar(lsp_types::Location::new(uri, range))
+    }))
+}
+
+pub fn references(
+    ctx: &mut Context,
+    request: <request::References as Request>::Params,
+) -> Result<Option<Vec<lsp_types::Location>>> {
+    let uri = request.text_document_position.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let offset = proto::offset(line_index, request.text_document_position.position);
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    if let Some(heading) = analysis
+        .headings
+        .iter()
+        .find(|heading| heading.range.contains(offset))
+    {
+        let range = proto::range(line_index, heading.range);
+        let anchor = heading.anchor.clone();
+        let mut locations = heading_references(ctx, &uri, &anchor);
+        if request.context.include_declaration {
+            locations.insert(0, lsp_types::Location::new(uri, range));
+        }
+        return Ok(Some(locations));
+    }
+
+    let Some(label) = analysis.footnote_at(offset) else {
+        return Ok(None);
+    };
+
+    let mut ranges = analysis
+        .footnote_references
+        .iter()
+        .filter(|reference| reference.normalized == label.normalized)
+        .map(|reference| reference.range)
+        .collect::<Vec<_>>();
+    if request.context.include_declaration {
+        if let Some(definition) = analysis.footnote_definition(&label.normalized) {
+            ranges.push(definition.label.range);
+        }
+    }
+
+    Ok(Some(
+        ranges
+            .into_iter()
+            .map(|range| lsp_types::Location::new(uri.clone(), proto::range(line_index, range)))
+            .collect(),
+    ))
+}
+
+pub fn hover(
+    ctx: &mut Context,
+    request: <request::HoverRequest as Request>::Params,
+) -> Result<Option<lsp_types::Hover>> {
+    let uri = request.text_document_position_params.text_document.uri;
+    ctx.refresh_schemas();
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let offset = proto::offset(line_index, request.text_document_position_params.position);
+
+    Ok(crate::hover::hover(ctx, &uri, contents, offset)
+        .map(|(range, markdown)| proto::hover(line_index, range, markdown)))
+}
+
+pub fn completion(
+    ctx: &mut Context,
+    request: <request::Completion as Request>::Params,
+) -> Result<Option<lsp_types::CompletionResponse>> {
+    let uri = request.text_document_position.text_document.uri;
+    ctx.refresh_schemas();
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let offset = proto::offset(line_index, request.text_document_position.position);
+
+    let items = crate::completion::completions(ctx, &uri, contents, offset)
+        .into_iter()
+        .map(|completion| proto::completion_item(line_index, completion))
+        .collect::<Vec<_>>();
+    if items.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(lsp_types::CompletionResponse::Array(items)))
+    }
+}
+
+pub fn document_link(
+    ctx: &mut Context,
+    request: <request::DocumentLinkRequest as Request>::Params,
+) -> Result<Option<Vec<lsp_types::DocumentLink>>> {
+    let uri = request.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let links = crate::document_link::document_links(&uri, &analysis, ctx.root())
+        .into_iter()
+        .map(|link| proto::document_link(line_index, link))
+        .collect();
+    Ok(Some(links))
+}
+
+pub fn document_link_resolve(
+    ctx: &mut Context,
+    mut link: <request::DocumentLinkResolve as Request>::Params,
+) -> Result<lsp_types::DocumentLink> {
+    if let Some((uri, anchor)) = link.data.as_ref().and_then(proto::heading_target) {
+        link.target = Some(crate::document_link::resolve(ctx, uri, anchor));
+        link.data = None;
+    }
+    Ok(link)
+}
+
+pub fn semantic_tokens_full(
+    ctx: &mut Context,
+    request: <request::SemanticTokensFullRequest as Request>::Params,
+) -> Result<Option<lsp_types::SemanticTokensResult>> {
+    let uri = request.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let tokens = crate::semantic_tokens::semantic_tokens(contents, &analysis);
+    let data = proto::semantic_tokens(line_index, tokens);
+    Ok(Some(ctx.store_semantic_tokens(uri, data).into()))
+}
+
+pub fn semantic_tokens_full_delta(
+    ctx: &mut Context,
+    request: <request::SemanticTokensFullDeltaRequest as Request>::Params,
+) -> Result<Option<lsp_types::SemanticTokensFullDeltaResult>> {
+    let uri = request.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let tokens = crate::semantic_tokens::semantic_tokens(contents, &analysis);
+    let data = proto::semantic_tokens(line_index, tokens);
+    let edits = ctx
+        .semantic_tokens(&uri)
+        .filter(|previous| previous.result_id.as_ref() == Some(&request.previous_result_id))
+        .map(|previous| proto::semantic_tokens_edits(&previous.data, &data));
+
+    let tokens = ctx.store_semantic_tokens(uri, data);
+    Ok(Some(match edits {
+        Some(edits) => lsp_types::SemanticTokensDelta {
+            result_id: tokens.result_id,
+            edits,
+        }
+        .into(),
+        None => tokens.into(),
+    }))
+}
+
+pub fn semantic_tokens_range(
+    ctx: &mut Context,
+    request: <request::SemanticTokensRangeRequest as Request>::Params,
+) -> Result<Option<lsp_types::SemanticTokensRangeResult>> {
+    let uri = request.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let range = proto::text_range(line_index, request.range);
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let tokens = crate::semantic_tokens::semantic_tokens(contents, &analysis)
+        .into_iter()
+        .filter(|token| token.range.intersect(range).is_some())
+        .collect();
+    Ok(Some(
+        lsp_types::SemanticTokens {
+            result_id: None,
+            data: proto::semantic_tokens(line_index, tokens),
+        }
+        .into(),
+    ))
+}
+
+pub fn selection_range(
+    ctx: &mut Context,
+    request: <request::SelectionRangeRequest as Request>::Params,
+) -> Result<Option<Vec<lsp_types::SelectionRange>>> {
+    let uri = request.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+    let root = crate::syntax::Node::parse(contents);
+
+    let mut selection_ranges = Vec::new();
+    for position in request.positions {
+        let offset = proto::offset(line_index, position);
+        let ranges = crate::selection::selection_ranges(contents, &analysis, &root, offset);
+        // Never `None`, since the whole document is always included.
+        selection_ranges.extend(proto::selection_range(line_index, ranges));
+    }
+    Ok(Some(selection_ranges))
+}
+
+pub fn document_symbol(
+    ctx: &mut Context,
+    request: <request::DocumentSymbolRequest as Request>::Params,
+) -> Result<Option<lsp_types::DocumentSymbolResponse>> {
+    let uri = request.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let symbols = crate::symbols::symbols(contents, &analysis)
+        .into_iter()
+        .map(|symbol| proto::document_symbol(line_index, symbol))
+        .collect();
+    Ok(Some(lsp_types::DocumentSymbolResponse::Nested(symbols)))
+}
+
+pub fn document_highlight(
+    ctx: &mut Context,
+    request: <request::DocumentHighlightRequest as Request>::Params,
+) -> Result<Option<Vec<lsp_types::DocumentHighlight>>> {
+    let uri = request.text_document_position_params.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let offset = proto::offset(line_index, request.text_document_position_params.position);
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    let highlights = crate::highlight::highlights(contents, &analysis, offset)
+        .into_iter()
+        .map(|(range, kind)| proto::document_highlight(line_index, range, kind))
+        .collect::<Vec<_>>();
+    if highlights.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(highlights))
+    }
+}
+
+pub fn code_lens(
+    ctx: &mut Context,
+    request: <request::CodeLensRequest as Request>::Params,
+) -> Result<Option<Vec<lsp_types::CodeLens>>> {
+    let uri = request.text_document.uri;
+    let (line_index, contents) = ctx.get_contents(&uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+
+    // Counting the references is left to `codeLens/resolve`.
+    let lenses = analysis
+        .headings
+        .iter()
+        .map(|heading| lsp_types::CodeLens {
+            range: proto::range(line_index, heading.range),
+            command: None,
+            data: Some(proto::heading_data(&uri, &heading.anchor)),
+        })
+        .collect();
+    Ok(Some(lenses))
+}
+
+pub fn code_lens_resolve(
+    ctx: &mut Context,
+    mut lens: <request::CodeLensResolve as Request>::Params,
+) -> Result<lsp_types::CodeLens> {
+    if let Some((uri, anchor)) = lens.data.as_ref().and_then(proto::heading_target) {
+        let locations = heading_references(ctx, &uri, anchor);
+        lens.command = Some(proto::show_references(&uri, lens.range.start, &locations));
+    }
+    Ok(lens)
+}
+
+pub fn execute_command(
+    ctx: &mut Context,
+    request: <request::ExecuteCommand as Request>::Params,
+) -> Result<Option<serde_json::Value>> {
+    match request.command.as_str() {
+        crate::command::SHOW_REFERENCES => {
+            let mut arguments = request.arguments.into_iter();
+            let (Some(uri), Some(position)) = (
+                arguments
+                    .next()
+                    .and_then(|uri| serde_json::from_value::<Url>(uri).ok()),
+                arguments
+                    .next()
+                    .and_then(|position| serde_json::from_value(position).ok()),
+            ) else {
+                return Ok(None);
+            };
+            let locations = show_references(ctx, &uri, position);
+            Ok(Some(serde_json::json!(locations)))
+        }
+        crate::command::ADD_WORD => {
+            if let Some(word) = request.arguments.first().and_then(|word| word.as_str()) {
+                add_word(ctx, word)?;
+            }
+            Ok(None)
+        }
+        crate::command::UPDATE_TOC => {
+            let mut arguments = request.arguments.into_iter();
+            let Some(uri) = arguments
+                .next()
+                .and_then(|uri| serde_json::from_value::<Url>(uri).ok())
+            else {
+                return Ok(None);
+            };
+            let position = arguments
+                .next()
+                .and_then(|position| serde_json::from_value(position).ok());
+
+            update_toc(ctx, &uri, position)?;
+            Ok(None)
+        }
+        command @ (crate::command::NUMBER_HEADINGS | crate::command::REMOVE_HEADING_NUMBERS) => {
+            let Some(uri) = request
+                .arguments
+                .into_iter()
+                .next()
+                .and_then(|uri| serde_json::from_value::<Url>(uri).ok())
+            else {
+                return Ok(None);
+            };
+            let remove = command == crate::command::REMOVE_HEADING_NUMBERS;
+            number_headings(ctx, &uri, remove)?;
+            Ok(None)
+        }
+        crate::command::TOGGLE_TASK => {
+            let mut arguments = request.arguments.into_iter();
+            let (Some(uri), Some(range)) = (
+                arguments
+                    .next()
+                    .and_then(|uri| serde_json::from_value::<Url>(uri).ok()),
+                arguments
+                    .next()
+                    .and_then(|range| serde_json::from_value(range).ok()),
+            ) else {
+                return Ok(None);
+            };
+
+            let today = arguments
+                .next()
+                .and_then(|date| crate::task::Date::parse(date.as_str()?))
+                .unwrap_or_else(crate::task::Date::today);
+
+            toggle_task(ctx, &uri, range, today)?;
+            Ok(None)
+        }
+        crate::command::EXTRACT_SECTION => {
+            let mut arguments = request.arguments.into_iter();
+            let (Some(uri), Some(position)) = (
+                arguments
+                    .next()
+                    .and_then(|uri| serde_json::from_value::<Url>(uri).ok()),
+                arguments
+                    .next()
+                    .and_then(|position| serde_json::from_value(position).ok()),
+            ) else {
+                return Ok(None);
+            };
+
+            extract_section(ctx, &uri, position)?;
+            Ok(None)
+        }
+        command => {
+            tracing::warn!("unknown command: {}", command);
+            Ok(None)
+        }
+    }
+}
+
+/// Finds the links to a heading across the workspace.
+fn heading_references(ctx: &mut Context, uri: &Url, anchor: &str) -> Vec<lsp_types::Location> {
+    let mut locations = ctx
+        .link_index()
+        .references(uri, Some(anchor))
+        .map(|(uri, line_index, link)| {
+            lsp_types::Location::new(uri.clone(), proto::range(line_index, link.range))
+        })
+        .collect::<Vec<_>>();
+    locations.sort_by(|a, b| (a.uri.as_str(), a.range.start).cmp(&(b.uri.as_str(), b.range.start)));
+    locations
+}
+
+/// Shows the links to the heading at a position: the client opens the only
+/// one, or lets the user pick one of several.
+fn show_references(
+    ctx: &mut Context,
+    uri: &Url,
+    position: lsp_types::Position,
+) -> Vec<lsp_types::Location> {
+    let Some(text) = ctx.document_text(uri) else {
+        return Vec::new();
+    };
+    let line_index = LineIndex::new(&text, ctx.position_encoding());
+    let offset = proto::offset(&line_index, position);
+    let analysis = Analysis::new(&text, ctx.config().slug);
+    let Some(heading) = analysis
+        .headings
+        .into_iter()
+        .find(|heading| heading.range.contains_inclusive(offset))
+    else {
+        return Vec::new();
+    };
+    let locations = heading_references(ctx, uri, &heading.anchor);
+
+    match locations.as_slice() {
+        [] => ctx.send_notification::<lsp_types::notification::ShowMessage>(
+            lsp_types::ShowMessageParams {
+                typ: lsp_types::MessageType::INFO,
+                message: format!("No links to `{}`", heading.text.trim()),
+            },
+        ),
+        [location] => ctx.send_request::<request::ShowDocument>(
+            proto::show_document(location.clone()),
+            |_, _| {},
+        ),
+        locations => {
+            let actions = locations
+                .iter()
+                .map(|location| lsp_types::MessageActionItem {
+                    title: location_title(ctx.root(), location),
+                    properties: std::iter::once((
+                        "location".to_owned(),
+                        lsp_types::MessageActionItemProperty::Object(serde_json::json!(location)),
+                    ))
+                    .collect(),
+                })
+                .collect();
+            ctx.send_request::<request::ShowMessageRequest>(
+                lsp_types::ShowMessageRequestParams {
+                    typ: lsp_types::MessageType::INFO,
+                    message: format!("{} links to `{}`", locations.len(), heading.text.trim()),
+                    actions: Some(actions),
+                },
+                |ctx, response| {
+                    let location = response
+                        .result
+                        .and_then(|item| {
+                            serde_json::from_value::<lsp_types::MessageActionItem>(item).ok()
+                        })
+                        .and_then(|mut item| item.properties.remove("location"))
+                        .and_then(|location| match location {
+                            lsp_types::MessageActionItemProperty::Object(location) => {
+                                serde_json::from_value(location).ok()
+                            }
+                            _ => None,
+                        });
+                    if let Some(location) = location {
+                        ctx.send_request::<request::ShowDocument>(
+                            proto::show_document(location),
+                            |_, _| {},
+                        );
+                    }
+                },
+            );
+        }
+    }
+    locations
+}
+
+/// Returns `path:line` for a location, with the path relative to the root.
+fn location_title(root: Option<&Path>, location: &lsp_types::Location) -> String {
+    let path = location.uri.to_file_path().ok();
+    let path = match (&path, root) {
+        (Some(path), Some(root)) => path.strip_prefix(root).unwrap_or(path),
+        (Some(path), None) => path.as_path(),
+        (None, _) => return location.uri.to_string(),
+    };
+    format!("{}:{}", path.display(), location.range.start.line + 1)
+}
+
+/// Adds a word to the words file, and rechecks the open documents.
+fn add_word(ctx: &mut Context, word: &str) -> Result<()> {
+    let Some(checker) = ctx.spell_checker_mut() else {
+        return Ok(());
+    };
+    if let Err(error) = checker.add_word(word) {
+        tracing::warn!("can't add `{}` to the words file: {}", word, error);
+        return Ok(());
+    }
+    for uri in ctx.open_documents() {
+        crate::handle::notification::publish_diagnostics(ctx, uri)?;
+    }
+    Ok(())
+}
+
+/// Inserts or updates the table of contents of a document.
+fn update_toc(ctx: &mut Context, uri: &Url, position: Option<lsp_types::Position>) -> Result<()> {
+    let (line_index, contents) = ctx.get_contents(uri)?;
+    let analysis = Analysis::new(contents, ctx.config().slug);
+    let toc = &ctx.config().toc;
+    let edit = if let Some(markers) = crate::toc::markers(contents) {
+        crate::toc::update(contents, &analysis, toc, markers)
+    } else {
+        let offset = match position {
+            Some(position) => proto::offset(line_index, position),
+            // Before the first heading that is listed.
+            None => analysis
+                .headings
+                .iter()
+                .find(|heading| toc.includes(heading.level))
+                .map(|heading| heading.range.start())
+                .unwrap_or_default(),
+        };
+        crate::toc::insert(contents, &analysis, toc, offset)
+    };
+    if let Some(edit) = edit {
+        let edit = proto::workspace_edit(line_index, uri, edit);
+        ctx.send_request::<request::ApplyWorkspaceEdit>(
+            lsp_types::ApplyWorkspaceEditParams {
+                label: Some("Update table of contents".to_owned()),
+                edit,
+            },
+            |_, _| {},
+        );
+    }
+    Ok(())
+}
+
+/// Toggles the checkboxes of the list items in a range of a document.
+fn toggle_task(
+    ctx: &mut Context,
+    uri: &Url,
+    range: lsp_types::Range,
+    today: crate::task::Date,
+) -> Result<()> {
+    let (line_index, contents) = ctx.get_contents(uri)?;
+    let range = proto::text_range(line_index, range);
+    let edit = crate::task::toggle(contents, range, &ctx.config().task, today);
+    if !edit.is_empty() {
+        let edit = proto::workspace_edit(line_index, uri, edit);
+        ctx.send_request::<request::ApplyWorkspaceEdit>(
+            lsp_types::ApplyWorkspaceEditParams {
+                label: Some("Toggle task".to_owned()),
+                edit,
+            },
+            |_, _| {},
+        );
+    }
+    Ok(())
+}
+
+/// Moves the section of the heading at a position into a new document next to
+/// the original one, and redirects the links to its headings.
+fn extract_section(ctx: &mut Context, uri: &Url, position: lsp_types::Position) -> Result<()> {
+    let (line_index, contents) = ctx.get_contents(uri)?;
+    let offset = proto::offset(line_index, position);
+    let analysis = Analysis::new(contents, ctx.config().slug);
+    let Some(heading) = analysis
+        .headings
+        .iter()
+        .find(|heading| heading.range.contains_inclusive(offset))
+    else {
+        return Ok(());
+    };
+    let Some(original) = uri
+        .path_segments()
+        .and_then(Iterator::last)
+        .filter(|name| !name.is_empty())
+    else {
+        return Ok(());
+    };
+
+    let is_taken = |name: &str| {
+        uri.join(name)
+            .map_or(true, |uri| ctx.document_text(&uri).is_some())
+    };
+    let name = crate::extract::file_name(heading, is_taken);
+    let Ok(new_uri) = uri.join(&name) else {
+        return Ok(());
+    };
+    let Some(extraction) = crate::extract::extract(
+        contents,
+        &analysis,
+        heading,
+        ctx.config().slug,
+        original,
+        &name,
+    ) else {
+        ctx.send_notification::<lsp_types::notification::ShowMessage>(
+            lsp_types::ShowMessageParams {
+                typ: lsp_types::MessageType::WARNING,
+                message: format!(
+                    "Can't extract `{}`: it would renumber the anchors of the duplicate headings after it",
+                    heading.text
+                ),
+            },
+        );
+        return Ok(());
+    };
+    let original_edits = proto::text_edit_vec(line_index, extraction.edit);
+
+    let mut changes = HashMap::<Url, Vec<lsp_types::TextEdit>>::new();
+    let index = ctx.link_index();
+    for (i, (old, new)) in extraction.anchors.iter().enumerate() {
+        for (source, line_index, link) in index.references(uri, Some(old)) {
+            // Links within the document are redirected by the extraction.
+            let (false, Some(range)) = (source == uri, link.destination_range) else {
+                continue;
+            };
+            let Some(path) = source.make_relative(&new_uri) else {
+                continue;
+            };
+            // Links to the extracted heading point to the new document itself.
+            let destination = if i == 0 {
+                path
+            } else {
+                format!("{path}#{new}")
+            };
+            let edit = lsp_types::TextEdit::new(proto::range(line_index, range), destination);
+            // Reference-style links share the destination of the definition.
+            let edits = changes.entry(source.clone()).or_default();
+            if !edits.contains(&edit) {
+                edits.push(edit);
+            }
+        }
+    }
+
+    let mut operations = proto::create_file(new_uri, extraction.contents).to_vec();
+    operations.push(proto::text_document_edit(uri.clone(), original_edits));
+    operations.extend(
+        changes
+            .into_iter()
+            .map(|(uri, edits)| proto::text_document_edit(uri, edits)),
+    );
+    ctx.send_request::<request::ApplyWorkspaceEdit>(
+        lsp_types::ApplyWorkspaceEditParams {
+            label: Some("Extract section".to_owned()),
+            edit: lsp_types::WorkspaceEdit {
+                document_changes: Some(lsp_types::DocumentChanges::Operations(operations)),
+                ..Default::default()
+            },
+        },
+        |_, _| {},
+    );
+    Ok(())
+}
+
+/// Renumbers the headings of a document and updates the links to the headings
+/// whose anchors change.
+fn number_headings(ctx: &mut Context, uri: &Url, remove: bool) -> Result<()> {
+    let (line_index, contents) = ctx.get_contents(uri)?;
+    let slug = ctx.config().slug;
+    let analysis = Analysis::new(contents, slug);
+    let renumbering = crate::numbering::renumber(contents, &analysis, slug, remove);
+    if renumbering.edit.is_empty() {
+        return Ok(());
+    }
+
+    let mut changes = HashMap::<Url, Vec<lsp_types::TextEdit>>::new();
+    changes.insert(
+        uri.clone(),
+        proto::text_edit_vec(line_index, renumbering.edit),
+    );
+    let index = ctx.link_index();
+    for (old, new) in &renumbering.anchors {
+        for (source, line_index, link) in index.references(uri, Some(old)) {
+            let Some(range) = link.anchor_range else {
+                continue;
+            };
+            let edit = lsp_types::TextEdit::new(proto::range(line_index, range), new.clone());
+            // Reference-style links share the destination of the definition.
+            let edits = changes.entry(source.clone()).or_default();
+            if !edits.contains(&edit) {
+                edits.push(edit);
+            }
+        }
+    }
+
+    let label = if remove {
+        "Remove heading numbers"
+    } else {
+        "Number headings"
+    };
+    ctx.send_request::<request::ApplyWorkspaceEdit>(
+        lsp_types::ApplyWorkspaceEditParams {
+            label: Some(label.to_owned()),
+            edit: lsp_types::WorkspaceEdit::new(changes.into_iter().collect()),
+        },
+        |_, _| {},
+    );
+    Ok(())
+}
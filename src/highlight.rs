@@ -0,0 +1,145 @@
+//! Highlighting the uses and definition of the symbol at the cursor.
+
+use text_edit::{TextRange, TextSize};
+
+use crate::analysis::{Analysis, Heading};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HighlightKind {
+    /// A use, such as a link.
+    Read,
+    /// The definition, such as a reference definition or heading.
+    Write,
+}
+
+/// Finds the uses and definition of the reference label, footnote label or
+/// heading at an offset.
+pub(crate) fn highlights(
+    text: &str,
+    analysis: &Analysis,
+    offset: TextSize,
+) -> Vec<(TextRange, HighlightKind)> {
+    if let Some(label) = analysis.footnote_at(offset) {
+        let uses = analysis
+            .footnote_references
+            .iter()
+            .filter(|reference| reference.normalized == label.normalized)
+            .map(|reference| (reference.range, HighlightKind::Read));
+        let definition = analysis
+            .footnote_definition(&label.normalized)
+            .map(|definition| (definition.label.range, HighlightKind::Write));
+        return uses.chain(definition).collect();
+    }
+
+    let label = analysis
+        .links
+        .iter()
+        .filter_map(|link| link.label.as_ref())
+        .chain(
+            analysis
+                .definitions
+                .iter()
+                .map(|definition| &definition.label),
+        )
+        .find(|label| label.range.contains_inclusive(offset));
+    if let Some(label) = label {
+        let uses = analysis
+            .references(&label.normalized)
+            .filter_map(|link| link.label.as_ref())
+            .map(|label| (label.range, HighlightKind::Read));
+        let definitions = analysis
+            .definitions
+            .iter()
+            .filter(|definition| definition.label.normalized == label.normalized)
+            .map(|definition| (definition.label.range, HighlightKind::Write));
+        return uses.chain(definitions).collect();
+    }
+
+    let heading = analysis
+        .headings
+        .iter()
+        .find(|heading| heading.range.contains(offset))
+        .or_else(|| {
+            let link = analysis.link_at(offset)?;
+            analysis.heading(link.destination.strip_prefix('#')?)
+        });
+    match heading {
+        Some(heading) => heading_highlights(text, analysis, heading),
+        None => Vec::new(),
+    }
+}
+
+#[allow(clippy::string_slice)]
+fn heading_highlights(
+    text: &str,
+    analysis: &Analysis,
+    heading: &Heading,
+) -> Vec<(TextRange, HighlightKind)> {
+    let destination = format!("#{}", heading.anchor);
+    let uses = analysis
+        .links
+        .iter()
+        .filter(|link| !link.is_reference() && link.destination == destination)
+        .filter_map(|link| link.destination_range)
+        .map(|range| (range, HighlightKind::Read));
+    // The heading range includes the line break.
+    let range = TextRange::at(
+        heading.range.start(),
+        TextSize::of(text[heading.range].trim_end()),
+    );
+    uses.chain([(range, HighlightKind::Write)]).collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    fn highlights_at<'a>(text: &'a str, marker: &str) -> Vec<(&'a str, HighlightKind)> {
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let offset = TextSize::from(text.find(marker).unwrap() as u32);
+        highlights(text, &analysis, offset)
+            .into_iter()
+            .map(|(range, kind)| (&text[range], kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_labels() {
+        let text = "[a][Foo] [foo][] [b][bar]\n\n[foo]: /foo\n[bar]: /bar\n";
+        assert_eq!(
+            highlights_at(text, "oo]:"),
+            [
+                ("Foo", HighlightKind::Read),
+                ("foo", HighlightKind::Read),
+                ("foo", HighlightKind::Write)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let text = "A[^1], b[^1].\n\n[^1]: Note.\n";
+        assert_eq!(
+            highlights_at(text, "1]:"),
+            [
+                ("1", HighlightKind::Read),
+                ("1", HighlightKind::Read),
+                ("1", HighlightKind::Write)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_headings() {
+        let text = "# Intro\n\nSee [above](#intro) and [other](other.md#intro).\n";
+        let expected = [
+            ("#intro", HighlightKind::Read),
+            ("# Intro", HighlightKind::Write),
+        ];
+        assert_eq!(highlights_at(text, "Intro"), expected);
+        assert_eq!(highlights_at(text, "above"), expected);
+        assert!(highlights_at(text, "other").is_empty());
+    }
+}
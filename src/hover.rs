@@ -0,0 +1,130 @@
+//! Hover previews for links, images and footnotes, and descriptions of front
+//! matter keys.
+
+use std::{fmt::Write, path::Path};
+
+use lsp_types::Url;
+use text_edit::{TextRange, TextSize};
+
+use crate::{
+    analysis::{front_matter, local_destination, Analysis, Link},
+    image, schema, workspace, Context,
+};
+
+/// Returns the range the hover applies to and its Markdown contents.
+pub(crate) fn hover(
+    ctx: &Context,
+    uri: &Url,
+    text: &str,
+    offset: TextSize,
+) -> Option<(TextRange, String)> {
+    if front_matter(text).is_some_and(|range| range.contains(offset)) {
+        return schema::hover(&schema::schemas(ctx, uri), text, offset);
+    }
+    let analysis = Analysis::new(text, ctx.config().slug);
+
+    if let Some(label) = analysis
+        .footnote_references
+        .iter()
+        .find(|label| label.range.contains_inclusive(offset))
+    {
+        let definition = analysis.footnote_definition(&label.normalized)?;
+        return Some((label.range, definition.text.clone()));
+    }
+
+    let link = analysis.link_at(offset)?;
+    let markdown = if link.is_image {
+        image(uri, &link.destination, ctx.root())?
+    } else if link.is_reference() {
+        reference(link)?
+    } else {
+        section(ctx, uri, text, &analysis, &link.destination)?
+    };
+    Some((link.range, markdown))
+}
+
+fn reference(link: &Link) -> Option<String> {
+    if !link.resolved {
+        return None;
+    }
+    let mut markdown = format!("`{}`", link.destination);
+    if !link.title.is_empty() {
+        write!(markdown, "\n\n{}", link.title).unwrap();
+    }
+    Some(markdown)
+}
+
+/// Shows the heading and first paragraph of the section a link points to.
+#[allow(clippy::string_slice)]
+fn section(
+    ctx: &Context,
+    uri: &Url,
+    text: &str,
+    analysis: &Analysis,
+    destination: &str,
+) -> Option<String> {
+    let (path, fragment) = local_destination(destination)?;
+
+    let target;
+    let (text, analysis) = if path.is_empty() {
+        (text, analysis)
+    } else {
+        let uri = workspace::resolve(uri, path, ctx.root())?;
+        if !is_markdown(Path::new(uri.path())) {
+            return None;
+        }
+        let text = ctx.document_text(&uri)?.into_owned();
+        target = (Analysis::new(&text, ctx.config().slug), text);
+        (target.1.as_str(), &target.0)
+    };
+
+    let section = match fragment {
+        Some(anchor) => {
+            let heading = analysis.heading(anchor)?;
+            analysis.section(heading, TextSize::of(text))
+        }
+        None => TextRange::up_to(TextSize::of(text)),
+    };
+
+    let heading = analysis
+        .headings
+        .iter()
+        .find(|heading| section.contains_range(heading.range));
+    let paragraph = analysis
+        .paragraphs
+        .iter()
+        .find(|paragraph| section.contains_range(**paragraph));
+
+    let preview = heading
+        .map(|heading| text[heading.range].trim())
+        .into_iter()
+        .chain(paragraph.map(|paragraph| text[*paragraph].trim()))
+        .collect::<Vec<_>>();
+    if preview.is_empty() {
+        None
+    } else {
+        Some(preview.join("\n\n"))
+    }
+}
+
+/// Shows the path, dimensions and size of a local image.
+fn image(uri: &Url, destination: &str, root: Option<&Path>) -> Option<String> {
+    let (path, _) = local_destination(destination)?;
+    let file = workspace::resolve(uri, path, root)?.to_file_path().ok()?;
+    let size = std::fs::metadata(&file).ok()?.len();
+
+    let mut markdown = format!("`{path}`\n\n");
+    if let Some((width, height)) = image::read_dimensions(&file) {
+        write!(markdown, "{width} × {height} pixels, ").unwrap();
+    }
+    markdown.push_str(&image::file_size(size));
+    Some(markdown)
+}
+
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            matches!(extension.to_ascii_lowercase().as_str(), "md" | "markdown")
+        })
+}
@@ -0,0 +1,128 @@
+//! `.gitignore` support.
+
+use std::path::{Path, PathBuf};
+
+use crate::glob;
+
+#[derive(Clone, Debug)]
+struct Rule {
+    /// The directory of the `.gitignore` file the rule comes from.
+    base: PathBuf,
+    pattern: String,
+    negated: bool,
+    directory_only: bool,
+    /// Whether the pattern is matched against the path relative to `base`
+    /// rather than just the file name.
+    anchored: bool,
+}
+
+/// The ignore rules that apply to the entries of a directory.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Gitignore {
+    rules: Vec<Rule>,
+}
+
+impl Gitignore {
+    /// Loads the `.gitignore` files in `dir` and its ancestors up to `root`.
+    pub(crate) fn for_directory(root: &Path, dir: &Path) -> Self {
+        let mut dirs = dir
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(root))
+            .collect::<Vec<_>>();
+        // Rules in deeper directories take precedence.
+        dirs.reverse();
+
+        let mut gitignore = Self::default();
+        for dir in dirs {
+            if let Ok(contents) = std::fs::read_to_string(dir.join(".gitignore")) {
+                gitignore.add(dir, &contents);
+            }
+        }
+        gitignore
+    }
+
+    fn add(&mut self, base: &Path, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (directory_only, pattern) = match pattern.strip_suffix('/') {
+                Some(pattern) => (true, pattern),
+                None => (false, pattern),
+            };
+            let anchored = pattern.contains('/');
+
+            self.rules.push(Rule {
+                base: base.to_owned(),
+                pattern: pattern.trim_start_matches('/').to_owned(),
+                negated,
+                directory_only,
+                anchored,
+            });
+        }
+    }
+
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.directory_only && !is_dir {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&rule.base) else {
+                continue;
+            };
+            let relative = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let name = relative.rsplit('/').next().unwrap_or_default();
+
+            let is_match = if rule.anchored {
+                glob::matches(&rule.pattern, &relative)
+            } else {
+                glob::matches(&rule.pattern, name)
+            };
+            if is_match {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let mut gitignore = Gitignore::default();
+        gitignore.add(
+            Path::new("/repo"),
+            "# build output\ntarget/\n*.log\n!keep.log\n/draft.md\n",
+        );
+        gitignore.add(Path::new("/repo/docs"), "generated/*.md\n");
+
+        let ignored = |path: &str, is_dir| gitignore.is_ignored(Path::new(path), is_dir);
+        assert!(ignored("/repo/target", true));
+        assert!(!ignored("/repo/target", false));
+        assert!(ignored("/repo/docs/debug.log", false));
+        assert!(!ignored("/repo/keep.log", false));
+        assert!(ignored("/repo/draft.md", false));
+        assert!(!ignored("/repo/docs/draft.md", false));
+        assert!(ignored("/repo/docs/generated/api.md", false));
+        assert!(!ignored("/repo/generated/api.md", false));
+        assert!(ignored("/repo/.git", true));
+    }
+}
@@ -0,0 +1,175 @@
+//! Reads the pixel dimensions of an image from its header.
+
+use std::{fs::File, io::Read, path::Path};
+
+/// How much of a file is read for its header. JPEGs can have thumbnails and
+/// color profiles before their frame header.
+const HEADER_LEN: u64 = 256 * 1024;
+
+/// Reads the dimensions of an image file from its header, without reading the
+/// rest of the file.
+pub(crate) fn read_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let mut header = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(HEADER_LEN)
+        .read_to_end(&mut header)
+        .ok()?;
+    dimensions(&header)
+}
+
+/// Returns the width and height of a PNG, GIF, JPEG, BMP or WebP image.
+pub(crate) fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some((be_u32(bytes, 16)?, be_u32(bytes, 20)?))
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some((le_u16(bytes, 6)?.into(), le_u16(bytes, 8)?.into()))
+    } else if bytes.starts_with(b"BM") {
+        let width = le_i32(bytes, 18)?;
+        // Bottom-up bitmaps have a negative height.
+        let height = le_i32(bytes, 22)?;
+        Some((width.unsigned_abs(), height.unsigned_abs()))
+    } else if bytes.starts_with(b"\xff\xd8") {
+        jpeg(bytes)
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        webp(bytes)
+    } else {
+        None
+    }
+}
+
+fn jpeg(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    loop {
+        if *bytes.get(i)? != 0xff {
+            return None;
+        }
+        let marker = *bytes.get(i + 1)?;
+        match marker {
+            // Padding.
+            0xff => i += 1,
+            // Markers without a payload.
+            0x01 | 0xd0..=0xd7 => i += 2,
+            // Start of frame, excluding the DHT, JPG and DAC markers.
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                let height = be_u16(bytes, i + 5)?;
+                let width = be_u16(bytes, i + 7)?;
+                return Some((width.into(), height.into()));
+            }
+            _ => i += 2 + usize::from(be_u16(bytes, i + 2)?),
+        }
+    }
+}
+
+fn webp(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        b"VP8 " => {
+            let width = le_u16(bytes, 26)? & 0x3fff;
+            let height = le_u16(bytes, 28)? & 0x3fff;
+            Some((width.into(), height.into()))
+        }
+        b"VP8L" => {
+            let bits = le_u32(bytes, 21)?;
+            Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+        }
+        b"VP8X" => Some((le_u24(bytes, 24)? + 1, le_u24(bytes, 27)? + 1)),
+        _ => None,
+    }
+}
+
+fn be_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn le_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn le_u24(bytes: &[u8], offset: usize) -> Option<u32> {
+    let [a, b, c]: [u8; 3] = bytes.get(offset..offset + 3)?.try_into().ok()?;
+    Some(u32::from_le_bytes([a, b, c, 0]))
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn le_i32(bytes: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Formats a file size with binary units, e.g. `12.3 KiB`.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn file_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{size:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        assert_eq!(dimensions(&png), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_gif() {
+        let gif = b"GIF89a\x20\x03\x58\x02";
+        assert_eq!(dimensions(gif), Some((800, 600)));
+    }
+
+    #[test]
+    fn test_jpeg() {
+        let jpeg = [
+            0xff, 0xd8, // SOI
+            0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, // APP0 with an empty payload
+            0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0xe0, 0x02, 0x80, // SOF0, 640x480
+        ];
+        assert_eq!(dimensions(&jpeg), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_unknown() {
+        assert_eq!(dimensions(b"<svg></svg>"), None);
+        assert_eq!(dimensions(b"\x89PNG\r\n\x1a\n"), None);
+    }
+
+    #[test]
+    fn test_file_size() {
+        assert_eq!(file_size(12), "12 B");
+        assert_eq!(file_size(12_595), "12.3 KiB");
+        assert_eq!(file_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
@@ -10,12 +10,25 @@ use std::{iter, mem};
 use hashbrown::HashMap;
 use text_edit::{TextRange, TextSize};
 
+/// The unit the client counts columns in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum PositionEncoding {
+    Utf8,
+    /// The default if the client doesn't support anything else.
+    #[default]
+    Utf16,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct LineIndex {
     /// Offset the the beginning of each line, zero-based
     pub(crate) newlines: Vec<TextSize>,
     /// List of non-ASCII characters on each line
     pub(crate) utf16_lines: HashMap<u32, Vec<Utf16Char>>,
+    /// The encoding of the columns sent to and received from the client
+    pub(crate) encoding: PositionEncoding,
+    /// The length of the text
+    pub(crate) len: TextSize,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -59,7 +72,7 @@ impl Utf16Char {
 }
 
 impl LineIndex {
-    pub(crate) fn new(text: &str) -> LineIndex {
+    pub(crate) fn new(text: &str, encoding: PositionEncoding) -> LineIndex {
         let mut utf16_lines = HashMap::default();
         let mut utf16_chars = Vec::new();
 
@@ -101,6 +114,8 @@ impl LineIndex {
         LineIndex {
             newlines,
             utf16_lines,
+            encoding,
+            len: TextSize::of(text),
         }
     }
 
@@ -114,10 +129,27 @@ impl LineIndex {
         }
     }
 
-    pub(crate) fn offset(&self, line_col: LineCol) -> Option<TextSize> {
-        self.newlines
-            .get(line_col.line as usize)
-            .map(|offset| offset + TextSize::from(line_col.col))
+    /// Returns the offset of a position. Like the LSP, a column past the end
+    /// of its line means the end of the line, and a line past the end of the
+    /// text means the end of the text. A column inside a character means the
+    /// start of the character.
+    pub(crate) fn offset(&self, line_col: LineCol) -> TextSize {
+        let Some(&start) = self.newlines.get(line_col.line as usize) else {
+            return self.len;
+        };
+        let end = self
+            .newlines
+            .get(line_col.line as usize + 1)
+            .map_or(self.len, |&next| next - TextSize::from(1));
+        let mut col = TextSize::from(line_col.col).min(end - start);
+        if let Some(c) = self
+            .utf16_lines
+            .get(&line_col.line)
+            .and_then(|utf16_chars| utf16_chars.iter().find(|c| c.start < col && col < c.end))
+        {
+            col = c.start;
+        }
+        start + col
     }
 
     pub(crate) fn to_utf16(&self, line_col: LineCol) -> LineColUtf16 {
@@ -169,7 +201,7 @@ impl LineIndex {
         if let Some(utf16_chars) = self.utf16_lines.get(&line) {
             for c in utf16_chars {
                 if col > u32::from(c.start) {
-                    col += u32::from(c.len()) - c.len_utf16() as u32;
+                    col = col.saturating_add(u32::from(c.len()) - c.len_utf16() as u32);
                 } else {
                     // From here on, all utf16 characters come *after* the character we are mapping,
                     // so we don't need to take them into account
@@ -187,6 +219,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::zero_prefixed_literal)]
     fn test_line_index() {
         let text = "hello\nworld";
         let table = [
@@ -201,19 +234,37 @@ mod tests {
             (12, 1, 6),
         ];
 
-        let index = LineIndex::new(text);
+        let index = LineIndex::new(text, PositionEncoding::Utf16);
         for &(offset, line, col) in &table {
             assert_eq!(index.line_col(offset.into()), LineCol { line, col });
         }
 
         let text = "\nhello\nworld";
         let table = [(0, 0, 0), (1, 1, 0), (2, 1, 1), (6, 1, 5), (7, 2, 0)];
-        let index = LineIndex::new(text);
+        let index = LineIndex::new(text, PositionEncoding::Utf16);
         for &(offset, line, col) in &table {
             assert_eq!(index.line_col(offset.into()), LineCol { line, col });
         }
     }
 
+    #[test]
+    fn test_offset() {
+        let text = "ab\nメc\n𐐏";
+        let index = LineIndex::new(text, PositionEncoding::Utf16);
+        let offset = |line, col| index.offset(index.to_utf8(LineColUtf16 { line, col }));
+
+        assert_eq!(offset(0, 1), TextSize::from(1));
+        assert_eq!(offset(1, 1), TextSize::from(6));
+        // Past the end of a line or of the text.
+        assert_eq!(offset(0, 10), TextSize::from(2));
+        assert_eq!(offset(2, 10), TextSize::from(12));
+        assert_eq!(offset(5, 0), TextSize::from(12));
+        assert_eq!(offset(0, u32::MAX), TextSize::from(2));
+        // Inside a character.
+        assert_eq!(offset(2, 1), TextSize::from(8));
+        assert_eq!(index.offset(LineCol { line: 1, col: 1 }), TextSize::from(3));
+    }
+
     #[test]
     fn test_char_len() {
         assert_eq!('メ'.len_utf8(), 3);
@@ -226,6 +277,7 @@ mod tests {
             "
 const C: char = 'x';
 ",
+            PositionEncoding::Utf16,
         );
         assert_eq!(col_index.utf16_lines.len(), 0);
     }
@@ -236,6 +288,7 @@ const C: char = 'x';
             "
 const C: char = 'メ';
 ",
+            PositionEncoding::Utf16,
         );
 
         assert_eq!(col_index.utf16_lines.len(), 1);
@@ -260,7 +313,7 @@ const C: char = 'メ';
         // UTF-16 to UTF-8
         assert_eq!(col_index.utf16_to_utf8_col(1, 19), TextSize::from(21));
 
-        let col_index = LineIndex::new("a𐐏b");
+        let col_index = LineIndex::new("a𐐏b", PositionEncoding::Utf16);
         assert_eq!(col_index.utf16_to_utf8_col(0, 3), TextSize::from(5));
     }
 
@@ -270,6 +323,7 @@ const C: char = 'メ';
             "
 const C: char = \"メ メ\";
 ",
+            PositionEncoding::Utf16,
         );
 
         assert_eq!(col_index.utf16_lines.len(), 1);
@@ -315,24 +369,24 @@ const C: char = \"メ メ\";
         }
 
         let text = "a\nbb\nccc\n";
-        let line_index = LineIndex::new(text);
+        let line_index = LineIndex::new(text, PositionEncoding::Utf16);
 
         let actual = line_index.lines(r(0, 9)).collect::<Vec<_>>();
         let expected = vec![r(0, 2), r(2, 5), r(5, 9)];
         assert_eq!(actual, expected);
 
         let text = "";
-        let line_index = LineIndex::new(text);
+        let line_index = LineIndex::new(text, PositionEncoding::Utf16);
 
         let actual = line_index.lines(r(0, 0)).collect::<Vec<_>>();
         let expected = vec![];
         assert_eq!(actual, expected);
 
         let text = "\n";
-        let line_index = LineIndex::new(text);
+        let line_index = LineIndex::new(text, PositionEncoding::Utf16);
 
         let actual = line_index.lines(r(0, 1)).collect::<Vec<_>>();
         let expected = vec![r(0, 1)];
-        assert_eq!(actual, expected)
+        assert_eq!(actual, expected);
     }
 }
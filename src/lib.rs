@@ -27,7 +27,6 @@
     clippy::rest_pat_in_fully_bound_structs,
     clippy::same_name_method,
     clippy::string_slice,
-    clippy::string_to_string,
     clippy::str_to_string,
     clippy::undocumented_unsafe_blocks,
     clippy::unreachable,
@@ -43,11 +42,39 @@
     clippy::missing_panics_doc
 )]
 
+mod analysis;
+mod assist;
+mod command;
+mod completion;
+mod config;
+mod convert;
 mod ctx;
+mod diagnostics;
+mod document_link;
 mod error;
+mod extract;
 mod fmt;
+mod front_matter;
+mod glob;
+mod highlight;
+mod hover;
+mod ignore;
+mod image;
 mod index;
+mod lint;
+mod missing;
+mod numbering;
 mod proto;
+mod schema;
+mod selection;
+mod semantic_tokens;
+mod slug;
+mod spell;
+mod symbols;
+mod syntax;
+mod task;
+mod toc;
+mod workspace;
 
 pub mod handle;
 
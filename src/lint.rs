@@ -0,0 +1,1455 @@
+//! Style checks in the spirit of markdownlint.
+//!
+//! Rules have the IDs and aliases of their markdownlint counterparts, so
+//! existing configurations carry over, and the ones without a counterpart
+//! have `MDLS` IDs. Every rule is enabled by default, and can be disabled or
+//! configured by ID or alias, e.g. `{ "MD013": { "lineLength": 100 },
+//! "no-hard-tabs": false }`, or disabled for the whole configuration with
+//! `"default": false`.
+//!
+//! Rules can also be disabled inline:
+//!
+//! - `<!-- mdls-disable MD013 -->` until a matching `<!-- mdls-enable -->`,
+//! - `<!-- mdls-disable-line MD013 -->` on its own line,
+//! - `<!-- mdls-disable-next-line MD013 -->` on the line after it.
+//!
+//! Without any rules, the comments apply to all of them.
+
+use std::collections::BTreeMap;
+
+use hashbrown::HashSet;
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use serde::{de::DeserializeOwned, Deserialize};
+use text_edit::{TextEdit, TextRange, TextSize};
+
+use crate::{
+    analysis::{self, parser_options, text_range, Analysis, Heading, LinkKind},
+    diagnostics::{Diagnostic, Fix, Severity},
+    extract::set_level,
+    front_matter,
+    slug::SlugStyle,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rule {
+    pub(crate) id: &'static str,
+    pub(crate) alias: &'static str,
+}
+
+impl Rule {
+    fn is_named(self, name: &str) -> bool {
+        self.id.eq_ignore_ascii_case(name) || self.alias.eq_ignore_ascii_case(name)
+    }
+}
+
+pub(crate) const HEADING_INCREMENT: Rule = Rule {
+    id: "MD001",
+    alias: "heading-increment",
+};
+pub(crate) const LIST_MARKER_STYLE: Rule = Rule {
+    id: "MD004",
+    alias: "ul-style",
+};
+pub(crate) const TRAILING_SPACES: Rule = Rule {
+    id: "MD009",
+    alias: "no-trailing-spaces",
+};
+pub(crate) const HARD_TABS: Rule = Rule {
+    id: "MD010",
+    alias: "no-hard-tabs",
+};
+pub(crate) const LINE_LENGTH: Rule = Rule {
+    id: "MD013",
+    alias: "line-length",
+};
+pub(crate) const BLANKS_AROUND_HEADINGS: Rule = Rule {
+    id: "MD022",
+    alias: "blanks-around-headings",
+};
+pub(crate) const DUPLICATE_HEADINGS: Rule = Rule {
+    id: "MD024",
+    alias: "no-duplicate-heading",
+};
+pub(crate) const SINGLE_H1: Rule = Rule {
+    id: "MD025",
+    alias: "single-h1",
+};
+pub(crate) const BLANKS_AROUND_FENCES: Rule = Rule {
+    id: "MD031",
+    alias: "blanks-around-fences",
+};
+pub(crate) const BLANKS_AROUND_LISTS: Rule = Rule {
+    id: "MD032",
+    alias: "blanks-around-lists",
+};
+pub(crate) const BARE_URLS: Rule = Rule {
+    id: "MD034",
+    alias: "no-bare-urls",
+};
+pub(crate) const EMPHASIS_AS_HEADING: Rule = Rule {
+    id: "MD036",
+    alias: "no-emphasis-as-heading",
+};
+
+pub(crate) const NO_ALT_TEXT: Rule = Rule {
+    id: "MD045",
+    alias: "no-alt-text",
+};
+pub(crate) const DESCRIPTIVE_LINK_TEXT: Rule = Rule {
+    id: "MD059",
+    alias: "descriptive-link-text",
+};
+
+pub(crate) const MISSING_H1: Rule = Rule {
+    id: "MDLS001",
+    alias: "missing-h1",
+};
+pub(crate) const TABLE_HEADER: Rule = Rule {
+    id: "MDLS002",
+    alias: "table-header",
+};
+
+/// The configuration of the rules by ID or alias, which is either whether
+/// the rule is enabled or its options.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct LintConfig {
+    rules: BTreeMap<String, serde_json::Value>,
+}
+
+impl LintConfig {
+    fn is_enabled(&self, rule: Rule) -> bool {
+        self.config(rule).as_bool() != Some(false)
+    }
+
+    /// Returns the options of a rule, or `None` if it is disabled.
+    fn options<T: Default + DeserializeOwned>(&self, rule: Rule) -> Option<T> {
+        match self.config(rule) {
+            serde_json::Value::Bool(false) => None,
+            options @ serde_json::Value::Object(_) => Some(
+                serde_json::from_value(options.clone()).unwrap_or_else(|error| {
+                    tracing::warn!("invalid options for {}: {}", rule.id, error);
+                    T::default()
+                }),
+            ),
+            _ => Some(T::default()),
+        }
+    }
+
+    fn config(&self, rule: Rule) -> &serde_json::Value {
+        self.rules
+            .iter()
+            .find(|(name, _)| rule.is_named(name))
+            .or_else(|| self.rules.iter().find(|(name, _)| *name == "default"))
+            .map_or(&serde_json::Value::Null, |(_, config)| config)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MarkerStyle {
+    /// The same marker as the first list item.
+    #[default]
+    Consistent,
+    Dash,
+    Asterisk,
+    Plus,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ListMarkerOptions {
+    style: MarkerStyle,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct TrailingSpacesOptions {
+    /// The number of trailing spaces allowed for a hard line break.
+    br_spaces: usize,
+}
+
+impl Default for TrailingSpacesOptions {
+    fn default() -> Self {
+        Self { br_spaces: 2 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct HardTabsOptions {
+    code_blocks: bool,
+}
+
+impl Default for HardTabsOptions {
+    fn default() -> Self {
+        Self { code_blocks: true }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct LineLengthOptions {
+    line_length: usize,
+    code_blocks: bool,
+    tables: bool,
+    headings: bool,
+}
+
+impl Default for LineLengthOptions {
+    fn default() -> Self {
+        Self {
+            line_length: 80,
+            code_blocks: true,
+            tables: true,
+            headings: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DuplicateHeadingsOptions {
+    /// Whether only headings with the same parent are compared, which is the
+    /// default unlike in markdownlint.
+    siblings_only: bool,
+}
+
+impl Default for DuplicateHeadingsOptions {
+    fn default() -> Self {
+        Self {
+            siblings_only: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct MissingH1Options {
+    /// The front matter key that gives the document a title in place of an
+    /// h1, or empty to always require an h1.
+    front_matter_title: String,
+}
+
+impl Default for MissingH1Options {
+    fn default() -> Self {
+        Self {
+            front_matter_title: "title".to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct SingleH1Options {
+    level: usize,
+}
+
+impl Default for SingleH1Options {
+    fn default() -> Self {
+        Self { level: 1 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct EmphasisAsHeadingOptions {
+    /// Emphasized paragraphs ending with one of these are sentences.
+    punctuation: String,
+}
+
+impl Default for EmphasisAsHeadingOptions {
+    fn default() -> Self {
+        Self {
+            punctuation: ".,;:!?。，；：！？".to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DescriptiveLinkTextOptions {
+    /// Link texts that don't say where the link goes, compared ignoring case
+    /// and surrounding punctuation.
+    prohibited_texts: Vec<String>,
+}
+
+impl Default for DescriptiveLinkTextOptions {
+    fn default() -> Self {
+        Self {
+            prohibited_texts: ["click here", "here", "link", "more"]
+                .map(ToOwned::to_owned)
+                .to_vec(),
+        }
+    }
+}
+
+/// An `mdls-disable` or `mdls-enable` comment.
+#[derive(Clone, Debug)]
+struct Directive {
+    line: usize,
+    kind: DirectiveKind,
+    /// The rules it applies to, or all of them if empty.
+    rules: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DirectiveKind {
+    Disable,
+    Enable,
+    DisableLine,
+    DisableNextLine,
+}
+
+impl Directive {
+    fn applies_to(&self, rule: Rule) -> bool {
+        self.rules.is_empty() || self.rules.iter().any(|name| rule.is_named(name))
+    }
+}
+
+/// The structure of a document that the rules look at.
+#[derive(Debug, Default)]
+struct Document {
+    /// The lines, without line breaks.
+    lines: Vec<TextRange>,
+    /// The first line after the front matter.
+    first_line: usize,
+    /// Fenced and indented code blocks, and whether they are fenced.
+    code_blocks: Vec<(TextRange, bool)>,
+    tables: Vec<TextRange>,
+    /// The header rows of the tables.
+    table_heads: Vec<TextRange>,
+    /// Lists that aren't nested in other lists.
+    lists: Vec<TextRange>,
+    /// The markers of unordered list items.
+    bullets: Vec<(TextSize, char)>,
+    /// Text outside of links, with adjacent text events merged.
+    texts: Vec<TextRange>,
+    /// Paragraphs consisting of a single emphasized line, and their text.
+    emphasized: Vec<(TextRange, String)>,
+    /// Inline HTML and HTML blocks, with adjacent pieces merged.
+    html: Vec<TextRange>,
+    directives: Vec<Directive>,
+}
+
+impl Document {
+    #[allow(clippy::string_slice)]
+    fn new(text: &str) -> Self {
+        let mut document = Self::default();
+        let mut start = 0;
+        for line in text.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\n', '\r']);
+            document
+                .lines
+                .push(text_range(start..start + content.len()));
+            start += line.len();
+        }
+        if let Some(front_matter) = analysis::front_matter(text) {
+            document.first_line = document.line(front_matter.end());
+        }
+
+        let mut list_depth = 0;
+        // Whether the lists being parsed are ordered.
+        let mut ordered = Vec::new();
+        let mut link_depth = 0;
+        let mut in_code_block = false;
+        let mut text_run: Option<std::ops::Range<usize>> = None;
+        // The paragraph being parsed, if it still looks like a heading.
+        let mut paragraph: Option<EmphasizedParagraph> = None;
+
+        for (event, range) in Parser::new_ext(text, parser_options()).into_offset_iter() {
+            if let Some(candidate) = &mut paragraph {
+                if let Event::End(Tag::Paragraph) = event {
+                    if candidate.is_heading_like() && !text[range.clone()].trim().contains('\n') {
+                        let text = std::mem::take(&mut candidate.text);
+                        document.emphasized.push((text_range(range.clone()), text));
+                    }
+                    paragraph = None;
+                } else {
+                    candidate.push(&event);
+                }
+            }
+
+            match &event {
+                Event::Text(_) if link_depth == 0 && !in_code_block => match &mut text_run {
+                    Some(run) if run.end == range.start => run.end = range.end,
+                    _ => {
+                        document.texts.extend(text_run.take().map(text_range));
+                        text_run = Some(range.clone());
+                    }
+                },
+                _ => document.texts.extend(text_run.take().map(text_range)),
+            }
+
+            match event {
+                Event::Start(Tag::CodeBlock(kind)) => {
+                    let fenced = matches!(kind, CodeBlockKind::Fenced(_));
+                    document.code_blocks.push((text_range(range), fenced));
+                    in_code_block = true;
+                }
+                Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+                Event::Start(Tag::Table(_)) => document.tables.push(text_range(range)),
+                Event::Start(Tag::TableHead) => document.table_heads.push(text_range(range)),
+                Event::Start(Tag::List(first)) => {
+                    if list_depth == 0 {
+                        document.lists.push(text_range(range));
+                    }
+                    list_depth += 1;
+                    ordered.push(first.is_some());
+                }
+                Event::End(Tag::List(_)) => {
+                    list_depth -= 1;
+                    ordered.pop();
+                }
+                Event::Start(Tag::Item) if ordered.last() == Some(&false) => {
+                    if let Some(marker @ ('-' | '*' | '+')) = text[range.start..].chars().next() {
+                        let offset = TextSize::try_from(range.start).expect("offset fits");
+                        document.bullets.push((offset, marker));
+                    }
+                }
+                Event::Start(Tag::Link(..) | Tag::Image(..)) => link_depth += 1,
+                Event::End(Tag::Link(..) | Tag::Image(..)) => link_depth -= 1,
+                Event::Start(Tag::Paragraph) if list_depth == 0 => {
+                    paragraph = Some(EmphasizedParagraph::default());
+                }
+                Event::Html(_) => document.push_html(text, text_range(range)),
+                _ => {}
+            }
+        }
+        document.texts.extend(text_run.map(text_range));
+        document
+    }
+
+    #[allow(clippy::string_slice)]
+    fn push_html(&mut self, text: &str, range: TextRange) {
+        let line = self.line(range.start());
+        self.directives
+            .extend(directives(&text[range]).map(|(kind, rules)| Directive { line, kind, rules }));
+        match self.html.last_mut() {
+            Some(last) if last.end() == range.start() => *last = last.cover(range),
+            _ => self.html.push(range),
+        }
+    }
+
+    /// Returns the line containing an offset.
+    fn line(&self, offset: TextSize) -> usize {
+        self.lines
+            .partition_point(|line| line.start() <= offset)
+            .saturating_sub(1)
+    }
+
+    #[allow(clippy::string_slice)]
+    fn is_blank(&self, text: &str, line: usize) -> bool {
+        self.lines.get(line).is_some_and(|&range| {
+            text[range]
+                .trim_start_matches(|c: char| c == '>' || c.is_whitespace())
+                .is_empty()
+        })
+    }
+
+    fn in_code_block(&self, offset: TextSize) -> bool {
+        self.code_blocks
+            .iter()
+            .any(|(range, _)| range.contains(offset))
+    }
+
+    fn is_suppressed(&self, rule: Rule, line: usize) -> bool {
+        let mut disabled = false;
+        for directive in &self.directives {
+            if directive.line > line || !directive.applies_to(rule) {
+                continue;
+            }
+            match directive.kind {
+                DirectiveKind::Disable => disabled = true,
+                DirectiveKind::Enable => disabled = false,
+                DirectiveKind::DisableLine if directive.line == line => return true,
+                DirectiveKind::DisableNextLine if directive.line + 1 == line => return true,
+                DirectiveKind::DisableLine | DirectiveKind::DisableNextLine => {}
+            }
+        }
+        disabled
+    }
+}
+
+/// Tracks whether a paragraph consists of a single emphasized span.
+#[derive(Debug, Default)]
+struct EmphasizedParagraph {
+    depth: usize,
+    closed: bool,
+    failed: bool,
+    text: String,
+}
+
+impl EmphasizedParagraph {
+    fn push(&mut self, event: &Event<'_>) {
+        match event {
+            Event::Start(Tag::Emphasis | Tag::Strong) if !self.closed => self.depth += 1,
+            Event::End(Tag::Emphasis | Tag::Strong) if self.depth > 0 => {
+                self.depth -= 1;
+                self.closed = self.depth == 0;
+            }
+            Event::Text(text) | Event::Code(text) if self.depth > 0 => self.text.push_str(text),
+            _ if self.depth > 0 => {}
+            _ => self.failed = true,
+        }
+    }
+
+    fn is_heading_like(&self) -> bool {
+        self.closed && !self.failed
+    }
+}
+
+/// Parses the `mdls-*` comments in a piece of HTML.
+fn directives(html: &str) -> impl Iterator<Item = (DirectiveKind, Vec<String>)> + '_ {
+    html.split("<!--").skip(1).filter_map(|comment| {
+        let (comment, _) = comment.split_once("-->")?;
+        let mut words = comment.split_whitespace();
+        let kind = match words.next()? {
+            "mdls-disable" => DirectiveKind::Disable,
+            "mdls-enable" => DirectiveKind::Enable,
+            "mdls-disable-line" => DirectiveKind::DisableLine,
+            "mdls-disable-next-line" => DirectiveKind::DisableNextLine,
+            _ => return None,
+        };
+        Some((kind, words.map(ToOwned::to_owned).collect()))
+    })
+}
+
+/// Runs the enabled rules on a document.
+pub(crate) fn lint(
+    text: &str,
+    analysis: &Analysis,
+    slug: SlugStyle,
+    config: &LintConfig,
+) -> Vec<Diagnostic> {
+    let document = Document::new(text);
+    let mut problems = Vec::new();
+    let mut report = |rule: Rule, range: TextRange, message: String, fix: Option<Fix>| {
+        problems.push((rule, range, message, fix));
+    };
+
+    let front_matter_end = document
+        .lines
+        .get(document.first_line)
+        .map_or(TextSize::of(text), |line| line.start());
+    let headings = analysis.headings.iter().collect::<Vec<_>>();
+
+    heading_levels(text, &headings, config, &mut report);
+    top_level_headings(text, &document, &headings, config, &mut report);
+    if let Some(options) = config.options(DUPLICATE_HEADINGS) {
+        duplicate_headings(&headings, &options, slug, text, &mut report);
+    }
+    if config.is_enabled(BLANKS_AROUND_HEADINGS) {
+        let ranges = headings.iter().map(|heading| heading.range);
+        blanks_around(
+            text,
+            &document,
+            BLANKS_AROUND_HEADINGS,
+            "heading",
+            ranges,
+            &mut report,
+        );
+    }
+    if config.is_enabled(BLANKS_AROUND_FENCES) {
+        let ranges = document
+            .code_blocks
+            .iter()
+            .filter(|(_, fenced)| *fenced)
+            .map(|(range, _)| *range);
+        blanks_around(
+            text,
+            &document,
+            BLANKS_AROUND_FENCES,
+            "code block",
+            ranges,
+            &mut report,
+        );
+    }
+    if config.is_enabled(BLANKS_AROUND_LISTS) {
+        let ranges = document.lists.iter().copied();
+        blanks_around(
+            text,
+            &document,
+            BLANKS_AROUND_LISTS,
+            "list",
+            ranges,
+            &mut report,
+        );
+    }
+    if let Some(options) = config.options(TRAILING_SPACES) {
+        trailing_spaces(text, &document, &options, &mut report);
+    }
+    if let Some(options) = config.options(HARD_TABS) {
+        hard_tabs(text, &document, &options, &mut report);
+    }
+    if let Some(options) = config.options(LINE_LENGTH) {
+        line_length(text, analysis, &document, &options, &mut report);
+    }
+    if let Some(options) = config.options(LIST_MARKER_STYLE) {
+        list_markers(&document, &options, &mut report);
+    }
+    if config.is_enabled(BARE_URLS) {
+        bare_urls(text, &document, &mut report);
+    }
+    if let Some(options) = config.options(EMPHASIS_AS_HEADING) {
+        emphasis_as_heading(text, &document, &options, &mut report);
+    }
+    accessibility(text, analysis, &document, config, &mut report);
+
+    problems
+        .into_iter()
+        .filter(|(rule, range, _, _)| {
+            range.start() >= front_matter_end
+                && !document.is_suppressed(*rule, document.line(range.start()))
+        })
+        .map(|(rule, range, message, fix)| {
+            let mut diagnostic = Diagnostic::new(range, Severity::Warning, rule.id, message);
+            diagnostic.fixes.extend(fix);
+            diagnostic
+        })
+        .collect()
+}
+
+/// Reports skipped heading levels, with a fix that promotes the heading and
+/// its subheadings to close the gap.
+fn heading_levels(
+    text: &str,
+    headings: &[&Heading],
+    config: &LintConfig,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    if !config.is_enabled(HEADING_INCREMENT) {
+        return;
+    }
+    for (index, pair) in headings.windows(2).enumerate() {
+        let (previous, heading) = (pair[0], pair[1]);
+        if heading.level > previous.level + 1 {
+            let level = previous.level + 1;
+            report(
+                HEADING_INCREMENT,
+                first_line(text, heading.range),
+                format!(
+                    "heading levels should only increase by one, expected h{level} after h{}",
+                    previous.level
+                ),
+                shift_section(text, headings, index + 1, level),
+            );
+        }
+    }
+}
+
+/// Reports documents with more than one top-level heading, or without an h1.
+#[allow(clippy::string_slice)]
+fn top_level_headings(
+    text: &str,
+    document: &Document,
+    headings: &[&Heading],
+    config: &LintConfig,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    if let Some(options) = config.options::<SingleH1Options>(SINGLE_H1) {
+        let top_level = headings
+            .iter()
+            .enumerate()
+            .filter(|(_, heading)| heading.level == options.level)
+            .skip(1);
+        for (index, heading) in top_level {
+            report(
+                SINGLE_H1,
+                first_line(text, heading.range),
+                format!("multiple h{} headings in the document", options.level),
+                shift_section(text, headings, index, options.level + 1),
+            );
+        }
+    }
+
+    let Some(options) = config.options::<MissingH1Options>(MISSING_H1) else {
+        return;
+    };
+    if headings.iter().any(|heading| heading.level == 1)
+        || has_front_matter_key(text, &options.front_matter_title)
+    {
+        return;
+    }
+    let Some(&line) = document.lines[document.first_line..]
+        .iter()
+        .find(|&&line| !text[line].trim().is_empty())
+    else {
+        return;
+    };
+    // The first heading becomes the title if it has no siblings.
+    let fix = headings
+        .first()
+        .filter(|first| headings[1..].iter().all(|other| other.level > first.level))
+        .and_then(|_| shift_section(text, headings, 0, 1));
+    report(
+        MISSING_H1,
+        first_line(text, line),
+        "document has no h1 heading".to_owned(),
+        fix,
+    );
+}
+
+/// Reports headings that produce the same anchor as an earlier one.
+///
+/// Renderers number duplicate anchors in document order, so `#anchor` links
+/// written for one heading silently move when the headings are reordered.
+fn duplicate_headings(
+    headings: &[&Heading],
+    options: &DuplicateHeadingsOptions,
+    slug: SlugStyle,
+    text: &str,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    let mut seen = HashSet::new();
+    // The headings containing the current one, as indices.
+    let mut parents = Vec::<usize>::new();
+    for (index, heading) in headings.iter().enumerate() {
+        while parents
+            .last()
+            .is_some_and(|&parent| headings[parent].level >= heading.level)
+        {
+            parents.pop();
+        }
+        let parent = parents.last().copied().filter(|_| options.siblings_only);
+        parents.push(index);
+
+        let anchor = if heading.explicit {
+            heading.anchor.clone()
+        } else {
+            slug.slug(&heading.text)
+        };
+        if anchor.is_empty() || seen.insert((parent, anchor.clone())) {
+            continue;
+        }
+        let scope = if options.siblings_only {
+            "a sibling heading"
+        } else {
+            "another heading"
+        };
+        report(
+            DUPLICATE_HEADINGS,
+            first_line(text, heading.range),
+            format!("{scope} has the same anchor `#{anchor}`, which makes links to it ambiguous"),
+            None,
+        );
+    }
+}
+
+/// Returns whether the front matter sets a top-level key.
+fn has_front_matter_key(text: &str, key: &str) -> bool {
+    front_matter::parse(text)
+        .is_some_and(|front_matter| front_matter.entries.iter().any(|entry| entry.key == key))
+}
+
+/// Moves a heading to another level along with its subheadings, or returns
+/// `None` if that would take one of them past h6.
+fn shift_section(text: &str, headings: &[&Heading], index: usize, level: usize) -> Option<Fix> {
+    let heading = headings[index];
+    let subheadings = headings[index + 1..]
+        .iter()
+        .take_while(|other| other.level > heading.level)
+        .count();
+
+    let mut edit = TextEdit::builder();
+    for other in &headings[index..=index + subheadings] {
+        let new_level = other.level + level - heading.level;
+        if new_level > 6 {
+            return None;
+        }
+        for indel in set_level(text, other, new_level) {
+            edit.replace(indel.delete, indel.insert);
+        }
+    }
+    let verb = if level < heading.level {
+        "Promote"
+    } else {
+        "Demote"
+    };
+    let title = if subheadings == 0 {
+        format!("{verb} to h{level}")
+    } else {
+        format!("{verb} to h{level} with subheadings")
+    };
+    Some(fix(title, edit.finish()))
+}
+
+#[allow(clippy::string_slice)]
+fn hard_tabs(
+    text: &str,
+    document: &Document,
+    options: &HardTabsOptions,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    for &line in &document.lines[document.first_line..] {
+        if !options.code_blocks && document.in_code_block(line.start()) {
+            continue;
+        }
+        for (start, tabs) in runs(&text[line], '\t') {
+            let start = line.start() + TextSize::try_from(start).expect("offset fits");
+            let range = TextRange::at(start, TextSize::try_from(tabs).expect("length fits"));
+            // Tabs can be significant in code, e.g. in makefiles.
+            let spaces = (!document.in_code_block(line.start())).then(|| {
+                let column = columns(&text[TextRange::new(line.start(), start)]);
+                let end = columns(&text[TextRange::new(line.start(), range.end())]);
+                " ".repeat(end - column)
+            });
+            report(
+                HARD_TABS,
+                range,
+                "hard tab".to_owned(),
+                spaces.map(|spaces| {
+                    fix(
+                        "Replace tabs with spaces".to_owned(),
+                        TextEdit::replace(range, spaces),
+                    )
+                }),
+            );
+        }
+    }
+}
+
+fn list_markers(
+    document: &Document,
+    options: &ListMarkerOptions,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    let expected = match options.style {
+        MarkerStyle::Consistent => document.bullets.first().map(|&(_, marker)| marker),
+        MarkerStyle::Dash => Some('-'),
+        MarkerStyle::Asterisk => Some('*'),
+        MarkerStyle::Plus => Some('+'),
+    };
+    for &(offset, marker) in &document.bullets {
+        let Some(expected) = expected.filter(|&expected| expected != marker) else {
+            continue;
+        };
+        let range = TextRange::at(offset, TextSize::from(1));
+        report(
+            LIST_MARKER_STYLE,
+            range,
+            format!("expected `{expected}` as the list marker, found `{marker}`"),
+            Some(fix(
+                format!("Use `{expected}` as the list marker"),
+                TextEdit::replace(range, expected.to_string()),
+            )),
+        );
+    }
+}
+
+#[allow(clippy::string_slice)]
+fn bare_urls(
+    text: &str,
+    document: &Document,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    for &range in &document.texts {
+        for url in urls(&text[range]) {
+            let url = url + range.start();
+            let mut edit = TextEdit::builder();
+            edit.insert(url.start(), "<".to_owned());
+            edit.insert(url.end(), ">".to_owned());
+            report(
+                BARE_URLS,
+                url,
+                "bare URL, wrap it in `<>` to make it a link".to_owned(),
+                Some(fix("Wrap URL in `<>`".to_owned(), edit.finish())),
+            );
+        }
+    }
+}
+
+fn emphasis_as_heading(
+    text: &str,
+    document: &Document,
+    options: &EmphasisAsHeadingOptions,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    for (range, content) in &document.emphasized {
+        let content = content.trim_end();
+        if content.is_empty() || content.ends_with(|c| options.punctuation.contains(c)) {
+            continue;
+        }
+        report(
+            EMPHASIS_AS_HEADING,
+            first_line(text, *range),
+            "emphasis used instead of a heading".to_owned(),
+            None,
+        );
+    }
+}
+
+/// Reports images without alt text, links whose text doesn't describe where
+/// they go, and tables without a header, which all leave screen reader users
+/// guessing.
+#[allow(clippy::string_slice)]
+fn accessibility(
+    text: &str,
+    analysis: &Analysis,
+    document: &Document,
+    config: &LintConfig,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    if config.is_enabled(NO_ALT_TEXT) {
+        for link in &analysis.links {
+            if link.is_image && link.text.trim().is_empty() {
+                report(
+                    NO_ALT_TEXT,
+                    link.range,
+                    "image has no alt text".to_owned(),
+                    None,
+                );
+            }
+        }
+        for &range in &document.html {
+            for image in images_without_alt(&text[range]) {
+                report(
+                    NO_ALT_TEXT,
+                    image + range.start(),
+                    "`<img>` has no `alt` attribute".to_owned(),
+                    None,
+                );
+            }
+        }
+    }
+    if let Some(options) = config.options::<DescriptiveLinkTextOptions>(DESCRIPTIVE_LINK_TEXT) {
+        let normalize = |text: &str| {
+            let text = text.trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation());
+            text.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        };
+        let prohibited = options
+            .prohibited_texts
+            .iter()
+            .map(|text| normalize(text))
+            .collect::<Vec<_>>();
+        for link in &analysis.links {
+            if link.is_image || link.kind == LinkKind::Autolink {
+                continue;
+            }
+            let link_text = normalize(&link.text);
+            if !link_text.is_empty() && prohibited.contains(&link_text) {
+                report(
+                    DESCRIPTIVE_LINK_TEXT,
+                    link.range,
+                    format!(
+                        "link text `{}` doesn't say where the link goes",
+                        link.text.trim()
+                    ),
+                    None,
+                );
+            }
+        }
+    }
+    if config.is_enabled(TABLE_HEADER) {
+        for &head in &document.table_heads {
+            let row = text[head]
+                .trim()
+                .trim_start_matches('|')
+                .trim_end_matches('|');
+            if row.split('|').all(|cell| cell.trim().is_empty()) {
+                report(
+                    TABLE_HEADER,
+                    first_line(text, head),
+                    "table has an empty header row".to_owned(),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+/// Finds the `<img>` tags without an `alt` attribute, except the ones hidden
+/// from screen readers.
+#[allow(clippy::string_slice)]
+fn images_without_alt(html: &str) -> Vec<TextRange> {
+    // Lowercasing ASCII keeps the offsets.
+    let html = html.to_ascii_lowercase();
+    let mut images = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = html[offset..].find("<img") {
+        let start = offset + start;
+        let rest = &html[start + "<img".len()..];
+        offset = start + "<img".len();
+        if !rest.starts_with(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>') {
+            continue;
+        }
+        let (attributes, len) = attributes(rest);
+        offset += len;
+        let described = attributes
+            .iter()
+            .any(|&(name, value)| name == "alt" || (name == "aria-hidden" && value == "true"));
+        if !described {
+            images.push(text_range(start..offset));
+        }
+    }
+    images
+}
+
+/// Parses the attributes of an HTML tag, starting after the tag name, and
+/// returns them with the length of the rest of the tag.
+#[allow(clippy::string_slice)]
+fn attributes(tag: &str) -> (Vec<(&str, &str)>, usize) {
+    let mut attributes = Vec::new();
+    let mut rest = tag;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if rest.is_empty() || rest.starts_with('>') {
+            break;
+        }
+        let name_len = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len());
+        let name = &rest[..name_len];
+        rest = rest[name_len..].trim_start();
+        let mut value = "";
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            (value, rest) = if let Some(quote @ ('"' | '\'')) = after.chars().next() {
+                let quoted = &after[1..];
+                let end = quoted.find(quote).unwrap_or(quoted.len());
+                (&quoted[..end], quoted.get(end + 1..).unwrap_or_default())
+            } else {
+                let end = after
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(after.len());
+                after.split_at(end)
+            };
+        }
+        attributes.push((name, value));
+    }
+    let len = tag.len() - rest.len() + usize::from(rest.starts_with('>'));
+    (attributes, len)
+}
+
+/// Reports blocks that aren't separated from their surroundings by blank
+/// lines.
+#[allow(clippy::string_slice)]
+fn blanks_around(
+    text: &str,
+    document: &Document,
+    rule: Rule,
+    what: &str,
+    ranges: impl Iterator<Item = TextRange>,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    for range in ranges {
+        let content = text[range].trim_end();
+        if content.is_empty() {
+            continue;
+        }
+        let first = document.line(range.start());
+        let last = document.line(range.start() + TextSize::of(content));
+
+        // Blocks that start after a list marker continue the line before.
+        let prefix = TextRange::new(document.lines[first].start(), range.start());
+        let starts_line = text[prefix].chars().all(|c| c == '>' || c.is_whitespace());
+        if starts_line && first > document.first_line && !document.is_blank(text, first - 1) {
+            let line = document.lines[first];
+            report(
+                rule,
+                line,
+                format!("{what} should be preceded by a blank line"),
+                Some(fix(
+                    "Insert blank line".to_owned(),
+                    TextEdit::insert(line.start(), format!("{}\n", quote_prefix(&text[line]))),
+                )),
+            );
+        }
+        if last + 1 < document.lines.len() && !document.is_blank(text, last + 1) {
+            let line = document.lines[last];
+            report(
+                rule,
+                line,
+                format!("{what} should be followed by a blank line"),
+                Some(fix(
+                    "Insert blank line".to_owned(),
+                    TextEdit::insert(
+                        document.lines[last + 1].start(),
+                        format!("{}\n", quote_prefix(&text[line])),
+                    ),
+                )),
+            );
+        }
+    }
+}
+
+#[allow(clippy::string_slice)]
+fn trailing_spaces(
+    text: &str,
+    document: &Document,
+    options: &TrailingSpacesOptions,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    for &line in &document.lines[document.first_line..] {
+        let content = &text[line];
+        let trimmed = content.trim_end_matches([' ', '\t']);
+        let trailing = &content[trimmed.len()..];
+        if trailing.is_empty() || document.in_code_block(line.start()) {
+            continue;
+        }
+        let is_break = !trimmed.trim().is_empty()
+            && trailing.len() == options.br_spaces
+            && trailing.bytes().all(|b| b == b' ');
+        if is_break {
+            continue;
+        }
+        let range = TextRange::new(line.end() - TextSize::of(trailing), line.end());
+        report(
+            TRAILING_SPACES,
+            range,
+            "trailing whitespace".to_owned(),
+            Some(fix(
+                "Remove trailing whitespace".to_owned(),
+                TextEdit::delete(range),
+            )),
+        );
+    }
+}
+
+#[allow(clippy::string_slice)]
+fn line_length(
+    text: &str,
+    analysis: &Analysis,
+    document: &Document,
+    options: &LineLengthOptions,
+    report: &mut impl FnMut(Rule, TextRange, String, Option<Fix>),
+) {
+    let in_any = |mut ranges: std::slice::Iter<'_, TextRange>, offset| {
+        ranges.any(|range| range.contains(offset))
+    };
+    let headings = analysis
+        .headings
+        .iter()
+        .map(|heading| heading.range)
+        .collect::<Vec<_>>();
+    let definitions = analysis
+        .definitions
+        .iter()
+        .map(|definition| definition.range)
+        .collect::<Vec<_>>();
+    for &line in &document.lines[document.first_line..] {
+        let content = &text[line];
+        let Some((limit, _)) = content.char_indices().nth(options.line_length) else {
+            continue;
+        };
+        // Long words, such as URLs, can't be wrapped.
+        if !content[limit..].contains(char::is_whitespace) {
+            continue;
+        }
+        let offset = line.start();
+        let skip = (!options.code_blocks && document.in_code_block(offset))
+            || (!options.tables && in_any(document.tables.iter(), offset))
+            || (!options.headings && in_any(headings.iter(), offset))
+            || in_any(definitions.iter(), offset);
+        if skip {
+            continue;
+        }
+        let length = content.chars().count();
+        let start = offset + TextSize::try_from(limit).expect("offset fits");
+        report(
+            LINE_LENGTH,
+            TextRange::new(start, line.end()),
+            format!(
+                "line is {length} characters long, expected at most {}",
+                options.line_length
+            ),
+            None,
+        );
+    }
+}
+
+/// Lint fixes only change formatting, so they are all safe to apply at once.
+fn fix(title: String, edit: TextEdit) -> Fix {
+    Fix {
+        title,
+        edit,
+        safe: true,
+    }
+}
+
+/// Returns the `>` markers a line starts with, which a blank line in the same
+/// block quote needs too.
+fn quote_prefix(line: &str) -> &str {
+    let rest = line.trim_start_matches(['>', ' ']);
+    line.strip_suffix(rest).unwrap_or_default().trim_end()
+}
+
+/// Returns the width of a piece of a line, with tab stops every four columns.
+fn columns(text: &str) -> usize {
+    text.chars().fold(0, |column, c| {
+        if c == '\t' {
+            tab_stop(column)
+        } else {
+            column + 1
+        }
+    })
+}
+
+/// Returns the column a tab at a column advances to.
+fn tab_stop(column: usize) -> usize {
+    (column / 4 + 1) * 4
+}
+
+/// Returns the range of the first line of a block, without the line break.
+#[allow(clippy::string_slice)]
+fn first_line(text: &str, range: TextRange) -> TextRange {
+    let content = text[range].lines().next().unwrap_or_default();
+    TextRange::at(range.start(), TextSize::of(content.trim_end()))
+}
+
+/// Returns the start and length of every run of a character.
+#[allow(clippy::string_slice)]
+fn runs(line: &str, c: char) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let start = offset + line[offset..].find(c)?;
+        let len = line[start..].len() - line[start..].trim_start_matches(c).len();
+        offset = start + len;
+        Some((start, len))
+    })
+}
+
+/// Finds the `http://` and `https://` URLs in a piece of text.
+#[allow(clippy::string_slice)]
+pub(crate) fn urls(text: &str) -> Vec<TextRange> {
+    let mut urls = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| text[offset..].find(scheme))
+        .min()
+    {
+        let start = offset + start;
+        let rest = &text[start..];
+        let mut url = rest
+            .find(|c: char| c.is_whitespace() || c == '<' || c == '>')
+            .map_or(rest, |end| &rest[..end]);
+        // Trailing punctuation ends the sentence rather than the URL.
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"', '*', '_']);
+            let trimmed = match trimmed.strip_suffix(')') {
+                Some(inner) if !inner.contains('(') => inner,
+                _ => trimmed,
+            };
+            if trimmed.len() == url.len() {
+                break;
+            }
+            url = trimmed;
+        }
+        offset = start + url.len().max(1);
+        if url.len() > "https://".len() {
+            urls.push(text_range(start..start + url.len()));
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, diagnostics::fix_all, slug::SlugStyle};
+
+    fn problems_with<'a>(
+        text: &'a str,
+        config: &serde_json::Value,
+    ) -> Vec<(&'static str, &'a str)> {
+        let config = serde_json::from_value(config.clone()).unwrap();
+        let analysis = Analysis::new(text, SlugStyle::default());
+        lint(text, &analysis, SlugStyle::default(), &config)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code, &text[diagnostic.range]))
+            .collect()
+    }
+
+    fn problems(text: &str, rule: Rule) -> Vec<&str> {
+        let mut config = serde_json::json!({ "default": false });
+        config[rule.id] = true.into();
+        problems_with(text, &config)
+            .into_iter()
+            .map(|(_, range)| range)
+            .collect()
+    }
+
+    fn fixed(text: &str, rules: &[Rule]) -> String {
+        let mut lint = serde_json::json!({ "default": false });
+        for rule in rules {
+            lint[rule.id] = true.into();
+        }
+        let config = Config {
+            lint: serde_json::from_value(lint).unwrap(),
+            ..Config::default()
+        };
+        let mut fixed = text.to_owned();
+        fix_all(text, &config).apply(&mut fixed);
+        fixed
+    }
+
+    #[test]
+    fn test_headings() {
+        let text = "# A\n\n### B\n\n## C\n\n# D\nE\n\n---\n";
+        assert_eq!(problems(text, HEADING_INCREMENT), ["### B"]);
+        assert_eq!(problems(text, SINGLE_H1), ["# D"]);
+        assert_eq!(problems(text, BLANKS_AROUND_HEADINGS), ["# D"]);
+    }
+
+    #[test]
+    fn test_missing_h1() {
+        assert_eq!(problems("## A\n\n### B\n", MISSING_H1), ["## A"]);
+        assert_eq!(fixed("## A\n\n### B\n", &[MISSING_H1]), "# A\n\n## B\n");
+        assert_eq!(
+            fixed("Text\n\n## A\n\n## B\n", &[MISSING_H1]),
+            "Text\n\n## A\n\n## B\n"
+        );
+        assert!(problems("---\ntitle: X\n---\n\n## A\n", MISSING_H1).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_headings() {
+        let text = "# A\n\n## Setup\n\n### Linux\n\n## Usage\n\n### Linux\n\n## Setup {#setup}\n";
+        assert_eq!(problems(text, DUPLICATE_HEADINGS), ["## Setup {#setup}"]);
+        let config = serde_json::json!({ "default": false, "MD024": { "siblingsOnly": false } });
+        assert_eq!(
+            problems_with(text, &config),
+            [("MD024", "### Linux"), ("MD024", "## Setup {#setup}")]
+        );
+    }
+
+    #[test]
+    fn test_heading_fixes() {
+        assert_eq!(
+            fixed("# A\n\n### B\n\n#### C\n\n## D\n", &[HEADING_INCREMENT]),
+            "# A\n\n## B\n\n### C\n\n## D\n"
+        );
+        assert_eq!(
+            fixed("# A\n\n# B\n\nC\n-\n", &[SINGLE_H1]),
+            "# A\n\n## B\n\n### C\n"
+        );
+        let text = "# A\n\n# B\n\n###### C\n";
+        assert_eq!(fixed(text, &[SINGLE_H1]), text);
+    }
+
+    #[test]
+    fn test_blanks_around_blocks() {
+        let text =
+            "Text\n```\ncode\n```\n- a\n- b\n\n  ```\n  ok\n  ```\n\nafter\n> quote\n> - c\n";
+        assert_eq!(problems(text, BLANKS_AROUND_FENCES), ["```", "```"]);
+        assert_eq!(problems(text, BLANKS_AROUND_LISTS), ["- a", "> - c"]);
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let text = "a  \nb   \n\t c\t\n \n```\nd  \t\n```\n";
+        assert_eq!(problems(text, TRAILING_SPACES), ["   ", "\t", " "]);
+        assert_eq!(problems(text, HARD_TABS), ["\t", "\t", "\t"]);
+        let config =
+            serde_json::json!({ "default": false, "no-hard-tabs": { "codeBlocks": false } });
+        assert_eq!(problems_with(text, &config).len(), 2);
+    }
+
+    #[test]
+    fn test_line_length() {
+        let long = "word ".repeat(20);
+        let text = format!("{long}\n{}\n\n[a]: /a \"{long}\"\n", "x".repeat(100));
+        assert_eq!(problems(&text, LINE_LENGTH), ["word word word word "]);
+        let config = serde_json::json!({ "default": false, "MD013": { "lineLength": 120 } });
+        assert!(problems_with(&text, &config).is_empty());
+    }
+
+    #[test]
+    fn test_list_markers() {
+        let text = "- a\n  * b\n+ c\n\n1. d\n";
+        assert_eq!(problems(text, LIST_MARKER_STYLE), ["*", "+"]);
+        let config = serde_json::json!({ "default": false, "ul-style": { "style": "asterisk" } });
+        assert_eq!(
+            problems_with(text, &config),
+            [("MD004", "-"), ("MD004", "+")]
+        );
+    }
+
+    #[test]
+    fn test_bare_urls() {
+        let text = "See https://example.com/a_(b). Or <https://x.y>, [https://z](https://z) and `http://code`.\n";
+        assert_eq!(problems(text, BARE_URLS), ["https://example.com/a_(b)"]);
+    }
+
+    #[test]
+    fn test_emphasis_as_heading() {
+        let text = "**Intro**\n\n*A sentence.*\n\n**Not** alone\n\n- **item**\n";
+        assert_eq!(problems(text, EMPHASIS_AS_HEADING), ["**Intro**"]);
+    }
+
+    #[test]
+    fn test_fixes() {
+        assert_eq!(
+            fixed("# A\n\n### B\n\n#### C\n", &[HEADING_INCREMENT]),
+            "# A\n\n## B\n\n### C\n"
+        );
+        assert_eq!(
+            fixed(
+                "Text\n# A\n- b\n\n> x\n> ## Q\n> y\n",
+                &[BLANKS_AROUND_HEADINGS, BLANKS_AROUND_LISTS]
+            ),
+            "Text\n\n# A\n\n- b\n\n> x\n>\n> ## Q\n>\n> y\n"
+        );
+        assert_eq!(
+            fixed(
+                "a \tb\t\n\t c  \n```\n\tcode\n```\n",
+                &[TRAILING_SPACES, HARD_TABS]
+            ),
+            "a   b\n     c  \n```\n\tcode\n```\n"
+        );
+        assert_eq!(fixed("- a\n* b\n", &[LIST_MARKER_STYLE]), "- a\n- b\n");
+        assert_eq!(
+            fixed("See https://x.y, https://z.w.\n", &[BARE_URLS]),
+            "See <https://x.y>, <https://z.w>.\n"
+        );
+    }
+
+    #[test]
+    fn test_alt_text() {
+        let text = "![](a.png) ![ ](b.png) ![Logo](c.png)\n\n\
+                    <p><img src=\"d.png\"\n  width=\"10\"></p>\n\n\
+                    Inline <IMG SRC='e.png' ALT=''> <img src=f.png aria-hidden=true> <imgx>\n";
+        assert_eq!(
+            problems(text, NO_ALT_TEXT),
+            [
+                "![](a.png)",
+                "![ ](b.png)",
+                "<img src=\"d.png\"\n  width=\"10\">"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_descriptive_link_text() {
+        let text = "[Click here!](/a), [here](/b), [the guide](/c) and <https://d.e>\n";
+        assert_eq!(
+            problems(text, DESCRIPTIVE_LINK_TEXT),
+            ["[Click here!](/a)", "[here](/b)"]
+        );
+        let config = serde_json::json!({
+            "default": false,
+            "MD059": { "prohibitedTexts": ["the guide"] }
+        });
+        assert_eq!(problems_with(text, &config), [("MD059", "[the guide](/c)")]);
+    }
+
+    #[test]
+    fn test_table_header() {
+        let text = "| | |\n|-|-|\n|a|b|\n\n| A | B |\n|---|---|\n| a | b |\n";
+        assert_eq!(problems(text, TABLE_HEADER), ["| | |"]);
+    }
+
+    #[test]
+    fn test_directives() {
+        let text = "a \n<!-- mdls-disable no-trailing-spaces -->\nb \n<!-- mdls-enable -->\nc \n\
+                    <!-- mdls-disable-next-line MD009 -->\nd \ne \n";
+        assert_eq!(problems(text, TRAILING_SPACES).len(), 3);
+        let text = "---\ntitle: x \n---\n\n# A\n\nb\tc <!-- mdls-disable-line -->\n";
+        assert!(problems_with(text, &serde_json::json!({})).is_empty());
+    }
+}
@@ -27,7 +27,6 @@
     clippy::rest_pat_in_fully_bound_structs,
     clippy::same_name_method,
     clippy::string_slice,
-    clippy::string_to_string,
     clippy::str_to_string,
     clippy::undocumented_unsafe_blocks,
     clippy::unreachable,
@@ -38,7 +37,7 @@
     rustdoc::bare_urls
 )]
 
-use mmdls::{Error, Context, handle};
+use mmdls::{handle, Context, Error};
 
 use lsp_server::Message;
 use lsp_types::{notification, request};
@@ -67,12 +66,14 @@ fn main() -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
                                         },
                                         Err(error) => {
                                             let error: Error = error;
-                                            error.into()
+                                            error.into_response(request.id.clone())
                                         },
                                     }
-                                    Err(_) => {
-                                        todo!();
-                                    }
+                                    Err(error) => lsp_server::Response::new_err(
+                                        request.id.clone(),
+                                        lsp_server::ErrorCode::InvalidParams as i32,
+                                        error.to_string(),
+                                    ),
                                 }
                             )*
                             _ => lsp_server::Response::new_err(
@@ -87,6 +88,22 @@ fn main() -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
                 ctx.register_request(request.clone());
                 let response = dispatch! {
                     request::Formatting => handle::request::format,
+                    request::CodeActionRequest => handle::request::code_action,
+                    request::GotoDefinition => handle::request::goto_definition,
+                    request::References => handle::request::references,
+                    request::HoverRequest => handle::request::hover,
+                    request::Completion => handle::request::completion,
+                    request::DocumentLinkRequest => handle::request::document_link,
+                    request::DocumentLinkResolve => handle::request::document_link_resolve,
+                    request::SemanticTokensFullRequest => handle::request::semantic_tokens_full,
+                    request::SemanticTokensFullDeltaRequest => handle::request::semantic_tokens_full_delta,
+                    request::SemanticTokensRangeRequest => handle::request::semantic_tokens_range,
+                    request::SelectionRangeRequest => handle::request::selection_range,
+                    request::DocumentHighlightRequest => handle::request::document_highlight,
+                    request::DocumentSymbolRequest => handle::request::document_symbol,
+                    request::CodeLensRequest => handle::request::code_lens,
+                    request::CodeLensResolve => handle::request::code_lens_resolve,
+                    request::ExecuteCommand => handle::request::execute_command,
                 };
                 ctx.respond(response);
             }
@@ -114,6 +131,7 @@ fn main() -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
                 dispatch! {
                     notification::DidOpenTextDocument => handle::notification::did_open_text_document,
                     notification::DidChangeTextDocument => handle::notification::did_change_text_document,
+                    notification::DidChangeConfiguration => handle::notification::did_change_configuration,
                 }
             }
         }
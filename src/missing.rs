@@ -0,0 +1,231 @@
+//! Reporting broken links, and creating their targets.
+
+use std::{borrow::Cow, path::Path};
+
+use hashbrown::HashMap;
+use lsp_types::Url;
+use text_edit::{Indel, TextSize};
+
+use crate::{
+    analysis::{local_destination, Analysis, LinkKind},
+    diagnostics::{Diagnostic, Severity},
+    hover::is_markdown,
+    slug::SlugStyle,
+    workspace,
+};
+
+/// The diagnostic code of links to a file or heading that doesn't exist.
+pub(crate) const CODE: &str = "missing-target";
+
+/// Reports the links to files that don't exist, and to anchors that no
+/// heading of their document has.
+///
+/// `document_text` returns the text of a Markdown document, which is open or
+/// else read from disk.
+pub(crate) fn diagnostics<'a>(
+    uri: &Url,
+    analysis: &Analysis,
+    root: Option<&Path>,
+    slug: SlugStyle,
+    mut document_text: impl FnMut(&Url) -> Option<Cow<'a, str>>,
+) -> Vec<Diagnostic> {
+    // The documents linked to, which are often linked to more than once.
+    let mut targets: HashMap<Url, Option<Analysis>> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for link in &analysis.links {
+        // Undefined references are reported already, and autolinks are URLs
+        // or email addresses.
+        if link.kind == LinkKind::Autolink || (link.is_reference() && !link.resolved) {
+            continue;
+        }
+        let Some((path, anchor)) = local_destination(&link.destination) else {
+            continue;
+        };
+        let anchor = anchor.filter(|anchor| !anchor.is_empty());
+        let message = if path.is_empty() {
+            match anchor {
+                Some(anchor) if analysis.heading(anchor).is_none() => {
+                    format!("no heading for `#{anchor}`")
+                }
+                _ => continue,
+            }
+        } else {
+            let Some(mut target) = workspace::resolve(uri, path, root) else {
+                continue;
+            };
+            target.set_fragment(None);
+            target.set_query(None);
+            let Ok(file) = target.to_file_path() else {
+                continue;
+            };
+            if is_markdown(&file) {
+                let target_analysis = targets.entry(target).or_insert_with_key(|target| {
+                    document_text(target).map(|text| Analysis::new(&text, slug))
+                });
+                match (target_analysis, anchor) {
+                    (None, _) if !file.exists() => format!("`{path}` doesn't exist"),
+                    (Some(target_analysis), Some(anchor))
+                        if target_analysis.heading(anchor).is_none() =>
+                    {
+                        format!("no heading for `#{anchor}` in `{path}`")
+                    }
+                    _ => continue,
+                }
+            } else if file.exists() {
+                continue;
+            } else {
+                format!("`{path}` doesn't exist")
+            }
+        };
+        let range = link.destination_range.unwrap_or(link.range);
+        diagnostics.push(Diagnostic::new(range, Severity::Warning, CODE, message));
+    }
+    diagnostics
+}
+
+/// Returns the contents of a new document for a link, with a heading made from
+/// the link text, or from the file name if the link has no text.
+pub(crate) fn new_document(
+    link_text: &str,
+    path: &str,
+    anchor: Option<&str>,
+    slug: SlugStyle,
+) -> String {
+    let stem = path.rsplit('/').next().unwrap_or(path);
+    let stem = stem.rsplit_once('.').map_or(stem, |(stem, _)| stem);
+    let title = match link_text.trim() {
+        "" => stem,
+        text => text,
+    };
+    let mut contents = format!("# {title}\n");
+
+    let analysis = Analysis::new(&contents, slug);
+    if let Some(anchor) = anchor.filter(|anchor| analysis.heading(anchor).is_none()) {
+        append_heading(&contents, &analysis, slug, link_text, anchor).apply(&mut contents);
+    }
+    contents
+}
+
+/// Appends a heading that `#anchor` resolves to.
+///
+/// The heading is made from the link text if that gives the right anchor, or
+/// else from the anchor itself, and as a last resort gets an explicit id.
+pub(crate) fn append_heading(
+    text: &str,
+    analysis: &Analysis,
+    slug: SlugStyle,
+    link_text: &str,
+    anchor: &str,
+) -> Indel {
+    // A lone level 1 heading is the title, and the rest are below it.
+    let titles = analysis
+        .headings
+        .iter()
+        .filter(|heading| heading.level == 1)
+        .count();
+    let level = match analysis.headings.iter().map(|heading| heading.level).min() {
+        None => 1,
+        Some(1) if titles == 1 => 2,
+        Some(level) => level,
+    };
+    let hashes = "#".repeat(level);
+
+    let separator = if text.is_empty() || text.ends_with("\n\n") {
+        ""
+    } else if text.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+
+    let mut from_anchor = anchor.replace('-', " ");
+    if let Some(first) = from_anchor.chars().next() {
+        from_anchor.replace_range(..first.len_utf8(), &first.to_uppercase().to_string());
+    }
+    let candidates = [link_text.trim(), from_anchor.as_str()];
+    let heading = candidates
+        .iter()
+        .filter(|candidate| !candidate.is_empty())
+        .map(|candidate| format!("{separator}{hashes} {candidate}\n"))
+        .find(|heading| {
+            let text = format!("{text}{heading}");
+            let analysis = Analysis::new(&text, slug);
+            analysis
+                .headings
+                .last()
+                .is_some_and(|heading| heading.anchor == anchor)
+        })
+        .unwrap_or_else(|| format!("{separator}{hashes} {from_anchor} {{#{anchor}}}\n"));
+    Indel::insert(TextSize::of(text), heading)
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+
+    fn appended(text: &str, link_text: &str, anchor: &str) -> String {
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let mut text = text.to_owned();
+        append_heading(&text, &analysis, SlugStyle::default(), link_text, anchor).apply(&mut text);
+        text
+    }
+
+    #[test]
+    fn test_new_document() {
+        let slug = SlugStyle::default();
+        assert_eq!(
+            new_document("Getting started", "docs/start.md", None, slug),
+            "# Getting started\n"
+        );
+        assert_eq!(new_document(" ", "docs/start.md", None, slug), "# start\n");
+        assert_eq!(
+            new_document("Setup", "setup.md", Some("install"), slug),
+            "# Setup\n\n## Install\n"
+        );
+    }
+
+    #[test]
+    fn test_append_heading() {
+        assert_eq!(appended("", "Usage", "usage"), "# Usage\n");
+        assert_eq!(
+            appended("# Title\n\nText.", "the usage", "usage"),
+            "# Title\n\nText.\n\n## Usage\n"
+        );
+        assert_eq!(
+            appended("## A\n\n## B\n", "Usage", "usage"),
+            "## A\n\n## B\n\n## Usage\n"
+        );
+        assert_eq!(
+            appended("# A\n", "see here", "faq!"),
+            "# A\n\n## Faq! {#faq!}\n"
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let uri = Url::parse("file:///docs/a.md").unwrap();
+        let text = "# A\n\n[a](#a) [b](#b) [c](b.md) [d](b.md#d) [e](b.md#e) [f](c.md) \
+                    [g](/img.png) [h](https://example.com/#x)\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let b = Url::parse("file:///docs/b.md").unwrap();
+        let mut reads = 0;
+        let diagnostics = diagnostics(&uri, &analysis, None, SlugStyle::default(), |target| {
+            reads += 1;
+            (*target == b).then_some(Cow::Borrowed("# D\n"))
+        });
+        let reported = diagnostics
+            .iter()
+            .map(|diagnostic| (&text[diagnostic.range], diagnostic.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reported,
+            [
+                ("#b", "no heading for `#b`"),
+                ("b.md#e", "no heading for `#e` in `b.md`"),
+                ("c.md", "`c.md` doesn't exist"),
+            ]
+        );
+        assert_eq!(reads, 2);
+    }
+}
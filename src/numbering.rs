@@ -0,0 +1,197 @@
+//! Hierarchical heading numbers, such as `## 2.3 Design`.
+
+use text_edit::{TextEdit, TextRange, TextSize};
+
+use crate::{
+    analysis::{Analysis, Heading},
+    slug::SlugStyle,
+};
+
+/// The result of renumbering the headings of a document.
+#[derive(Clone, Debug)]
+pub(crate) struct Renumbering {
+    pub(crate) edit: TextEdit,
+    /// The old and new anchors of the headings whose anchor changed.
+    pub(crate) anchors: Vec<(String, String)>,
+}
+
+/// Adds or updates the numbers of the headings, or removes them.
+///
+/// A lone level 1 heading at the start is the title of the document and isn't
+/// numbered. The other headings are numbered by their nesting, so skipped
+/// levels don't produce numbers like `1.0.1`.
+#[allow(clippy::string_slice)]
+pub(crate) fn renumber(
+    text: &str,
+    analysis: &Analysis,
+    slug: SlugStyle,
+    remove: bool,
+) -> Renumbering {
+    let has_title = analysis
+        .headings
+        .first()
+        .is_some_and(|heading| heading.level == 1)
+        && analysis
+            .headings
+            .iter()
+            .filter(|heading| heading.level == 1)
+            .count()
+            == 1;
+
+    let mut edit = TextEdit::builder();
+    let mut levels: Vec<usize> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+    for (index, heading) in analysis.headings.iter().enumerate() {
+        let Some(start) = content_start(text, heading) else {
+            continue;
+        };
+        let number = if remove || (has_title && index == 0) {
+            String::new()
+        } else {
+            while levels.last().is_some_and(|&level| level >= heading.level) {
+                levels.pop();
+            }
+            let depth = levels.len();
+            levels.push(heading.level);
+            counts.truncate(depth + 1);
+            counts.resize(depth + 1, 0);
+            counts[depth] += 1;
+            let number = counts.iter().map(ToString::to_string).collect::<Vec<_>>();
+            format!("{} ", number.join("."))
+        };
+
+        let existing = number_len(&text[start..]);
+        let range = TextRange::at(
+            TextSize::from(start as u32),
+            TextSize::from(existing as u32),
+        );
+        if text[range] != number {
+            edit.replace(range, number);
+        }
+    }
+    let edit = edit.finish();
+
+    let mut renumbered = text.to_owned();
+    edit.apply(&mut renumbered);
+    let renumbered = Analysis::new(&renumbered, slug);
+    let anchors = analysis
+        .headings
+        .iter()
+        .zip(&renumbered.headings)
+        .filter(|(old, new)| old.anchor != new.anchor)
+        .map(|(old, new)| (old.anchor.clone(), new.anchor.clone()))
+        .collect();
+
+    Renumbering { edit, anchors }
+}
+
+/// Returns the offset of the text of a heading, after the `#`s of an ATX
+/// heading, or `None` if it is empty.
+#[allow(clippy::string_slice)]
+fn content_start(text: &str, heading: &Heading) -> Option<usize> {
+    let start = usize::from(heading.range.start());
+    let source = &text[start..usize::from(heading.range.end())];
+    let content = source.trim_start_matches(' ');
+    let content = match content.strip_prefix('#') {
+        Some(content) => content
+            .trim_start_matches('#')
+            .trim_start_matches([' ', '\t']),
+        None => content,
+    };
+    if content.trim().is_empty() {
+        None
+    } else {
+        Some(start + source.len() - content.len())
+    }
+}
+
+/// Returns the length of the number at the start of a heading's text,
+/// including the whitespace after it.
+///
+/// Components longer than three digits are more likely years, as in
+/// `## 2022 roadmap`, than section numbers.
+#[allow(clippy::string_slice)]
+fn number_len(content: &str) -> usize {
+    let number = content.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let prefix = &content[..content.len() - number.len()];
+    let is_number = prefix
+        .strip_suffix('.')
+        .unwrap_or(prefix)
+        .split('.')
+        .all(|component| (1..=3).contains(&component.len()));
+    let rest = number.trim_start_matches([' ', '\t']);
+    if is_number && rest.len() < number.len() {
+        content.len() - rest.len()
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+# Spec
+
+## 3 Intro
+
+### Scope
+
+## 1.1. Design
+
+#### Data
+
+Setext
+------
+";
+
+    fn apply(text: &str, remove: bool) -> (String, Vec<(String, String)>) {
+        let analysis = Analysis::new(text, SlugStyle::Github);
+        let renumbering = renumber(text, &analysis, SlugStyle::Github, remove);
+        let mut text = text.to_owned();
+        renumbering.edit.apply(&mut text);
+        (text, renumbering.anchors)
+    }
+
+    #[test]
+    fn test_number() {
+        let (text, anchors) = apply(TEXT, false);
+        assert_eq!(
+            text,
+            "# Spec\n\n## 1 Intro\n\n### 1.1 Scope\n\n## 2 Design\n\n#### 2.1 Data\n\n3 Setext\n------\n"
+        );
+        assert_eq!(
+            anchors,
+            [
+                ("3-intro", "1-intro"),
+                ("scope", "11-scope"),
+                ("11-design", "2-design"),
+                ("data", "21-data"),
+                ("setext", "3-setext"),
+            ]
+            .map(|(old, new)| (old.to_owned(), new.to_owned()))
+        );
+        assert!(apply(&text, false).1.is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let (text, _) = apply(TEXT, true);
+        assert_eq!(
+            text,
+            "# Spec\n\n## Intro\n\n### Scope\n\n## Design\n\n#### Data\n\nSetext\n------\n"
+        );
+    }
+
+    #[test]
+    fn test_number_len() {
+        assert_eq!(number_len("2.3 Design"), 4);
+        assert_eq!(number_len("2.3.\tDesign"), 5);
+        assert_eq!(number_len("2022 roadmap"), 0);
+        assert_eq!(number_len("1..2 Design"), 0);
+        assert_eq!(number_len("2.x Design"), 0);
+        assert_eq!(number_len("Design"), 0);
+        assert_eq!(number_len("42"), 0);
+    }
+}
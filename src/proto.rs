@@ -1,6 +1,17 @@
+use lsp_types::Url;
 use text_edit::{Indel, TextEdit, TextRange, TextSize};
 
-use crate::index::LineIndex;
+use crate::{
+    assist::{Assist, AssistKind},
+    command,
+    completion::{Completion, CompletionKind},
+    diagnostics::{Diagnostic, Fix, Severity},
+    document_link::{DocumentLink, Target},
+    highlight::HighlightKind,
+    index::{LineCol, LineColUtf16, LineIndex, PositionEncoding},
+    semantic_tokens::{Token, TOKEN_MODIFIERS, TOKEN_TYPES},
+    symbols::{Symbol, SymbolKind},
+};
 
 pub(crate) fn text_edit_vec(
     line_index: &LineIndex,
@@ -28,5 +39,472 @@ pub(crate) fn range(line_index: &LineIndex, range: TextRange) -> lsp_types::Rang
 
 pub(crate) fn position(line_index: &LineIndex, offset: TextSize) -> lsp_types::Position {
     let line_col = line_index.line_col(offset);
-    lsp_types::Position::new(line_col.line, line_col.col)
+    match line_index.encoding {
+        PositionEncoding::Utf8 => lsp_types::Position::new(line_col.line, line_col.col),
+        PositionEncoding::Utf16 => {
+            let line_col = line_index.to_utf16(line_col);
+            lsp_types::Position::new(line_col.line, line_col.col)
+        }
+    }
+}
+
+pub(crate) fn diagnostic(
+    line_index: &LineIndex,
+    uri: &Url,
+    diagnostic: Diagnostic,
+) -> lsp_types::Diagnostic {
+    let severity = match diagnostic.severity {
+        Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
+        Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+        Severity::Information => lsp_types::DiagnosticSeverity::INFORMATION,
+    };
+    let related_information = diagnostic
+        .related
+        .into_iter()
+        .map(|(range, message)| lsp_types::DiagnosticRelatedInformation {
+            location: lsp_types::Location::new(uri.clone(), self::range(line_index, range)),
+            message,
+        })
+        .collect::<Vec<_>>();
+
+    lsp_types::Diagnostic {
+        range: range(line_index, diagnostic.range),
+        severity: Some(severity),
+        code: Some(lsp_types::NumberOrString::String(
+            diagnostic.code.to_owned(),
+        )),
+        source: Some("mdls".to_owned()),
+        message: diagnostic.message,
+        related_information: (!related_information.is_empty()).then_some(related_information),
+        tags: diagnostic
+            .unnecessary
+            .then(|| vec![lsp_types::DiagnosticTag::UNNECESSARY]),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn workspace_edit(
+    line_index: &LineIndex,
+    uri: &Url,
+    text_edit: TextEdit,
+) -> lsp_types::WorkspaceEdit {
+    let changes = std::iter::once((uri.clone(), text_edit_vec(line_index, text_edit))).collect();
+    lsp_types::WorkspaceEdit::new(changes)
+}
+
+pub(crate) fn quick_fix(
+    line_index: &LineIndex,
+    uri: &Url,
+    fix: Fix,
+    diagnostic: lsp_types::Diagnostic,
+) -> lsp_types::CodeAction {
+    lsp_types::CodeAction {
+        title: fix.title,
+        kind: Some(lsp_types::CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic]),
+        edit: Some(workspace_edit(line_index, uri, fix.edit)),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn code_action(
+    line_index: &LineIndex,
+    uri: &Url,
+    assist: Assist,
+) -> lsp_types::CodeAction {
+    let kind = match assist.kind {
+        AssistKind::Rewrite => lsp_types::CodeActionKind::REFACTOR_REWRITE,
+        AssistKind::Source => lsp_types::CodeActionKind::SOURCE,
+    };
+    lsp_types::CodeAction {
+        title: assist.title,
+        kind: Some(kind),
+        edit: Some(workspace_edit(line_index, uri, assist.edit)),
+        ..Default::default()
+    }
+}
+
+/// The kind of the action that applies all safe fixes, which editors can be
+/// configured to run on save.
+pub(crate) const FIX_ALL: lsp_types::CodeActionKind =
+    lsp_types::CodeActionKind::new("source.fixAll.mdls");
+
+/// The kinds of code actions the server provides.
+pub(crate) fn code_action_kinds() -> Vec<lsp_types::CodeActionKind> {
+    vec![
+        lsp_types::CodeActionKind::QUICKFIX,
+        lsp_types::CodeActionKind::REFACTOR_EXTRACT,
+        lsp_types::CodeActionKind::REFACTOR_REWRITE,
+        lsp_types::CodeActionKind::SOURCE,
+        FIX_ALL,
+    ]
+}
+
+pub(crate) fn fix_all(line_index: &LineIndex, uri: &Url, edit: TextEdit) -> lsp_types::CodeAction {
+    lsp_types::CodeAction {
+        title: "Fix all auto-fixable problems".to_owned(),
+        kind: Some(FIX_ALL),
+        edit: Some(workspace_edit(line_index, uri, edit)),
+        ..Default::default()
+    }
+}
+
+/// Offers to add an unknown word to the words file, which is done by a
+/// command since the file may not be open.
+pub(crate) fn add_word(
+    word: &str,
+    words_file: &str,
+    diagnostic: lsp_types::Diagnostic,
+) -> lsp_types::CodeAction {
+    let title = format!("Add `{word}` to `{words_file}`");
+    lsp_types::CodeAction {
+        title: title.clone(),
+        kind: Some(lsp_types::CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic]),
+        command: Some(lsp_types::Command {
+            title,
+            command: command::ADD_WORD.to_owned(),
+            arguments: Some(vec![serde_json::json!(word)]),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Offers to extract the section of the heading at a position, which is done
+/// by a command since it needs the links of the whole workspace.
+pub(crate) fn extract_section(uri: &Url, position: lsp_types::Position) -> lsp_types::CodeAction {
+    let title = "Extract section to new file";
+    lsp_types::CodeAction {
+        title: title.to_owned(),
+        kind: Some(lsp_types::CodeActionKind::REFACTOR_EXTRACT),
+        command: Some(lsp_types::Command {
+            title: title.to_owned(),
+            command: command::EXTRACT_SECTION.to_owned(),
+            arguments: Some(vec![serde_json::json!(uri), serde_json::json!(position)]),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Creates a document with the given contents.
+pub(crate) fn create_file(uri: Url, contents: String) -> [lsp_types::DocumentChangeOperation; 2] {
+    let create = lsp_types::ResourceOp::Create(lsp_types::CreateFile {
+        uri: uri.clone(),
+        options: Some(lsp_types::CreateFileOptions {
+            overwrite: Some(false),
+            ignore_if_exists: Some(false),
+        }),
+        annotation_id: None,
+    });
+    let start = lsp_types::Position::new(0, 0);
+    let insert = lsp_types::TextEdit::new(lsp_types::Range::new(start, start), contents);
+    [
+        lsp_types::DocumentChangeOperation::Op(create),
+        text_document_edit(uri, vec![insert]),
+    ]
+}
+
+pub(crate) fn text_document_edit(
+    uri: Url,
+    edits: Vec<lsp_types::TextEdit>,
+) -> lsp_types::DocumentChangeOperation {
+    lsp_types::DocumentChangeOperation::Edit(lsp_types::TextDocumentEdit {
+        text_document: lsp_types::OptionalVersionedTextDocumentIdentifier { uri, version: None },
+        edits: edits.into_iter().map(lsp_types::OneOf::Left).collect(),
+    })
+}
+
+pub(crate) fn offset(line_index: &LineIndex, position: lsp_types::Position) -> TextSize {
+    let line_col = match line_index.encoding {
+        PositionEncoding::Utf8 => LineCol {
+            line: position.line,
+            col: position.character,
+        },
+        PositionEncoding::Utf16 => line_index.to_utf8(LineColUtf16 {
+            line: position.line,
+            col: position.character,
+        }),
+    };
+    line_index.offset(line_col)
+}
+
+pub(crate) fn text_range(line_index: &LineIndex, range: lsp_types::Range) -> TextRange {
+    let start = offset(line_index, range.start);
+    let end = offset(line_index, range.end);
+    TextRange::new(start, end.max(start))
+}
+
+pub(crate) fn hover(
+    line_index: &LineIndex,
+    range: TextRange,
+    markdown: String,
+) -> lsp_types::Hover {
+    lsp_types::Hover {
+        contents: lsp_types::HoverContents::Markup(lsp_types::MarkupContent {
+            kind: lsp_types::MarkupKind::Markdown,
+            value: markdown,
+        }),
+        range: Some(self::range(line_index, range)),
+    }
+}
+
+pub(crate) fn completion_item(
+    line_index: &LineIndex,
+    completion: Completion,
+) -> lsp_types::CompletionItem {
+    let kind = match completion.kind {
+        CompletionKind::File => lsp_types::CompletionItemKind::FILE,
+        CompletionKind::Folder => lsp_types::CompletionItemKind::FOLDER,
+        CompletionKind::Heading | CompletionKind::Reference => {
+            lsp_types::CompletionItemKind::REFERENCE
+        }
+        CompletionKind::Property => lsp_types::CompletionItemKind::PROPERTY,
+        CompletionKind::Value => lsp_types::CompletionItemKind::VALUE,
+    };
+    let command = completion.retrigger.then(|| lsp_types::Command {
+        title: "Trigger completion".to_owned(),
+        command: "editor.action.triggerSuggest".to_owned(),
+        arguments: None,
+    });
+
+    lsp_types::CompletionItem {
+        label: completion.label,
+        kind: Some(kind),
+        detail: completion.detail,
+        documentation: completion.documentation.map(|documentation| {
+            lsp_types::Documentation::MarkupContent(lsp_types::MarkupContent {
+                kind: lsp_types::MarkupKind::Markdown,
+                value: documentation,
+            })
+        }),
+        sort_text: completion.sort_text,
+        text_edit: Some(lsp_types::CompletionTextEdit::Edit(lsp_types::TextEdit {
+            range: range(line_index, completion.range),
+            new_text: completion.insert,
+        })),
+        additional_text_edits: (!completion.additional_edits.is_empty()).then(|| {
+            completion
+                .additional_edits
+                .into_iter()
+                .map(|indel| text_edit(line_index, indel))
+                .collect()
+        }),
+        command,
+        ..Default::default()
+    }
+}
+
+/// Converts a document link, deferring links to headings to
+/// `documentLink/resolve` with the target in the `data` field.
+pub(crate) fn document_link(line_index: &LineIndex, link: DocumentLink) -> lsp_types::DocumentLink {
+    let (target, data) = match link.target {
+        Target::Url(url) => (Some(url), None),
+        Target::Heading(uri, anchor) => (None, Some(heading_data(&uri, &anchor))),
+    };
+    lsp_types::DocumentLink {
+        range: range(line_index, link.range),
+        target,
+        tooltip: None,
+        data,
+    }
+}
+
+/// Identifies a heading in the `data` field of a document link or code lens.
+pub(crate) fn heading_data(uri: &Url, anchor: &str) -> serde_json::Value {
+    serde_json::json!({ "uri": uri, "anchor": anchor })
+}
+
+/// Extracts the heading stored by [`heading_data`].
+pub(crate) fn heading_target(data: &serde_json::Value) -> Option<(Url, &str)> {
+    let uri = Url::parse(data.get("uri")?.as_str()?).ok()?;
+    let anchor = data.get("anchor")?.as_str()?;
+    Some((uri, anchor))
+}
+
+pub(crate) fn semantic_tokens_legend() -> lsp_types::SemanticTokensLegend {
+    lsp_types::SemanticTokensLegend {
+        token_types: TOKEN_TYPES
+            .into_iter()
+            .map(lsp_types::SemanticTokenType::new)
+            .collect(),
+        token_modifiers: TOKEN_MODIFIERS
+            .into_iter()
+            .map(lsp_types::SemanticTokenModifier::new)
+            .collect(),
+    }
+}
+
+/// Encodes single-line tokens relative to the previous token.
+pub(crate) fn semantic_tokens(
+    line_index: &LineIndex,
+    tokens: Vec<Token>,
+) -> Vec<lsp_types::SemanticToken> {
+    let mut previous = lsp_types::Position::default();
+    tokens
+        .into_iter()
+        .map(|token| {
+            let range = range(line_index, token.range);
+            let delta_line = range.start.line - previous.line;
+            let delta_start = if delta_line == 0 {
+                range.start.character - previous.character
+            } else {
+                range.start.character
+            };
+            previous = range.start;
+            lsp_types::SemanticToken {
+                delta_line,
+                delta_start,
+                length: range.end.character - range.start.character,
+                token_type: token.kind.index(),
+                token_modifiers_bitset: token.kind.modifiers(),
+            }
+        })
+        .collect()
+}
+
+/// Computes the edit that turns one set of tokens into another, by replacing
+/// everything between their common prefix and suffix.
+pub(crate) fn semantic_tokens_edits(
+    old: &[lsp_types::SemanticToken],
+    new: &[lsp_types::SemanticToken],
+) -> Vec<lsp_types::SemanticTokensEdit> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    if old.is_empty() && new.is_empty() {
+        return Vec::new();
+    }
+    // The edit is in terms of the integers the tokens are serialized to.
+    vec![lsp_types::SemanticTokensEdit {
+        start: 5 * prefix as u32,
+        delete_count: 5 * old.len() as u32,
+        data: Some(new.to_vec()),
+    }]
+}
+
+/// Nests ranges, given innermost first, into a selection range.
+pub(crate) fn selection_range(
+    line_index: &LineIndex,
+    ranges: Vec<TextRange>,
+) -> Option<lsp_types::SelectionRange> {
+    ranges.into_iter().rev().fold(None, |parent, range| {
+        Some(lsp_types::SelectionRange {
+            range: self::range(line_index, range),
+            parent: parent.map(Box::new),
+        })
+    })
+}
+
+#[allow(deprecated)]
+pub(crate) fn document_symbol(line_index: &LineIndex, symbol: Symbol) -> lsp_types::DocumentSymbol {
+    let kind = match symbol.kind {
+        SymbolKind::FrontMatter => lsp_types::SymbolKind::MODULE,
+        SymbolKind::Heading | SymbolKind::String => lsp_types::SymbolKind::STRING,
+        SymbolKind::Object => lsp_types::SymbolKind::OBJECT,
+        SymbolKind::Array => lsp_types::SymbolKind::ARRAY,
+        SymbolKind::Number => lsp_types::SymbolKind::NUMBER,
+        SymbolKind::Boolean => lsp_types::SymbolKind::BOOLEAN,
+        SymbolKind::Null => lsp_types::SymbolKind::NULL,
+    };
+    let children = symbol
+        .children
+        .into_iter()
+        .map(|child| document_symbol(line_index, child))
+        .collect::<Vec<_>>();
+    lsp_types::DocumentSymbol {
+        name: symbol.name,
+        detail: symbol.detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: range(line_index, symbol.range),
+        selection_range: range(line_index, symbol.selection_range),
+        children: (!children.is_empty()).then_some(children),
+    }
+}
+
+pub(crate) fn document_highlight(
+    line_index: &LineIndex,
+    range: TextRange,
+    kind: HighlightKind,
+) -> lsp_types::DocumentHighlight {
+    let kind = match kind {
+        HighlightKind::Read => lsp_types::DocumentHighlightKind::READ,
+        HighlightKind::Write => lsp_types::DocumentHighlightKind::WRITE,
+    };
+    lsp_types::DocumentHighlight {
+        range: self::range(line_index, range),
+        kind: Some(kind),
+    }
+}
+
+/// Asks the client to open a location, selecting its range.
+pub(crate) fn show_document(location: lsp_types::Location) -> lsp_types::ShowDocumentParams {
+    lsp_types::ShowDocumentParams {
+        uri: location.uri,
+        external: Some(false),
+        take_focus: Some(true),
+        selection: Some(location.range),
+    }
+}
+
+pub(crate) fn show_references(
+    uri: &Url,
+    position: lsp_types::Position,
+    locations: &[lsp_types::Location],
+) -> lsp_types::Command {
+    let title = match locations.len() {
+        1 => "1 reference".to_owned(),
+        count => format!("{count} references"),
+    };
+    lsp_types::Command {
+        title,
+        command: command::SHOW_REFERENCES.to_owned(),
+        arguments: Some(vec![
+            serde_json::json!(uri),
+            serde_json::json!(position),
+            serde_json::json!(locations),
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show_references() {
+        let uri = Url::parse("file:///a.md").unwrap();
+        let position = lsp_types::Position::new(2, 0);
+        let location = lsp_types::Location::new(
+            Url::parse("file:///b.md").unwrap(),
+            lsp_types::Range::new(
+                lsp_types::Position::new(0, 0),
+                lsp_types::Position::new(0, 9),
+            ),
+        );
+        let command = show_references(&uri, position, std::slice::from_ref(&location));
+        assert_eq!(command.title, "1 reference");
+        assert_eq!(command.command, "mdls.showReferences");
+        assert_eq!(
+            command.arguments,
+            Some(vec![
+                serde_json::json!("file:///a.md"),
+                serde_json::json!({ "line": 2, "character": 0 }),
+                serde_json::json!([location]),
+            ])
+        );
+        assert!(command::ALL.contains(&command.command.as_str()));
+    }
 }
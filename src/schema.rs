@@ -0,0 +1,1177 @@
+//! JSON Schema validation, completion and hover for front matter.
+//!
+//! The workspace maps glob patterns of documents to schema files, both
+//! relative to its root, and every schema that matches a document applies to
+//! its front matter. Validation supports `type`, `enum`, `const`, `required`,
+//! `properties`, `additionalProperties`, `items`, the length and range
+//! keywords, `allOf`, `anyOf`, `oneOf` and `$ref`s within the schema.
+//! `pattern` and `format` aren't checked.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use hashbrown::HashMap;
+use lsp_types::Url;
+use serde::Deserialize;
+use serde_json::Value as Json;
+use text_edit::{TextRange, TextSize};
+
+use crate::{
+    analysis::{self, text_range},
+    completion::{Completion, CompletionKind},
+    diagnostics::{Diagnostic, Severity},
+    front_matter::{self, Entry, Format, Node, Value},
+    glob, Context,
+};
+
+/// The diagnostic code of front matter that doesn't match its schema.
+pub(crate) const CODE: &str = "front-matter-schema";
+
+/// Bounds the `$ref`s and combined schemas followed in a row, in case they
+/// form a cycle.
+const MAX_DEPTH: usize = 32;
+
+static TRUE: Json = Json::Bool(true);
+static FALSE: Json = Json::Bool(false);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct FrontMatterConfig {
+    /// JSON Schema files by the glob pattern of the documents they apply to.
+    pub(crate) schemas: BTreeMap<String, String>,
+}
+
+/// The parsed schema files, by path.
+#[derive(Debug, Default)]
+pub(crate) struct SchemaCache {
+    /// The schemas, and when their files were last read, to notice changes.
+    schemas: HashMap<PathBuf, (Option<SystemTime>, Option<Json>)>,
+}
+
+impl SchemaCache {
+    /// Reads the configured schema files that changed since they were last
+    /// read.
+    pub(crate) fn refresh(&mut self, config: &FrontMatterConfig, root: Option<&Path>) {
+        let Some(root) = root else { return };
+        for schema in config.schemas.values() {
+            let path = root.join(schema);
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if self
+                .schemas
+                .get(&path)
+                .is_some_and(|(cached, _)| *cached == modified)
+            {
+                continue;
+            }
+            let schema = load(&path);
+            self.schemas.insert(path, (modified, schema));
+        }
+    }
+
+    fn get(&self, path: &Path) -> Option<&Json> {
+        self.schemas.get(path)?.1.as_ref()
+    }
+}
+
+/// Returns the schemas that apply to a document, as of the last
+/// [`Context::refresh_schemas`].
+pub(crate) fn schemas<'a>(ctx: &'a Context, uri: &Url) -> Vec<&'a Json> {
+    let (Some(root), Ok(path)) = (ctx.root(), uri.to_file_path()) else {
+        return Vec::new();
+    };
+    let Ok(relative) = path.strip_prefix(root) else {
+        return Vec::new();
+    };
+    let relative = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    ctx.config()
+        .front_matter
+        .schemas
+        .iter()
+        .filter(|(pattern, _)| glob::matches(pattern, &relative))
+        .filter_map(|(_, schema)| ctx.schema_cache().get(&root.join(schema)))
+        .collect()
+}
+
+fn load(path: &Path) -> Option<Json> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| tracing::warn!("can't read schema {}: {}", path.display(), error))
+        .ok()?;
+    serde_json::from_str(&text)
+        .map_err(|error| tracing::warn!("invalid schema {}: {}", path.display(), error))
+        .ok()
+}
+
+/// A step from a schema into one of its subschemas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Item,
+}
+
+/// Reports front matter that doesn't match the schemas, or is missing keys
+/// they require.
+pub(crate) fn diagnostics(text: &str, schemas: &[&Json]) -> Vec<Diagnostic> {
+    if schemas.is_empty() {
+        return Vec::new();
+    }
+    let (node, range) = match front_matter::parse(text) {
+        // The error is reported already, and makes the keys after it look
+        // missing.
+        Some(front_matter) if front_matter.error.is_some() => return Vec::new(),
+        Some(front_matter) => {
+            let fence = TextRange::at(front_matter.range.start(), TextSize::from(3));
+            let node = Node {
+                range: front_matter.range,
+                value: Value::Object(front_matter.entries),
+            };
+            (node, fence)
+        }
+        None => {
+            let node = Node {
+                range: TextRange::default(),
+                value: Value::Object(Vec::new()),
+            };
+            (node, TextRange::default())
+        }
+    };
+
+    let mut problems = Vec::new();
+    for &root in schemas {
+        Schema { root }.validate(root, &node, range, &mut problems, 0);
+    }
+    problems.dedup();
+    problems
+        .into_iter()
+        .map(|(range, message)| Diagnostic::new(range, Severity::Error, CODE, message))
+        .collect()
+}
+
+/// Where in the front matter completion was requested.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Site {
+    /// The path of the mapping or table a key is completed in, or of the key
+    /// a value is completed for.
+    path: Vec<Step>,
+    /// The start of the key or value being completed.
+    start: usize,
+    kind: SiteKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SiteKind {
+    /// A key, which isn't one of the keys next to it.
+    Key {
+        existing: Vec<String>,
+    },
+    Value,
+    /// A YAML sequence item, which is a value or the first key of a mapping.
+    Item,
+}
+
+/// Completes the keys of the front matter and the values the schemas allow.
+#[allow(clippy::string_slice)]
+pub(crate) fn completions(schemas: &[&Json], text: &str, offset: TextSize) -> Vec<Completion> {
+    let Some(front_matter) = analysis::front_matter(text) else {
+        return Vec::new();
+    };
+    let content = front_matter::content(text, front_matter);
+    let offset = usize::from(offset);
+    if !(usize::from(content.start())..usize::from(content.end())).contains(&offset) {
+        return Vec::new();
+    }
+    let line_start = text[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+    let lines = Lines {
+        text,
+        start: usize::from(content.start()),
+        end: usize::from(content.end()),
+        line_start,
+    };
+    let format = front_matter::format(text);
+    let site = match format {
+        Format::Yaml => yaml_site(&lines, &text[line_start..offset]),
+        Format::Toml => toml_site(&lines, &text[line_start..offset]),
+    };
+    let Some(site) = site else {
+        return Vec::new();
+    };
+
+    let range = text_range(site.start..offset);
+    let mut completions: Vec<Completion> = Vec::new();
+    for &root in schemas {
+        let schema = Schema { root };
+        let Some(subschema) = schema.at(&site.path) else {
+            continue;
+        };
+        let existing: Option<&[String]> = match &site.kind {
+            SiteKind::Key { existing } => Some(existing),
+            SiteKind::Item => Some(&[]),
+            SiteKind::Value => None,
+        };
+        if let Some(existing) = existing {
+            completions.extend(
+                schema
+                    .properties(subschema)
+                    .into_iter()
+                    .filter(|(key, _)| !existing.iter().any(|other| other == key))
+                    .map(|(key, property)| schema.key_completion(key, property, format, range)),
+            );
+        }
+        if matches!(site.kind, SiteKind::Value | SiteKind::Item) {
+            completions.extend(schema.values(subschema).into_iter().filter_map(|value| {
+                Some(Completion {
+                    label: match value {
+                        Json::String(value) => value.clone(),
+                        value => value.to_string(),
+                    },
+                    kind: CompletionKind::Value,
+                    detail: None,
+                    documentation: None,
+                    sort_text: None,
+                    range,
+                    insert: format_value(value, format)?,
+                    additional_edits: Vec::new(),
+                    retrigger: false,
+                })
+            }));
+        }
+    }
+    let mut labels = Vec::new();
+    completions.retain(|completion| {
+        let is_new = !labels.contains(&completion.label);
+        labels.push(completion.label.clone());
+        is_new
+    });
+    completions
+}
+
+/// Shows the description the schemas give the key at an offset.
+pub(crate) fn hover(
+    schemas: &[&Json],
+    text: &str,
+    offset: TextSize,
+) -> Option<(TextRange, String)> {
+    let front_matter = front_matter::parse(text)?;
+    let (path, range) = key_at(&front_matter.entries, offset, &mut Vec::new())?;
+    let Some(Step::Key(key)) = path.last() else {
+        return None;
+    };
+    schemas.iter().find_map(|&root| {
+        let schema = Schema { root };
+        let markdown = schema.describe(key, schema.at(&path)?);
+        Some((range, markdown))
+    })
+}
+
+/// Returns the path and range of the key at an offset.
+fn key_at(
+    entries: &[Entry],
+    offset: TextSize,
+    path: &mut Vec<Step>,
+) -> Option<(Vec<Step>, TextRange)> {
+    for entry in entries {
+        path.push(Step::Key(entry.key.clone()));
+        if entry.key_range.contains_inclusive(offset) {
+            return Some((path.clone(), entry.key_range));
+        }
+        if entry.value.range.contains_inclusive(offset) {
+            if let Some(found) = node_key_at(&entry.value, offset, path) {
+                return Some(found);
+            }
+        }
+        path.pop();
+    }
+    None
+}
+
+fn node_key_at(
+    node: &Node,
+    offset: TextSize,
+    path: &mut Vec<Step>,
+) -> Option<(Vec<Step>, TextRange)> {
+    match &node.value {
+        Value::Object(entries) => key_at(entries, offset, path),
+        Value::Array(items) => {
+            let item = items
+                .iter()
+                .find(|item| item.range.contains_inclusive(offset))?;
+            path.push(Step::Item);
+            let found = node_key_at(item, offset, path);
+            path.pop();
+            found
+        }
+        Value::Scalar(_) => None,
+    }
+}
+
+/// The lines of the front matter, around the line being completed.
+struct Lines<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+    line_start: usize,
+}
+
+#[allow(clippy::string_slice)]
+impl<'a> Lines<'a> {
+    /// The lines above the current one, nearest first.
+    fn above(&self) -> impl Iterator<Item = &'a str> {
+        self.text[self.start..self.line_start].lines().rev()
+    }
+
+    /// The lines below the current one.
+    fn below(&self) -> impl Iterator<Item = &'a str> {
+        self.text[self.line_start..self.end].lines().skip(1)
+    }
+}
+
+/// A YAML line, split into its indentation, an optional `- ` and the column
+/// and text of its content.
+struct YamlLine<'a> {
+    indent: usize,
+    is_item: bool,
+    column: usize,
+    content: &'a str,
+}
+
+#[allow(clippy::string_slice)]
+impl<'a> YamlLine<'a> {
+    fn new(line: &'a str) -> Option<Self> {
+        let trimmed = line.trim_start_matches(' ');
+        if trimmed.trim().is_empty() || trimmed.starts_with('#') {
+            return None;
+        }
+        let indent = line.len() - trimmed.len();
+        let (is_item, content) = match trimmed.strip_prefix('-') {
+            Some(item) if item.is_empty() || item.starts_with(' ') => (true, item.trim_start()),
+            _ => (false, trimmed),
+        };
+        Some(Self {
+            indent,
+            is_item,
+            column: line.len() - content.len(),
+            content,
+        })
+    }
+
+    /// Returns the key of the line, if it has one.
+    fn key(&self) -> Option<String> {
+        let content = self.content.split(" #").next().unwrap_or_default();
+        let key = match content.find(": ") {
+            Some(colon) => &content[..colon],
+            None => content.trim_end().strip_suffix(':')?,
+        };
+        Some(unquote(key.trim()))
+    }
+
+    /// Returns the key of the line if its value is on the next lines.
+    fn nested_key(&self) -> Option<String> {
+        let content = self.content.split(" #").next().unwrap_or_default();
+        let key = content.trim_end().strip_suffix(':')?;
+        (!key.contains(": ")).then(|| unquote(key.trim()))
+    }
+}
+
+fn unquote(key: &str) -> String {
+    key.strip_prefix(['"', '\''])
+        .and_then(|key| key.strip_suffix(['"', '\'']))
+        .unwrap_or(key)
+        .to_owned()
+}
+
+/// Returns whether text can be a YAML or TOML key as it's typed.
+fn is_partial_key(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+#[allow(clippy::string_slice)]
+fn yaml_site(lines: &Lines<'_>, before: &str) -> Option<Site> {
+    let indent = before.len() - before.trim_start_matches(' ').len();
+    let line = YamlLine::new(before).unwrap_or(YamlLine {
+        indent,
+        is_item: false,
+        column: indent,
+        content: "",
+    });
+    let mut path = if line.is_item {
+        let mut path = yaml_path(lines, line.indent, true)?;
+        path.push(Step::Item);
+        path
+    } else {
+        yaml_path(lines, line.column, false)?
+    };
+
+    if let Some(colon) = line.content.find(": ") {
+        path.push(Step::Key(unquote(line.content[..colon].trim())));
+        let value = &line.content[colon + 1..];
+        let mut start = before.len() - value.trim_start().len();
+        // `key: [a, b`
+        if value.trim_start().starts_with('[') {
+            let open = before.rfind(['[', ',']).expect("there's a bracket");
+            start = open + 1 + before[open + 1..].len() - before[open + 1..].trim_start().len();
+            path.push(Step::Item);
+        }
+        return Some(Site {
+            path,
+            start: lines.line_start + start,
+            kind: SiteKind::Value,
+        });
+    }
+    if !is_partial_key(line.content) {
+        return None;
+    }
+    let kind = if line.is_item {
+        SiteKind::Item
+    } else {
+        SiteKind::Key {
+            existing: yaml_siblings(lines, line.column),
+        }
+    };
+    Some(Site {
+        path,
+        start: lines.line_start + line.column,
+        kind,
+    })
+}
+
+/// Works out the path of the mapping whose keys are at a column, or of the
+/// sequence whose items are, from the lines above.
+fn yaml_path(lines: &Lines<'_>, mut column: usize, mut in_sequence: bool) -> Option<Vec<Step>> {
+    // The steps from the innermost.
+    let mut steps = Vec::new();
+    for line in lines.above().filter_map(YamlLine::new) {
+        if column == 0 && !in_sequence {
+            break;
+        }
+        if !in_sequence && line.is_item && line.column == column {
+            // The mapping is an item of a sequence.
+            steps.push(Step::Item);
+            column = line.indent;
+            in_sequence = true;
+            continue;
+        }
+        let is_sibling = if in_sequence {
+            line.indent > column || line.is_item && line.indent == column
+        } else {
+            line.indent >= column
+        };
+        if is_sibling {
+            continue;
+        }
+        // The key whose value the mapping or sequence is.
+        steps.push(Step::Key(line.nested_key()?));
+        if line.is_item {
+            steps.push(Step::Item);
+        }
+        column = line.indent;
+        in_sequence = line.is_item;
+    }
+    if column > 0 || in_sequence {
+        return None;
+    }
+    steps.reverse();
+    Some(steps)
+}
+
+/// Returns the keys of the mapping whose keys are at a column.
+fn yaml_siblings(lines: &Lines<'_>, column: usize) -> Vec<String> {
+    let mut keys = Vec::new();
+    for line in lines.above().filter_map(YamlLine::new) {
+        if line.column == column {
+            keys.extend(line.key());
+        }
+        if line.is_item && line.column == column || line.indent < column {
+            break;
+        }
+    }
+    for line in lines.below().filter_map(YamlLine::new) {
+        if line.indent < column {
+            break;
+        }
+        if line.column == column {
+            keys.extend(line.key());
+        }
+    }
+    keys
+}
+
+/// Splits a dotted TOML key into its parts.
+fn toml_key(key: &str) -> Vec<Step> {
+    key.split('.')
+        .map(|part| Step::Key(unquote(part.trim())))
+        .collect()
+}
+
+#[allow(clippy::string_slice)]
+fn toml_site(lines: &Lines<'_>, before: &str) -> Option<Site> {
+    // The table the line is in.
+    let mut path = Vec::new();
+    if let Some(header) = lines
+        .above()
+        .map(str::trim)
+        .find(|line| line.starts_with('['))
+    {
+        let header = header.split(" #").next().unwrap_or_default().trim_end();
+        if let Some(name) = header
+            .strip_prefix("[[")
+            .and_then(|header| header.strip_suffix("]]"))
+        {
+            path = toml_key(name);
+            path.push(Step::Item);
+        } else {
+            path = toml_key(header.strip_prefix('[')?.strip_suffix(']')?);
+        }
+    }
+
+    let content = before.trim_start();
+    let column = before.len() - content.len();
+    if let Some(equals) = content.find('=') {
+        path.extend(toml_key(&content[..equals]));
+        let value = &content[equals + 1..];
+        let mut start = before.len() - value.trim_start().len();
+        // `key = [a, b`
+        if value.trim_start().starts_with('[') {
+            let open = before.rfind(['[', ',']).expect("there's a bracket");
+            start = open + 1 + before[open + 1..].len() - before[open + 1..].trim_start().len();
+            path.push(Step::Item);
+        }
+        return Some(Site {
+            path,
+            start: lines.line_start + start,
+            kind: SiteKind::Value,
+        });
+    }
+    if !is_partial_key(content) {
+        return None;
+    }
+    let key = |line: &str| {
+        let (key, _) = line.split_once('=')?;
+        let key = key.trim();
+        (!key.starts_with(['[', '#'])).then(|| unquote(key.split('.').next().unwrap_or(key).trim()))
+    };
+    let existing = lines
+        .above()
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .chain(
+            lines
+                .below()
+                .take_while(|line| !line.trim_start().starts_with('[')),
+        )
+        .filter_map(key)
+        .collect();
+    Some(Site {
+        path,
+        start: lines.line_start + column,
+        kind: SiteKind::Key { existing },
+    })
+}
+
+/// Formats a value as YAML or TOML, quoting strings only when needed.
+fn format_value(value: &Json, format: Format) -> Option<String> {
+    match (value, format) {
+        (Json::Array(_) | Json::Object(_), _) | (Json::Null, Format::Toml) => None,
+        (Json::String(string), Format::Yaml) if is_plain(string) => Some(string.clone()),
+        _ => Some(value.to_string()),
+    }
+}
+
+/// Returns whether a string can be written as a plain YAML scalar.
+fn is_plain(string: &str) -> bool {
+    string.starts_with(char::is_alphabetic)
+        && string
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | '/'))
+        && !matches!(
+            string.to_ascii_lowercase().as_str(),
+            "true" | "false" | "null" | "yes" | "no" | "on" | "off"
+        )
+}
+
+/// Describes a JSON type with an article, as in "expected a string".
+fn describe_type(name: &str) -> &str {
+    match name {
+        "string" => "a string",
+        "number" => "a number",
+        "integer" => "an integer",
+        "boolean" => "a boolean",
+        "array" => "an array",
+        "object" => "an object",
+        "null" => "null",
+        _ => name,
+    }
+}
+
+fn type_of(value: &Json) -> &'static str {
+    match value {
+        Json::Null => "null",
+        Json::Bool(_) => "boolean",
+        Json::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Json::Number(_) => "number",
+        Json::String(_) => "string",
+        Json::Array(_) => "array",
+        Json::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Json, name: &str) -> bool {
+    let actual = type_of(value);
+    actual == name || name == "number" && actual == "integer"
+}
+
+/// Shows a value in a message, with strings unquoted.
+fn show(value: &Json) -> String {
+    match value {
+        Json::String(string) => format!("`{string}`"),
+        value => format!("`{value}`"),
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Schema<'a> {
+    root: &'a Json,
+}
+
+impl<'a> Schema<'a> {
+    /// Follows `$ref`s to definitions in the schema.
+    fn resolve(self, mut schema: &'a Json) -> &'a Json {
+        for _ in 0..MAX_DEPTH {
+            let target = schema
+                .get("$ref")
+                .and_then(Json::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|pointer| self.root.pointer(pointer));
+            match target {
+                Some(target) => schema = target,
+                None => break,
+            }
+        }
+        schema
+    }
+
+    /// Returns a schema and the schemas it combines with `allOf`, `anyOf` and
+    /// `oneOf`, resolved.
+    fn branches(self, schema: &'a Json) -> Vec<&'a Json> {
+        let mut branches = Vec::new();
+        self.collect_branches(schema, &mut branches, 0);
+        branches
+    }
+
+    fn collect_branches(self, schema: &'a Json, branches: &mut Vec<&'a Json>, depth: usize) {
+        let schema = self.resolve(schema);
+        branches.push(schema);
+        if depth == MAX_DEPTH {
+            return;
+        }
+        for keyword in ["allOf", "anyOf", "oneOf"] {
+            for branch in schema
+                .get(keyword)
+                .and_then(Json::as_array)
+                .into_iter()
+                .flatten()
+            {
+                self.collect_branches(branch, branches, depth + 1);
+            }
+        }
+    }
+
+    /// Returns the first value of a keyword in a schema or the schemas it
+    /// combines.
+    fn keyword(self, schema: &'a Json, keyword: &str) -> Option<&'a Json> {
+        self.branches(schema)
+            .into_iter()
+            .find_map(|branch| branch.get(keyword))
+    }
+
+    fn property(self, schema: &'a Json, key: &str) -> Option<&'a Json> {
+        let branches = self.branches(schema);
+        branches
+            .iter()
+            .find_map(|branch| branch.get("properties")?.get(key))
+            .or_else(|| {
+                branches
+                    .iter()
+                    .find_map(|branch| branch.get("additionalProperties").filter(|a| a.is_object()))
+            })
+    }
+
+    fn items(self, schema: &'a Json) -> Option<&'a Json> {
+        self.branches(schema)
+            .into_iter()
+            .find_map(|branch| match branch.get("items")? {
+                Json::Array(items) => items.first(),
+                items => Some(items),
+            })
+    }
+
+    fn at(self, path: &[Step]) -> Option<&'a Json> {
+        path.iter().try_fold(self.root, |schema, step| match step {
+            Step::Key(key) => self.property(schema, key),
+            Step::Item => self.items(schema),
+        })
+    }
+
+    fn properties(self, schema: &'a Json) -> Vec<(&'a str, &'a Json)> {
+        let mut properties: Vec<(&str, &Json)> = Vec::new();
+        for branch in self.branches(schema) {
+            for (key, property) in branch
+                .get("properties")
+                .and_then(Json::as_object)
+                .into_iter()
+                .flatten()
+            {
+                if !properties.iter().any(|(other, _)| other == key) {
+                    properties.push((key, property));
+                }
+            }
+        }
+        properties
+    }
+
+    fn types(self, schema: &'a Json) -> Vec<&'a str> {
+        match self.keyword(schema, "type") {
+            Some(Json::String(name)) => vec![name],
+            Some(Json::Array(names)) => names.iter().filter_map(Json::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the values a schema allows, if it allows only a few.
+    fn values(self, schema: &'a Json) -> Vec<&'a Json> {
+        if let Some(values) = self.keyword(schema, "enum").and_then(Json::as_array) {
+            return values.iter().collect();
+        }
+        if let Some(value) = self.keyword(schema, "const") {
+            return vec![value];
+        }
+        if self.types(schema) == ["boolean"] {
+            return vec![&TRUE, &FALSE];
+        }
+        Vec::new()
+    }
+
+    fn description(self, schema: &'a Json) -> Option<&'a str> {
+        self.keyword(schema, "markdownDescription")
+            .or_else(|| self.keyword(schema, "description"))
+            .and_then(Json::as_str)
+    }
+
+    fn key_completion(
+        self,
+        key: &str,
+        property: &'a Json,
+        format: Format,
+        range: TextRange,
+    ) -> Completion {
+        let types = self.types(property);
+        let is_nested =
+            !types.is_empty() && types.iter().all(|name| matches!(*name, "object" | "array"));
+        let insert = match format {
+            Format::Yaml if is_nested => format!("{key}:"),
+            Format::Yaml => format!("{key}: "),
+            Format::Toml => format!("{key} = "),
+        };
+        Completion {
+            label: key.to_owned(),
+            kind: CompletionKind::Property,
+            detail: (!types.is_empty()).then(|| types.join(" | ")),
+            documentation: self.description(property).map(ToOwned::to_owned),
+            sort_text: None,
+            range,
+            insert,
+            additional_edits: Vec::new(),
+            retrigger: !self.values(property).is_empty(),
+        }
+    }
+
+    /// Describes a key with its type, title, description, allowed values and
+    /// default, in Markdown.
+    fn describe(self, key: &str, schema: &'a Json) -> String {
+        let mut markdown = format!("**{key}**");
+        let types = self.types(schema);
+        if !types.is_empty() {
+            write!(markdown, ": `{}`", types.join(" | ")).unwrap();
+        }
+        if let Some(title) = self.keyword(schema, "title").and_then(Json::as_str) {
+            write!(markdown, "\n\n{title}").unwrap();
+        }
+        if let Some(description) = self.description(schema) {
+            write!(markdown, "\n\n{description}").unwrap();
+        }
+        let values = self.values(schema);
+        if !values.is_empty() && self.types(schema) != ["boolean"] {
+            let values = values.into_iter().map(show).collect::<Vec<_>>();
+            write!(markdown, "\n\nOne of {}", values.join(", ")).unwrap();
+        }
+        if let Some(default) = self.keyword(schema, "default") {
+            write!(markdown, "\n\nDefaults to {}", show(default)).unwrap();
+        }
+        markdown
+    }
+
+    /// Checks a node against a schema, reporting problems at a range.
+    fn validate(
+        self,
+        schema: &'a Json,
+        node: &Node,
+        range: TextRange,
+        problems: &mut Vec<(TextRange, String)>,
+        depth: usize,
+    ) {
+        let schema = self.resolve(schema);
+        if depth == MAX_DEPTH {
+            return;
+        }
+        if *schema == Json::Bool(false) {
+            problems.push((range, "no value is allowed here".to_owned()));
+            return;
+        }
+        for branch in schema
+            .get("allOf")
+            .and_then(Json::as_array)
+            .into_iter()
+            .flatten()
+        {
+            self.validate(branch, node, range, problems, depth + 1);
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            let Some(branches) = schema.get(keyword).and_then(Json::as_array) else {
+                continue;
+            };
+            let matches = branches.iter().any(|branch| {
+                let mut branch_problems = Vec::new();
+                self.validate(branch, node, range, &mut branch_problems, depth + 1);
+                branch_problems.is_empty()
+            });
+            if !matches && !branches.is_empty() {
+                problems.push((range, "doesn't match any of the allowed schemas".to_owned()));
+            }
+        }
+
+        let value = node.to_json();
+        let types = match schema.get("type") {
+            Some(Json::String(name)) => vec![name.as_str()],
+            Some(Json::Array(names)) => names.iter().filter_map(Json::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(&value, name)) {
+            let expected = types.into_iter().map(describe_type).collect::<Vec<_>>();
+            problems.push((
+                range,
+                format!(
+                    "expected {}, found {}",
+                    expected.join(" or "),
+                    describe_type(type_of(&value))
+                ),
+            ));
+            return;
+        }
+        if let Some(values) = schema.get("enum").and_then(Json::as_array) {
+            if !values.contains(&value) {
+                let values = values.iter().map(show).collect::<Vec<_>>();
+                problems.push((range, format!("expected one of {}", values.join(", "))));
+            }
+        }
+        if let Some(expected) = schema.get("const").filter(|expected| **expected != value) {
+            problems.push((range, format!("expected {}", show(expected))));
+        }
+
+        match (&node.value, &value) {
+            (_, Json::String(string)) => {
+                let len = string.chars().count() as u64;
+                if let Some(min) = schema.get("minLength").and_then(Json::as_u64) {
+                    if len < min {
+                        problems.push((range, format!("should be at least {min} characters long")));
+                    }
+                }
+                if let Some(max) = schema.get("maxLength").and_then(Json::as_u64) {
+                    if len > max {
+                        problems.push((range, format!("should be at most {max} characters long")));
+                    }
+                }
+            }
+            (_, Json::Number(number)) => {
+                let number = number.as_f64().unwrap_or_default();
+                let bound = |keyword| schema.get(keyword).and_then(Json::as_f64);
+                let checks = [
+                    ("minimum", "at least"),
+                    ("maximum", "at most"),
+                    ("exclusiveMinimum", "greater than"),
+                    ("exclusiveMaximum", "less than"),
+                ];
+                for (keyword, relation) in checks {
+                    let Some(bound) = bound(keyword) else {
+                        continue;
+                    };
+                    let is_valid = match keyword {
+                        "minimum" => number >= bound,
+                        "maximum" => number <= bound,
+                        "exclusiveMinimum" => number > bound,
+                        _ => number < bound,
+                    };
+                    if !is_valid {
+                        problems.push((range, format!("should be {relation} {bound}")));
+                    }
+                }
+            }
+            (Value::Array(items), _) => self.validate_array(schema, items, range, problems, depth),
+            (Value::Object(entries), _) => {
+                self.validate_object(schema, entries, range, problems, depth);
+            }
+            _ => {}
+        }
+    }
+
+    fn validate_array(
+        self,
+        schema: &'a Json,
+        items: &[Node],
+        range: TextRange,
+        problems: &mut Vec<(TextRange, String)>,
+        depth: usize,
+    ) {
+        let len = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Json::as_u64) {
+            if len < min {
+                problems.push((range, format!("should have at least {min} items")));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Json::as_u64) {
+            if len > max {
+                problems.push((range, format!("should have at most {max} items")));
+            }
+        }
+        for (index, item) in items.iter().enumerate() {
+            let item_schema = match schema.get("items") {
+                Some(Json::Array(schemas)) => schemas.get(index),
+                item_schema => item_schema,
+            };
+            if let Some(item_schema) = item_schema {
+                self.validate(item_schema, item, item.range, problems, depth + 1);
+            }
+        }
+    }
+
+    fn validate_object(
+        self,
+        schema: &'a Json,
+        entries: &[Entry],
+        range: TextRange,
+        problems: &mut Vec<(TextRange, String)>,
+        depth: usize,
+    ) {
+        for key in schema
+            .get("required")
+            .and_then(Json::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Json::as_str)
+        {
+            if !entries.iter().any(|entry| entry.key == key) {
+                problems.push((range, format!("missing required key `{key}`")));
+            }
+        }
+        let properties = schema.get("properties").and_then(Json::as_object);
+        for entry in entries {
+            // Values left empty are reported at their key.
+            let value_range = if entry.value.range.is_empty() {
+                entry.key_range
+            } else {
+                entry.value.range
+            };
+            if let Some(property) = properties.and_then(|properties| properties.get(&entry.key)) {
+                self.validate(property, &entry.value, value_range, problems, depth + 1);
+                continue;
+            }
+            match schema.get("additionalProperties") {
+                Some(Json::Bool(false)) => {
+                    problems.push((entry.key_range, format!("unknown key `{}`", entry.key)));
+                }
+                Some(additional @ Json::Object(_)) => {
+                    self.validate(additional, &entry.value, value_range, problems, depth + 1);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Json {
+        serde_json::json!({
+            "type": "object",
+            "required": ["title", "date"],
+            "additionalProperties": false,
+            "properties": {
+                "title": { "type": "string", "description": "The title of the post." },
+                "date": { "type": "string" },
+                "draft": { "type": "boolean", "default": false },
+                "status": { "$ref": "#/definitions/status" },
+                "weight": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "enum": ["rust", "web"] } },
+                "author": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "email": { "type": "string" }
+                    }
+                },
+                "links": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "url": { "type": "string" },
+                            "rel": { "enum": ["me", "nofollow"] }
+                        }
+                    }
+                }
+            },
+            "definitions": {
+                "status": { "enum": ["draft", "published"], "description": "Where the post is." }
+            }
+        })
+    }
+
+    fn problems(text: &str) -> Vec<(&str, String)> {
+        diagnostics(text, &[&schema()])
+            .into_iter()
+            .map(|diagnostic| (&text[diagnostic.range], diagnostic.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let text = "---\ntitle: A\ndate: 2024-01-02\ntags: [rust]\n---\n";
+        assert_eq!(problems(text), []);
+
+        let text = "---\n\
+                    title: 1\n\
+                    draft: 'no'\n\
+                    status: done\n\
+                    weight: -1\n\
+                    tags: [go]\n\
+                    author:\n  email: e\n\
+                    extra:\n\
+                    ---\n";
+        assert_eq!(
+            problems(text),
+            [
+                ("---", "missing required key `date`".to_owned()),
+                ("1", "expected a string, found an integer".to_owned()),
+                ("'no'", "expected a boolean, found a string".to_owned()),
+                ("done", "expected one of `draft`, `published`".to_owned()),
+                ("-1", "should be at least 0".to_owned()),
+                ("go", "expected one of `rust`, `web`".to_owned()),
+                ("email: e", "missing required key `name`".to_owned()),
+                ("extra", "unknown key `extra`".to_owned()),
+            ]
+        );
+
+        assert_eq!(
+            problems("# No front matter\n"),
+            [
+                ("", "missing required key `title`".to_owned()),
+                ("", "missing required key `date`".to_owned()),
+            ]
+        );
+        // Syntax errors are reported instead.
+        assert_eq!(problems("---\ntitle: [\n---\n"), []);
+        assert!(diagnostics("---\na: 1\n---\n", &[]).is_empty());
+    }
+
+    /// Completes at the `|` in the text, returning the labels and the text
+    /// they replace.
+    fn complete(text: &str) -> (Vec<String>, String) {
+        let offset = text.find('|').unwrap();
+        let text = text.replace('|', "");
+        let completions = completions(&[&schema()], &text, TextSize::try_from(offset).unwrap());
+        let replaced = completions
+            .first()
+            .map(|completion| text[completion.range].to_owned())
+            .unwrap_or_default();
+        let labels = completions
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect();
+        (labels, replaced)
+    }
+
+    #[test]
+    fn test_key_completions() {
+        let (labels, replaced) = complete("---\ntitle: A\nd|\n---\n");
+        assert_eq!(
+            labels,
+            ["author", "date", "draft", "links", "status", "tags", "weight"]
+        );
+        assert_eq!(replaced, "d");
+        assert_eq!(complete("---\nauthor:\n  name: A\n  |\n---\n").0, ["email"]);
+        assert_eq!(complete("---\nlinks:\n  - url: x\n    |\n---\n").0, ["rel"]);
+        assert_eq!(complete("---\nlinks:\n  - |\n---\n").0, ["rel", "url"]);
+        assert_eq!(
+            complete("+++\ntitle = 'A'\n[author]\n|\n+++\n").0,
+            ["email", "name"]
+        );
+        assert!(complete("|---\n---\n").0.is_empty());
+        assert!(complete("---\n---\n|").0.is_empty());
+    }
+
+    #[test]
+    fn test_value_completions() {
+        assert_eq!(
+            complete("---\nstatus: pu|\n---\n"),
+            (
+                vec!["draft".to_owned(), "published".to_owned()],
+                "pu".to_owned()
+            )
+        );
+        assert_eq!(complete("---\ndraft: |\n---\n").0, ["true", "false"]);
+        assert_eq!(
+            complete("---\ntags: [rust, w|\n---\n"),
+            (vec!["rust".to_owned(), "web".to_owned()], "w".to_owned())
+        );
+        assert_eq!(complete("---\ntags:\n  - |\n---\n").0, ["rust", "web"]);
+        assert_eq!(
+            complete("---\nlinks:\n  - url: x\n    rel: |\n---\n").0,
+            ["me", "nofollow"]
+        );
+
+        let text = "+++\nstatus = |\n+++\n";
+        let offset = TextSize::try_from(text.find('|').unwrap()).unwrap();
+        let text = text.replace('|', "");
+        let inserts = completions(&[&schema()], &text, offset)
+            .into_iter()
+            .map(|completion| completion.insert)
+            .collect::<Vec<_>>();
+        assert_eq!(inserts, ["\"draft\"", "\"published\""]);
+    }
+
+    #[test]
+    fn test_hover() {
+        let text = "---\nstatus: draft\nauthor:\n  name: A\n---\n";
+        let (range, markdown) = hover(&[&schema()], text, 6.into()).unwrap();
+        assert_eq!(&text[range], "status");
+        assert_eq!(
+            markdown,
+            "**status**\n\nWhere the post is.\n\nOne of `draft`, `published`"
+        );
+        let (_, markdown) = hover(&[&schema()], text, 29.into()).unwrap();
+        assert_eq!(markdown, "**name**: `string`");
+        assert!(hover(&[&schema()], text, 13.into()).is_none());
+    }
+}
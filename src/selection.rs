@@ -0,0 +1,116 @@
+//! Expanding selections along the structure of a document.
+
+use text_edit::{TextRange, TextSize};
+
+use crate::{
+    analysis::Analysis,
+    syntax::{Node, NodeKind},
+};
+
+/// Returns the ranges a selection at an offset expands to, innermost first:
+/// the word, the enclosing inline and block nodes, then the sections of the
+/// headings above and finally the whole document.
+#[allow(clippy::string_slice)]
+pub(crate) fn selection_ranges(
+    text: &str,
+    analysis: &Analysis,
+    root: &Node,
+    offset: TextSize,
+) -> Vec<TextRange> {
+    let len = TextSize::of(text);
+    let mut ranges = Vec::new();
+    ranges.extend(word(text, offset));
+    ranges.extend(
+        root.path_to(offset)
+            .into_iter()
+            // Text nodes are split arbitrarily, e.g. at brackets, and the
+            // document is added last.
+            .filter(|node| !matches!(node.kind, NodeKind::Text | NodeKind::Document))
+            .map(|node| {
+                // Block ranges include the trailing line break.
+                let trimmed = text[node.range].trim_end();
+                TextRange::at(node.range.start(), TextSize::of(trimmed))
+            }),
+    );
+    ranges.extend(
+        analysis
+            .headings
+            .iter()
+            .map(|heading| analysis.section(heading, len))
+            .filter(|section| section.contains_inclusive(offset)),
+    );
+    ranges.push(TextRange::up_to(len));
+
+    ranges.sort_by_key(|range| range.len());
+    let mut expansion: Vec<TextRange> = Vec::new();
+    for range in ranges {
+        if !range.contains_inclusive(offset) {
+            continue;
+        }
+        match expansion.last() {
+            Some(last) if range == *last || !range.contains_range(*last) => {}
+            _ => expansion.push(range),
+        }
+    }
+    expansion
+}
+
+/// Returns the range of the word at an offset.
+fn word(text: &str, offset: TextSize) -> Option<TextRange> {
+    let offset = usize::from(offset);
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let (before, after) = (text.get(..offset)?, text.get(offset..)?);
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_word(c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = after
+        .char_indices()
+        .find(|&(_, c)| !is_word(c))
+        .map_or(text.len(), |(i, _)| offset + i);
+    (start < end).then(|| TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32)))
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    #[test]
+    fn test_selection_ranges() {
+        let text = "# A\n\n## B\n\nIntro.\n\n- x *yz w*\n- v\n\n## C\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let root = Node::parse(text);
+        let offset = TextSize::from(text.find("yz").unwrap() as u32 + 1);
+
+        let ranges = selection_ranges(text, &analysis, &root, offset)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranges,
+            [
+                "yz",
+                "*yz w*",
+                "- x *yz w*",
+                "- x *yz w*\n- v",
+                "## B\n\nIntro.\n\n- x *yz w*\n- v\n\n",
+                "# A\n\n## B\n\nIntro.\n\n- x *yz w*\n- v\n\n## C\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_word() {
+        let text = "ab ü";
+        assert_eq!(
+            word(text, 1.into()),
+            Some(TextRange::new(0.into(), 2.into()))
+        );
+        assert_eq!(word(text, 4.into()), None);
+        assert_eq!(word(text, 9.into()), None);
+    }
+}
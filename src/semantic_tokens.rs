@@ -0,0 +1,264 @@
+//! Semantic highlighting.
+//!
+//! Markdown constructs nest, e.g. a code span in a link in a heading, but
+//! semantic tokens can't overlap, so the innermost construct wins and the
+//! tokens of the outer ones are split around it.
+
+use pulldown_cmark::{Event, Parser, Tag};
+use text_edit::{TextRange, TextSize};
+
+use crate::analysis::{front_matter, parser_options, text_range, Analysis};
+
+/// The token type names, in the order of [`TokenKind::index`].
+pub(crate) const TOKEN_TYPES: [&str; 12] = [
+    "heading",
+    "emphasis",
+    "strong",
+    "strikethrough",
+    "codeSpan",
+    "linkText",
+    "linkDestination",
+    "referenceLabel",
+    "footnote",
+    "html",
+    "frontMatter",
+    "taskMarker",
+];
+
+/// The token modifier names, where bit `n - 1` is the level of a heading.
+pub(crate) const TOKEN_MODIFIERS: [&str; 6] =
+    ["level1", "level2", "level3", "level4", "level5", "level6"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Heading(usize),
+    Emphasis,
+    Strong,
+    Strikethrough,
+    CodeSpan,
+    LinkText,
+    LinkDestination,
+    ReferenceLabel,
+    Footnote,
+    Html,
+    FrontMatter,
+    TaskMarker,
+}
+
+impl TokenKind {
+    pub(crate) fn index(self) -> u32 {
+        match self {
+            TokenKind::Heading(_) => 0,
+            TokenKind::Emphasis => 1,
+            TokenKind::Strong => 2,
+            TokenKind::Strikethrough => 3,
+            TokenKind::CodeSpan => 4,
+            TokenKind::LinkText => 5,
+            TokenKind::LinkDestination => 6,
+            TokenKind::ReferenceLabel => 7,
+            TokenKind::Footnote => 8,
+            TokenKind::Html => 9,
+            TokenKind::FrontMatter => 10,
+            TokenKind::TaskMarker => 11,
+        }
+    }
+
+    pub(crate) fn modifiers(self) -> u32 {
+        match self {
+            TokenKind::Heading(level) => 1 << (level - 1),
+            _ => 0,
+        }
+    }
+}
+
+/// A token that spans a single line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub(crate) range: TextRange,
+    pub(crate) kind: TokenKind,
+}
+
+/// Returns the tokens of a document in order.
+pub(crate) fn semantic_tokens(text: &str, analysis: &Analysis) -> Vec<Token> {
+    let front_matter = front_matter(text);
+    let in_front_matter = |range: TextRange| {
+        front_matter.is_some_and(|front_matter| front_matter.contains(range.start()))
+    };
+
+    let mut spans = Vec::new();
+    spans.extend(front_matter.map(|range| (range, TokenKind::FrontMatter)));
+
+    // The start and end of the text of each link being parsed.
+    let mut link_texts: Vec<Option<(usize, usize)>> = Vec::new();
+    for (event, range) in Parser::new_ext(text, parser_options()).into_offset_iter() {
+        if in_front_matter(text_range(range.clone())) {
+            continue;
+        }
+        let is_link_end = matches!(event, Event::End(Tag::Link(..) | Tag::Image(..)));
+        if let (Some(link_text), false) = (link_texts.last_mut(), is_link_end) {
+            let (start, end) = link_text.get_or_insert((range.start, range.end));
+            *start = (*start).min(range.start);
+            *end = (*end).max(range.end);
+        }
+
+        let kind = match event {
+            Event::Start(Tag::Heading(level, ..)) => TokenKind::Heading(level as usize),
+            Event::Start(Tag::Emphasis) => TokenKind::Emphasis,
+            Event::Start(Tag::Strong) => TokenKind::Strong,
+            Event::Start(Tag::Strikethrough) => TokenKind::Strikethrough,
+            Event::Code(_) => TokenKind::CodeSpan,
+            Event::Html(_) => TokenKind::Html,
+            Event::TaskListMarker(_) => TokenKind::TaskMarker,
+            Event::Start(Tag::Link(..) | Tag::Image(..)) => {
+                link_texts.push(None);
+                continue;
+            }
+            Event::End(Tag::Link(..) | Tag::Image(..)) => {
+                if let Some((start, end)) = link_texts.pop().flatten() {
+                    spans.push((text_range(start..end), TokenKind::LinkText));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        spans.push((text_range(range), kind));
+    }
+
+    for link in &analysis.links {
+        spans.extend(
+            link.destination_range
+                .map(|range| (range, TokenKind::LinkDestination)),
+        );
+        spans.extend(
+            link.label
+                .as_ref()
+                .map(|label| (label.range, TokenKind::ReferenceLabel)),
+        );
+    }
+    for definition in &analysis.definitions {
+        spans.push((definition.label.range, TokenKind::ReferenceLabel));
+        spans.extend(
+            definition
+                .destination_range
+                .map(|range| (range, TokenKind::LinkDestination)),
+        );
+    }
+    let footnotes = analysis
+        .footnote_references
+        .iter()
+        .chain(analysis.footnote_definitions.iter().map(|def| &def.label));
+    for label in footnotes {
+        // Include the `[^` and `]`.
+        let range = TextRange::new(
+            label.range.start() - TextSize::from(2),
+            label.range.end() + TextSize::from(1),
+        );
+        spans.push((range, TokenKind::Footnote));
+    }
+
+    spans.retain(|&(range, kind)| kind == TokenKind::FrontMatter || !in_front_matter(range));
+    flatten(text, spans)
+}
+
+/// Splits overlapping spans into tokens, with inner spans taking precedence,
+/// and tokens into lines.
+#[allow(clippy::string_slice)]
+fn flatten(text: &str, mut spans: Vec<(TextRange, TokenKind)>) -> Vec<Token> {
+    // Outer spans first, so that inner ones are painted over them.
+    spans.sort_by_key(|(range, _)| (range.start(), std::cmp::Reverse(range.end())));
+    let mut painted = vec![None; text.len()];
+    for (range, kind) in spans {
+        let range = usize::from(range.start())..usize::from(range.end()).min(text.len());
+        painted[range].fill(Some(kind));
+    }
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while start < painted.len() {
+        let kind = painted[start];
+        let end = painted[start..]
+            .iter()
+            .position(|other| *other != kind)
+            .map_or(painted.len(), |len| start + len);
+        if let Some(kind) = kind {
+            let mut line_start = start;
+            for line in text[start..end].split_inclusive('\n') {
+                let trimmed = line.trim_start();
+                let token_start = line_start + line.len() - trimmed.len();
+                let token_end = token_start + trimmed.trim_end().len();
+                if token_start < token_end {
+                    tokens.push(Token {
+                        range: text_range(token_start..token_end),
+                        kind,
+                    });
+                }
+                line_start += line.len();
+            }
+        }
+        start = end;
+    }
+    tokens
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    fn tokens(text: &str) -> Vec<(&str, TokenKind)> {
+        let analysis = Analysis::new(text, SlugStyle::default());
+        semantic_tokens(text, &analysis)
+            .into_iter()
+            .map(|token| (&text[token.range], token.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            tokens("## A *b* `c`\n\n- [x] ~~d~~ **e\nf**\n"),
+            [
+                ("## A", TokenKind::Heading(2)),
+                ("*b*", TokenKind::Emphasis),
+                ("`c`", TokenKind::CodeSpan),
+                ("[x]", TokenKind::TaskMarker),
+                ("~~d~~", TokenKind::Strikethrough),
+                ("**e", TokenKind::Strong),
+                ("f**", TokenKind::Strong),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(
+            tokens("[a `b`](c.md) [d][e] [^1] <span>\n\n[e]: <f.md>\n[^1]: g\n"),
+            [
+                ("a", TokenKind::LinkText),
+                ("`b`", TokenKind::CodeSpan),
+                ("c.md", TokenKind::LinkDestination),
+                ("d", TokenKind::LinkText),
+                ("e", TokenKind::ReferenceLabel),
+                ("[^1]", TokenKind::Footnote),
+                ("<span>", TokenKind::Html),
+                ("e", TokenKind::ReferenceLabel),
+                ("f.md", TokenKind::LinkDestination),
+                ("[^1]", TokenKind::Footnote),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_front_matter() {
+        assert_eq!(
+            tokens("---\ntitle: *a*\n---\n# B\n"),
+            [
+                ("---", TokenKind::FrontMatter),
+                ("title: *a*", TokenKind::FrontMatter),
+                ("---", TokenKind::FrontMatter),
+                ("# B", TokenKind::Heading(1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Heading anchor generation.
+//!
+//! Every renderer turns heading text into anchors differently, and
+//! disambiguates duplicate anchors differently too, so links only resolve if
+//! anchors are generated the way the workspace's renderer does it.
+
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+/// The renderer whose heading anchors are replicated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SlugStyle {
+    #[default]
+    Github,
+    Gitlab,
+    Mdbook,
+    Pandoc,
+    Hugo,
+}
+
+impl SlugStyle {
+    /// Returns the anchor of a heading, before duplicates are numbered.
+    pub(crate) fn slug(self, text: &str) -> String {
+        match self {
+            SlugStyle::Github | SlugStyle::Hugo => github(text),
+            SlugStyle::Gitlab => gitlab(text),
+            SlugStyle::Mdbook => mdbook(text),
+            SlugStyle::Pandoc => pandoc(text),
+        }
+    }
+
+    /// Whether duplicates are numbered by counting the base anchor only, so
+    /// that e.g. the second `Intro` and `Intro 1` both become `intro-1`.
+    fn counts_duplicates(self) -> bool {
+        matches!(self, SlugStyle::Gitlab | SlugStyle::Mdbook)
+    }
+}
+
+/// Lowercases the text, removes punctuation and turns spaces into hyphens.
+fn github(text: &str) -> String {
+    text.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Like GitHub, but runs of hyphens are squeezed and anchors consisting only
+/// of digits are prefixed with `anchor-`.
+fn gitlab(text: &str) -> String {
+    let mut slug = String::new();
+    for c in github(text).chars() {
+        if !(c == '-' && slug.ends_with('-')) {
+            slug.push(c);
+        }
+    }
+    if !slug.is_empty() && slug.chars().all(|c| c.is_ascii_digit()) {
+        slug.insert_str(0, "anchor-");
+    }
+    slug
+}
+
+/// Only ASCII letters are lowercased, and any whitespace becomes a hyphen.
+fn mdbook(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| match c {
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c.to_ascii_lowercase()),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Periods are kept and everything before the first letter is removed.
+fn pandoc(text: &str) -> String {
+    let slug = text
+        .trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            '-' | '_' | '.' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .skip_while(|c| !c.is_alphabetic())
+        .collect::<String>();
+    if slug.is_empty() {
+        "section".to_owned()
+    } else {
+        slug
+    }
+}
+
+/// Generates unique anchors for the headings of a document.
+///
+/// Renderers disambiguate duplicate anchors by appending `-1`, `-2`, etc. in
+/// document order, so the headings have to be slugged in order too.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Slugger {
+    style: SlugStyle,
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub(crate) fn new(style: SlugStyle) -> Self {
+        Self {
+            style,
+            seen: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn slug(&mut self, text: &str) -> String {
+        let slug = self.style.slug(text);
+        if !self.style.counts_duplicates() {
+            return self.unique(&slug);
+        }
+
+        let count = self.seen.entry(slug.clone()).or_insert(0);
+        *count += 1;
+        match *count - 1 {
+            0 => slug,
+            n => format!("{slug}-{n}"),
+        }
+    }
+
+    /// Reserves an explicit `{#id}` so that generated anchors avoid it.
+    pub(crate) fn explicit(&mut self, id: &str) -> String {
+        let count = self.seen.entry(id.to_owned()).or_insert(0);
+        if self.style.counts_duplicates() {
+            *count += 1;
+        }
+        id.to_owned()
+    }
+
+    fn unique(&mut self, slug: &str) -> String {
+        let mut candidate = slug.to_owned();
+        while self.seen.contains_key(&candidate) {
+            let count = self.seen.get_mut(slug).expect("slug was seen");
+            *count += 1;
+            candidate = format!("{slug}-{count}");
+        }
+        self.seen.insert(candidate.clone(), 0);
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slugs(style: SlugStyle, headings: &[&str]) -> Vec<String> {
+        let mut slugger = Slugger::new(style);
+        headings.iter().map(|text| slugger.slug(text)).collect()
+    }
+
+    #[test]
+    fn test_github() {
+        assert_eq!(github("Hello, World!"), "hello-world");
+        assert_eq!(github("  `fmt::fmt` & co. "), "fmtfmt--co");
+        assert_eq!(github("snake_case and-kebab"), "snake_case-and-kebab");
+        assert_eq!(github("Ünïcödé"), "ünïcödé");
+    }
+
+    #[test]
+    fn test_gitlab() {
+        assert_eq!(gitlab("fmt & co."), "fmt-co");
+        assert_eq!(gitlab("2022"), "anchor-2022");
+    }
+
+    #[test]
+    fn test_mdbook() {
+        assert_eq!(mdbook("Hello,  World!"), "hello--world");
+        assert_eq!(mdbook("Ünïcödé"), "Ünïcödé");
+    }
+
+    #[test]
+    fn test_pandoc() {
+        assert_eq!(pandoc("1.2 Version 2.0!"), "version-2.0");
+        assert_eq!(pandoc("123"), "section");
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let headings = ["Intro", "Intro", "Intro 1", "Intro"];
+        assert_eq!(
+            slugs(SlugStyle::Github, &headings),
+            ["intro", "intro-1", "intro-1-1", "intro-2"]
+        );
+        assert_eq!(
+            slugs(SlugStyle::Mdbook, &headings),
+            ["intro", "intro-1", "intro-1", "intro-2"]
+        );
+    }
+
+    #[test]
+    fn test_explicit() {
+        for style in [SlugStyle::Github, SlugStyle::Gitlab] {
+            let mut slugger = Slugger::new(style);
+            assert_eq!(slugger.explicit("intro"), "intro");
+            assert_eq!(slugger.slug("Intro"), "intro-1");
+        }
+    }
+}
@@ -0,0 +1,758 @@
+//! Spell checking with Hunspell dictionaries.
+//!
+//! The dictionary is read from the `.aff` and `.dic` files at the configured
+//! path, and every word form is generated when it is loaded, so checking a
+//! word is a lookup. Only prefixes, suffixes, `NEEDAFFIX` and `FORBIDDENWORD`
+//! are supported, so words that Hunspell would accept through compounding or
+//! continuation classes are reported.
+//!
+//! Code, URLs, HTML and the front matter aren't checked. Words that aren't in
+//! the dictionary can be added to a words file in the workspace, which has one
+//! word per line.
+
+use std::{
+    fs,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use hashbrown::{HashMap, HashSet};
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use serde::Deserialize;
+use text_edit::{TextEdit, TextRange};
+
+use crate::{
+    analysis::{front_matter, parser_options, text_range},
+    diagnostics::{Diagnostic, Fix, Severity},
+    lint::urls,
+};
+
+/// The diagnostic code of unknown words.
+pub(crate) const CODE: &str = "spelling";
+
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Bounds the words two edits away that are looked up, since there are
+/// hundreds of thousands of them for longer words.
+const MAX_SECOND_EDITS: usize = 100_000;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct SpellConfig {
+    /// The path of the Hunspell dictionary, with or without the `.dic`
+    /// extension and relative to the workspace root. Without one, spelling
+    /// isn't checked.
+    pub(crate) dictionary: Option<String>,
+    /// The file with the words of the workspace, relative to its root.
+    pub(crate) words_file: String,
+}
+
+impl Default for SpellConfig {
+    fn default() -> Self {
+        Self {
+            dictionary: None,
+            words_file: ".mdls-words.txt".to_owned(),
+        }
+    }
+}
+
+/// How the `.aff` and `.dic` files write affix flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FlagMode {
+    /// A character per flag.
+    Char,
+    /// Two characters per flag.
+    Long,
+    /// Comma-separated numbers.
+    Num,
+}
+
+impl FlagMode {
+    fn parse(self, flags: &str) -> Vec<u32> {
+        match self {
+            FlagMode::Char => flags.chars().map(u32::from).collect(),
+            FlagMode::Long => {
+                let chars = flags.chars().collect::<Vec<_>>();
+                chars
+                    .chunks(2)
+                    .map(|pair| pair.iter().fold(0, |flag, &c| flag << 16 | u32::from(c)))
+                    .collect()
+            }
+            FlagMode::Num => flags
+                .split(',')
+                .filter_map(|flag| flag.trim().parse().ok())
+                .collect(),
+        }
+    }
+}
+
+/// A character class in an affix condition, where `.` is the empty negated
+/// class.
+#[derive(Clone, Debug)]
+struct CharClass {
+    chars: Vec<char>,
+    negated: bool,
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        self.chars.contains(&c) != self.negated
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Affix {
+    strip: String,
+    add: String,
+    condition: Vec<CharClass>,
+    cross_product: bool,
+}
+
+impl Affix {
+    fn apply_suffix(&self, stem: &str) -> Option<String> {
+        let chars = stem.chars().rev().collect::<Vec<_>>();
+        let matches = chars.len() >= self.condition.len()
+            && self
+                .condition
+                .iter()
+                .rev()
+                .zip(&chars)
+                .all(|(class, &c)| class.matches(c));
+        let stem = stem.strip_suffix(self.strip.as_str())?;
+        matches.then(|| format!("{stem}{}", self.add))
+    }
+
+    fn apply_prefix(&self, stem: &str) -> Option<String> {
+        let matches = stem.chars().count() >= self.condition.len()
+            && self
+                .condition
+                .iter()
+                .zip(stem.chars())
+                .all(|(class, c)| class.matches(c));
+        let stem = stem.strip_prefix(self.strip.as_str())?;
+        matches.then(|| format!("{}{stem}", self.add))
+    }
+}
+
+fn parse_condition(condition: &str) -> Vec<CharClass> {
+    if condition == "." {
+        return Vec::new();
+    }
+    let mut classes = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        let class = match c {
+            '.' => CharClass {
+                chars: Vec::new(),
+                negated: true,
+            },
+            '[' => {
+                let set = chars.by_ref().take_while(|&c| c != ']').collect::<String>();
+                match set.strip_prefix('^') {
+                    Some(set) => CharClass {
+                        chars: set.chars().collect(),
+                        negated: true,
+                    },
+                    None => CharClass {
+                        chars: set.chars().collect(),
+                        negated: false,
+                    },
+                }
+            }
+            c => CharClass {
+                chars: vec![c],
+                negated: false,
+            },
+        };
+        classes.push(class);
+    }
+    classes
+}
+
+/// The parts of an `.aff` file needed to generate word forms and suggestions.
+#[derive(Debug)]
+struct AffixFile {
+    flag_mode: FlagMode,
+    /// Flag sets that the `.dic` file refers to by number.
+    aliases: Vec<String>,
+    prefixes: HashMap<u32, Vec<Affix>>,
+    suffixes: HashMap<u32, Vec<Affix>>,
+    need_affix: Option<u32>,
+    forbidden: Option<u32>,
+    try_chars: Vec<char>,
+    replacements: Vec<(String, String)>,
+}
+
+impl AffixFile {
+    fn parse(aff: &str) -> Self {
+        let mut file = AffixFile {
+            flag_mode: FlagMode::Char,
+            aliases: Vec::new(),
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+            need_affix: None,
+            forbidden: None,
+            try_chars: Vec::new(),
+            replacements: Vec::new(),
+        };
+        let mut cross_products = HashMap::<(bool, u32), bool>::new();
+        for line in aff.lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields[..] {
+                ["FLAG", "long"] => file.flag_mode = FlagMode::Long,
+                ["FLAG", "num"] => file.flag_mode = FlagMode::Num,
+                ["TRY", chars, ..] => file.try_chars = chars.chars().collect(),
+                ["AF", flags, ..] if flags.parse::<usize>().is_err() => {
+                    file.aliases.push(flags.to_owned());
+                }
+                ["REP", from, to, ..] => {
+                    let from = from.trim_start_matches('^').trim_end_matches('$');
+                    let to = to.trim_start_matches('^').trim_end_matches('$');
+                    file.replacements
+                        .push((from.replace('_', " "), to.replace('_', " ")));
+                }
+                ["NEEDAFFIX" | "PSEUDOROOT", flag, ..] => {
+                    file.need_affix = file.flag_mode.parse(flag).first().copied();
+                }
+                ["FORBIDDENWORD", flag, ..] => {
+                    file.forbidden = file.flag_mode.parse(flag).first().copied();
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, ref rest @ ..] => {
+                    let is_prefix = kind == "PFX";
+                    let Some(&flag) = file.flag_mode.parse(flag).first() else {
+                        continue;
+                    };
+                    // The first line of an affix class is its header.
+                    let Some(&cross_product) = cross_products.get(&(is_prefix, flag)) else {
+                        cross_products.insert((is_prefix, flag), strip == "Y");
+                        continue;
+                    };
+                    // Continuation classes after the `/` aren't supported.
+                    let add = add.split('/').next().unwrap_or_default();
+                    let affix = Affix {
+                        strip: if strip == "0" { "" } else { strip }.to_owned(),
+                        add: if add == "0" { "" } else { add }.to_owned(),
+                        condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                        cross_product,
+                    };
+                    let affixes = if is_prefix {
+                        &mut file.prefixes
+                    } else {
+                        &mut file.suffixes
+                    };
+                    affixes.entry(flag).or_default().push(affix);
+                }
+                _ => {}
+            }
+        }
+        file
+    }
+
+    fn flags(&self, flags: &str) -> Vec<u32> {
+        match flags.parse::<usize>() {
+            Ok(alias) if !self.aliases.is_empty() => self
+                .aliases
+                .get(alias.wrapping_sub(1))
+                .map(|flags| self.flag_mode.parse(flags))
+                .unwrap_or_default(),
+            _ => self.flag_mode.parse(flags),
+        }
+    }
+
+    /// Returns the word forms of a stem.
+    fn forms(&self, stem: &str, flags: &[u32]) -> Vec<String> {
+        let mut forms = Vec::new();
+        if !self.need_affix.is_some_and(|flag| flags.contains(&flag)) {
+            forms.push(stem.to_owned());
+        }
+        let affixes = |affixes: &'_ HashMap<u32, Vec<Affix>>| {
+            flags
+                .iter()
+                .filter_map(|flag| affixes.get(flag))
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let prefixes = affixes(&self.prefixes);
+        for suffix in affixes(&self.suffixes) {
+            let Some(form) = suffix.apply_suffix(stem) else {
+                continue;
+            };
+            if suffix.cross_product {
+                forms.extend(
+                    prefixes
+                        .iter()
+                        .filter(|prefix| prefix.cross_product)
+                        .filter_map(|prefix| prefix.apply_prefix(&form)),
+                );
+            }
+            forms.push(form);
+        }
+        forms.extend(
+            prefixes
+                .iter()
+                .filter_map(|prefix| prefix.apply_prefix(stem)),
+        );
+        forms
+    }
+}
+
+/// Every word form of a Hunspell dictionary.
+#[derive(Debug, Default)]
+pub(crate) struct Dictionary {
+    words: HashSet<String>,
+    /// The characters suggestions try, most common first.
+    try_chars: Vec<char>,
+    /// Common misspellings and their corrections.
+    replacements: Vec<(String, String)>,
+}
+
+impl Dictionary {
+    pub(crate) fn parse(aff: &str, dic: &str) -> Self {
+        let aff = AffixFile::parse(aff);
+        let mut words = HashSet::new();
+        let mut forbidden = Vec::new();
+        // The first line is the number of words.
+        for line in dic.lines().skip(1) {
+            let Some(entry) = line.split_whitespace().next() else {
+                continue;
+            };
+            let (stem, flags) = split_entry(entry);
+            let flags = aff.flags(flags);
+            if aff.forbidden.is_some_and(|flag| flags.contains(&flag)) {
+                forbidden.push(stem);
+                continue;
+            }
+            words.extend(aff.forms(&stem, &flags));
+        }
+        for word in &forbidden {
+            words.remove(word);
+        }
+
+        let try_chars = if aff.try_chars.is_empty() {
+            "esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'"
+                .chars()
+                .collect()
+        } else {
+            aff.try_chars
+        };
+        Self {
+            words,
+            try_chars,
+            replacements: aff.replacements,
+        }
+    }
+
+    /// Loads the `.aff` and `.dic` files with the same name.
+    pub(crate) fn load(path: &Path) -> io::Result<Self> {
+        let read = |extension| {
+            let bytes = fs::read(path.with_extension(extension))?;
+            Ok::<_, io::Error>(decode(&bytes))
+        };
+        let (aff, dic) = (read("aff")?, read("dic")?);
+        Ok(Self::parse(&aff, &dic))
+    }
+}
+
+/// Splits a `.dic` entry into the word, where `\/` is a slash, and its flags.
+#[allow(clippy::string_slice)]
+fn split_entry(entry: &str) -> (String, &str) {
+    let mut word = String::new();
+    let mut chars = entry.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => word.extend(chars.next().map(|(_, c)| c)),
+            '/' => return (word, &entry[index + 1..]),
+            c => word.push(c),
+        }
+    }
+    (word, "")
+}
+
+/// Decodes a dictionary file, which is in UTF-8 or in the ISO 8859-1 its
+/// `SET` line names.
+fn decode(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let is_latin1 = text
+        .lines()
+        .any(|line| matches!(line.trim(), "SET ISO8859-1" | "SET ISO-8859-1"));
+    match std::str::from_utf8(bytes) {
+        Ok(text) if !is_latin1 => text.to_owned(),
+        _ if is_latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+        _ => text.into_owned(),
+    }
+}
+
+/// A dictionary and the words of the workspace.
+#[derive(Debug)]
+pub(crate) struct SpellChecker {
+    dictionary: Dictionary,
+    words_file: Option<PathBuf>,
+    words: HashSet<String>,
+    /// When the words file was last read, to notice changes.
+    modified: Option<SystemTime>,
+}
+
+impl SpellChecker {
+    pub(crate) fn new(dictionary: Dictionary, words_file: Option<PathBuf>) -> Self {
+        let mut checker = Self {
+            dictionary,
+            words_file,
+            words: HashSet::new(),
+            modified: None,
+        };
+        checker.refresh();
+        checker
+    }
+
+    /// Loads the configured dictionary, or returns `None` if there is none.
+    pub(crate) fn load(config: &SpellConfig, root: Option<&Path>) -> Option<Self> {
+        let resolve = |path: &str| match root {
+            Some(root) => root.join(path),
+            None => PathBuf::from(path),
+        };
+        let path = resolve(config.dictionary.as_deref()?);
+        let dictionary = Dictionary::load(&path)
+            .map_err(|error| tracing::warn!("can't read dictionary {}: {}", path.display(), error))
+            .ok()?;
+        let words_file = root.map(|root| root.join(&config.words_file));
+        Some(Self::new(dictionary, words_file))
+    }
+
+    /// Rereads the words file if it changed since it was last read.
+    pub(crate) fn refresh(&mut self) {
+        let Some(path) = &self.words_file else { return };
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        let words = fs::read_to_string(path).unwrap_or_default();
+        self.words = words
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+    }
+
+    pub(crate) fn words_file(&self) -> Option<&Path> {
+        self.words_file.as_deref()
+    }
+
+    /// Appends a word to the words file.
+    pub(crate) fn add_word(&mut self, word: &str) -> io::Result<()> {
+        let Some(path) = &self.words_file else {
+            return Ok(());
+        };
+        let existing = fs::read_to_string(path).unwrap_or_default();
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        if !existing.is_empty() && !existing.ends_with('\n') {
+            writeln!(file)?;
+        }
+        writeln!(file, "{word}")?;
+        self.words.insert(word.to_owned());
+        self.modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        Ok(())
+    }
+
+    pub(crate) fn is_correct(&self, word: &str) -> bool {
+        let word = word.replace('’', "'");
+        case_variants(&word)
+            .iter()
+            .any(|word| self.dictionary.words.contains(word) || self.words.contains(word))
+    }
+
+    /// Returns the known words one or two edits away from a word, with the
+    /// dictionary's common misspellings first.
+    pub(crate) fn suggest(&self, word: &str) -> Vec<String> {
+        let lower = word.replace('’', "'").to_lowercase();
+        let mut candidates = Vec::new();
+        for (from, to) in &self.dictionary.replacements {
+            for (index, _) in lower.match_indices(from.as_str()) {
+                let mut candidate = lower.clone();
+                candidate.replace_range(index..index + from.len(), to);
+                candidates.push(candidate);
+            }
+        }
+        let edits = edits(&lower, &self.dictionary.try_chars);
+        candidates.extend(edits.iter().cloned());
+
+        let mut suggestions = Vec::new();
+        let mut push = |candidate: String| {
+            // Suggestions differing only in case are the same to the user.
+            let is_new = !suggestions
+                .iter()
+                .any(|suggestion: &String| suggestion.to_lowercase() == candidate.to_lowercase());
+            if is_new && self.is_correct(&candidate) {
+                suggestions.push(candidate);
+            }
+            suggestions.len() < MAX_SUGGESTIONS
+        };
+        if candidates.into_iter().all(&mut push) {
+            // Only look further if nothing is close.
+            let _ = edits
+                .iter()
+                .flat_map(|edit| self::edits(edit, &self.dictionary.try_chars))
+                .take(MAX_SECOND_EDITS)
+                .all(&mut push);
+        }
+        suggestions
+            .into_iter()
+            .map(|suggestion| match_case(word, &suggestion))
+            .collect()
+    }
+}
+
+/// Returns the forms of a word that the dictionary may have: the word itself,
+/// and for capitalized and uppercase words their lowercase forms.
+fn case_variants(word: &str) -> Vec<String> {
+    let mut variants = vec![word.to_owned()];
+    let mut chars = word.chars();
+    let Some(first) = chars.next() else {
+        return variants;
+    };
+    let rest = chars.as_str();
+    if first.is_uppercase() && rest.chars().all(|c| !c.is_uppercase()) {
+        variants.push(format!("{}{rest}", first.to_lowercase()));
+    } else if word.chars().all(|c| !c.is_lowercase()) {
+        variants.push(word.to_lowercase());
+        variants.push(format!("{first}{}", rest.to_lowercase()));
+    }
+    variants
+}
+
+fn match_case(word: &str, suggestion: &str) -> String {
+    let mut chars = word.chars();
+    let capitalized = chars.next().is_some_and(char::is_uppercase);
+    if capitalized && chars.clone().count() > 0 && chars.all(|c| !c.is_lowercase()) {
+        return suggestion.to_uppercase();
+    }
+    let mut suggestion_chars = suggestion.chars();
+    match suggestion_chars.next() {
+        Some(first) if capitalized => {
+            format!("{}{}", first.to_uppercase(), suggestion_chars.as_str())
+        }
+        _ => suggestion.to_owned(),
+    }
+}
+
+/// Returns the words one deletion, transposition, replacement or insertion
+/// away.
+fn edits(word: &str, try_chars: &[char]) -> Vec<String> {
+    let chars = word.chars().collect::<Vec<_>>();
+    let mut edits = Vec::new();
+    for index in 0..chars.len() {
+        let mut edit = chars.clone();
+        edit.remove(index);
+        edits.push(edit.into_iter().collect());
+        if index + 1 < chars.len() {
+            let mut edit = chars.clone();
+            edit.swap(index, index + 1);
+            edits.push(edit.into_iter().collect());
+        }
+        for &c in try_chars.iter().filter(|&&c| c != chars[index]) {
+            let mut edit = chars.clone();
+            edit[index] = c;
+            edits.push(edit.into_iter().collect());
+        }
+    }
+    for index in 0..=chars.len() {
+        for &c in try_chars {
+            let mut edit = chars.clone();
+            edit.insert(index, c);
+            edits.push(edit.into_iter().collect());
+        }
+    }
+    edits
+}
+
+/// Returns the words of the prose of a document.
+///
+/// Words that look like code, such as `snake_case`, `camelCase` and file
+/// names, and uppercase abbreviations are left out.
+#[allow(clippy::string_slice)]
+pub(crate) fn words(text: &str) -> Vec<TextRange> {
+    let prose_start = front_matter(text).map_or(0.into(), TextRange::end);
+    let mut words = Vec::new();
+    let mut in_code_block = false;
+    let mut in_autolink = false;
+    for (event, range) in Parser::new_ext(text, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Start(Tag::Link(LinkType::Autolink | LinkType::Email, ..)) => {
+                in_autolink = true;
+            }
+            Event::End(Tag::Link(LinkType::Autolink | LinkType::Email, ..)) => {
+                in_autolink = false;
+            }
+            Event::Text(_) if !in_code_block && !in_autolink => {
+                let range = text_range(range);
+                if range.start() < prose_start {
+                    continue;
+                }
+                let urls = urls(&text[range]);
+                words.extend(
+                    split_words(&text[range])
+                        .into_iter()
+                        .filter(|word| !urls.iter().any(|url| url.contains_range(*word)))
+                        .map(|word| word + range.start()),
+                );
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+#[allow(clippy::string_slice)]
+fn split_words(text: &str) -> Vec<TextRange> {
+    let is_apostrophe = |c| c == '\'' || c == '’';
+    let mut words = Vec::new();
+    for chunk in runs(text, |c| !c.is_whitespace()) {
+        let chunk = trim(text, chunk, char::is_alphanumeric);
+        let looks_like_code = text[chunk.clone()].contains(|c: char| {
+            c.is_numeric() || matches!(c, '_' | '.' | '@' | '/' | '\\' | ':' | '=' | '#' | '$')
+        });
+        if looks_like_code {
+            continue;
+        }
+        for part in runs(&text[chunk.clone()], |c| {
+            c.is_alphabetic() || is_apostrophe(c)
+        }) {
+            let part = part.start + chunk.start..part.end + chunk.start;
+            let word = trim(text, part, char::is_alphabetic);
+            let mut chars = text[word.clone()].chars();
+            let is_abbreviation = chars.clone().all(|c| !c.is_lowercase());
+            chars.next();
+            let is_camel_case =
+                chars.clone().any(char::is_uppercase) && chars.any(char::is_lowercase);
+            if word.len() > 1 && !is_abbreviation && !is_camel_case {
+                words.push(text_range(word));
+            }
+        }
+    }
+    words
+}
+
+/// Returns the ranges of the runs of characters matching a predicate.
+fn runs(text: &str, predicate: impl Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (predicate(c), start) {
+            (true, None) => start = Some(index),
+            (false, Some(run_start)) => {
+                runs.push(run_start..index);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs.extend(start.map(|start| start..text.len()));
+    runs
+}
+
+/// Shrinks a range to start and end with characters matching a predicate.
+#[allow(clippy::string_slice)]
+fn trim(text: &str, range: Range<usize>, predicate: impl Fn(char) -> bool) -> Range<usize> {
+    let content = &text[range.clone()];
+    let trimmed = content.trim_start_matches(|c| !predicate(c));
+    let start = range.end - trimmed.len();
+    let end = start + trimmed.trim_end_matches(|c| !predicate(c)).len();
+    start..end
+}
+
+/// Reports the words that are neither in the dictionary nor in the words
+/// file.
+#[allow(clippy::string_slice)]
+pub(crate) fn diagnostics(text: &str, checker: &SpellChecker) -> Vec<Diagnostic> {
+    words(text)
+        .into_iter()
+        .filter(|&range| !checker.is_correct(&text[range]))
+        .map(|range| {
+            Diagnostic::new(
+                range,
+                Severity::Information,
+                CODE,
+                format!("unknown word `{}`", &text[range]),
+            )
+        })
+        .collect()
+}
+
+/// Returns fixes replacing an unknown word with the suggestions for it.
+pub(crate) fn fixes(word: &str, range: TextRange, checker: &SpellChecker) -> Vec<Fix> {
+    checker
+        .suggest(word)
+        .into_iter()
+        .map(|suggestion| Fix {
+            title: format!("Change to `{suggestion}`"),
+            edit: TextEdit::replace(range, suggestion),
+            safe: false,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\nTRY esianrtolcdugmphbyfvkwz\nREP 1\nREP f ph\n\n\
+                       PFX U Y 1\nPFX U 0 un .\n\n\
+                       SFX S Y 2\nSFX S 0 s [^y]\nSFX S y ies [^aeiou]y\n\n\
+                       SFX D N 1\nSFX D 0 ed .\n";
+    const DIC: &str = "5\ndoubt/USD\nstory/S\nphone/S\ncolour\nteh/X\n";
+
+    fn checker() -> SpellChecker {
+        let aff = format!("{AFF}FORBIDDENWORD X\n");
+        SpellChecker::new(Dictionary::parse(&aff, DIC), None)
+    }
+
+    #[test]
+    fn test_word_forms() {
+        let checker = checker();
+        for word in [
+            "doubt", "doubts", "undoubts", "undoubt", "doubted", "stories", "Phone", "PHONES",
+        ] {
+            assert!(checker.is_correct(word), "{word}");
+        }
+        for word in ["undoubted", "storys", "teh", "colOur"] {
+            assert!(!checker.is_correct(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        let checker = checker();
+        assert_eq!(checker.suggest("stries"), ["stories"]);
+        assert_eq!(checker.suggest("Fone"), ["Phone"]);
+        assert_eq!(checker.suggest("colr"), ["colour"]);
+    }
+
+    #[test]
+    fn test_words() {
+        let text = "---\ntitle: Mispeled\n---\n\nThe quik-brown fox's `codde` at \
+                    https://exampel.com and <b>tagg</b> <https://linkk.org>, \
+                    snake_case camelCase file.md v2 NASA o'clock.\n\n```\ncodde\n```\n";
+        let words = words(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            ["The", "quik", "brown", "fox's", "at", "and", "tagg", "o'clock"]
+        );
+    }
+}
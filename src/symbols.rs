@@ -0,0 +1,212 @@
+//! The outline of a document: the keys of its front matter and its headings.
+
+use serde_json::Value as Json;
+use text_edit::{TextRange, TextSize};
+
+use crate::{
+    analysis::Analysis,
+    front_matter::{self, Entry, Format, Node, Value},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SymbolKind {
+    FrontMatter,
+    Heading,
+    /// A key whose value is a table or mapping.
+    Object,
+    Array,
+    String,
+    Number,
+    Boolean,
+    Null,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Symbol {
+    pub(crate) name: String,
+    pub(crate) detail: Option<String>,
+    pub(crate) kind: SymbolKind,
+    pub(crate) range: TextRange,
+    /// The part of the range to select when the symbol is picked, such as the
+    /// key of an entry.
+    pub(crate) selection_range: TextRange,
+    pub(crate) children: Vec<Symbol>,
+}
+
+/// Returns the front matter followed by the headings, nested in the headings
+/// of their sections.
+pub(crate) fn symbols(text: &str, analysis: &Analysis) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    if let Some(front_matter) = front_matter::parse(text) {
+        let format = match front_matter.format {
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+        };
+        let fence = TextRange::at(front_matter.range.start(), TextSize::from(3));
+        symbols.push(Symbol {
+            name: "Front matter".to_owned(),
+            detail: Some(format.to_owned()),
+            kind: SymbolKind::FrontMatter,
+            range: front_matter.range,
+            selection_range: fence,
+            children: front_matter.entries.iter().map(entry).collect(),
+        });
+    }
+    symbols.extend(headings(text, analysis));
+    symbols
+}
+
+fn entry(entry: &Entry) -> Symbol {
+    let (kind, detail) = match &entry.value.value {
+        Value::Object(_) => (SymbolKind::Object, None),
+        Value::Array(items) => {
+            let plural = if items.len() == 1 { "" } else { "s" };
+            (
+                SymbolKind::Array,
+                Some(format!("{} item{plural}", items.len())),
+            )
+        }
+        Value::Scalar(Json::String(value)) => (SymbolKind::String, Some(value.clone())),
+        Value::Scalar(Json::Number(value)) => (SymbolKind::Number, Some(value.to_string())),
+        Value::Scalar(Json::Bool(value)) => (SymbolKind::Boolean, Some(value.to_string())),
+        Value::Scalar(_) => (SymbolKind::Null, None),
+    };
+    Symbol {
+        name: entry.key.clone(),
+        detail: detail.map(|detail| detail.lines().next().unwrap_or_default().to_owned()),
+        kind,
+        range: entry.key_range.cover(entry.value.range),
+        selection_range: entry.key_range,
+        children: children(&entry.value),
+    }
+}
+
+/// Returns the entries of a table or mapping, and the tables or mappings in
+/// an array by their index.
+fn children(node: &Node) -> Vec<Symbol> {
+    match &node.value {
+        Value::Object(entries) => entries.iter().map(entry).collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| matches!(item.value, Value::Object(_)))
+            .map(|(index, item)| Symbol {
+                name: index.to_string(),
+                detail: None,
+                kind: SymbolKind::Object,
+                range: item.range,
+                selection_range: item.range,
+                children: children(item),
+            })
+            .collect(),
+        Value::Scalar(_) => Vec::new(),
+    }
+}
+
+fn headings(text: &str, analysis: &Analysis) -> Vec<Symbol> {
+    let len = TextSize::of(text);
+    let mut roots = Vec::new();
+    // The headings whose sections are still open, with their levels.
+    let mut open = Vec::new();
+    for heading in &analysis.headings {
+        close(&mut open, &mut roots, heading.level);
+        let name = if heading.text.trim().is_empty() {
+            "#".repeat(heading.level)
+        } else {
+            heading.text.trim().to_owned()
+        };
+        let symbol = Symbol {
+            name,
+            detail: None,
+            kind: SymbolKind::Heading,
+            range: analysis.section(heading, len),
+            selection_range: heading.range,
+            children: Vec::new(),
+        };
+        open.push((heading.level, symbol));
+    }
+    close(&mut open, &mut roots, 0);
+    roots
+}
+
+/// Closes the sections of the headings at a level or deeper.
+fn close(open: &mut Vec<(usize, Symbol)>, roots: &mut Vec<Symbol>, level: usize) {
+    while open
+        .last()
+        .is_some_and(|(open_level, _)| *open_level >= level)
+    {
+        let (_, symbol) = open.pop().expect("checked above");
+        match open.last_mut() {
+            Some((_, parent)) => parent.children.push(symbol),
+            None => roots.push(symbol),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    /// Formats the symbols as an indented outline.
+    fn outline(symbols: &[Symbol], depth: usize) -> String {
+        let mut outline = String::new();
+        for symbol in symbols {
+            outline.push_str(&"  ".repeat(depth));
+            outline.push_str(&symbol.name);
+            if let Some(detail) = &symbol.detail {
+                write!(outline, " ({detail})").unwrap();
+            }
+            outline.push('\n');
+            outline.push_str(&self::outline(&symbol.children, depth + 1));
+        }
+        outline
+    }
+
+    fn symbols(text: &str) -> Vec<Symbol> {
+        super::symbols(text, &Analysis::new(text, SlugStyle::default()))
+    }
+
+    #[test]
+    fn test_headings() {
+        let text = "# A\n\n## B\n\n### C\n\n## D\n\n# E\n";
+        let symbols = symbols(text);
+        assert_eq!(outline(&symbols, 0), "A\n  B\n    C\n  D\nE\n");
+        assert_eq!(symbols[0].range, TextRange::new(0.into(), 24.into()));
+        assert_eq!(
+            symbols[0].children[0].range,
+            TextRange::new(5.into(), 18.into())
+        );
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let text = "---\n\
+                    title: A\n\
+                    tags: [a, b]\n\
+                    author:\n  name: B\n\
+                    ---\n\
+                    \n\
+                    # A\n";
+        let yaml = symbols(text);
+        assert_eq!(
+            outline(&yaml, 0),
+            "Front matter (YAML)\n  \
+               title (A)\n  \
+               tags (2 items)\n  \
+               author\n    \
+                 name (B)\n\
+             A\n"
+        );
+        assert_eq!(yaml[0].kind, SymbolKind::FrontMatter);
+        assert_eq!(yaml[0].children[1].kind, SymbolKind::Array);
+
+        let text = "+++\n[[menu]]\nname = 'a'\n+++\n";
+        assert_eq!(
+            outline(&symbols(text), 0),
+            "Front matter (TOML)\n  menu (1 item)\n    0\n      name (a)\n"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! A block and inline tree of a document.
+//!
+//! pulldown-cmark produces a flat stream of events; this nests them so that
+//! the constructs around an offset can be found.
+
+use pulldown_cmark::{Event, Parser, Tag};
+use text_edit::{TextRange, TextSize};
+
+use crate::analysis::{parser_options, text_range};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    Document,
+    Paragraph,
+    Heading,
+    BlockQuote,
+    CodeBlock,
+    List,
+    Item,
+    FootnoteDefinition,
+    Table,
+    TableHead,
+    TableRow,
+    TableCell,
+    Emphasis,
+    Strong,
+    Strikethrough,
+    Link,
+    Image,
+    Text,
+    Code,
+    Html,
+    FootnoteReference,
+    Break,
+    Rule,
+    TaskListMarker,
+}
+
+impl NodeKind {
+    fn from_tag(tag: &Tag<'_>) -> Self {
+        match tag {
+            Tag::Paragraph => NodeKind::Paragraph,
+            Tag::Heading(..) => NodeKind::Heading,
+            Tag::BlockQuote => NodeKind::BlockQuote,
+            Tag::CodeBlock(_) => NodeKind::CodeBlock,
+            Tag::List(_) => NodeKind::List,
+            Tag::Item => NodeKind::Item,
+            Tag::FootnoteDefinition(_) => NodeKind::FootnoteDefinition,
+            Tag::Table(_) => NodeKind::Table,
+            Tag::TableHead => NodeKind::TableHead,
+            Tag::TableRow => NodeKind::TableRow,
+            Tag::TableCell => NodeKind::TableCell,
+            Tag::Emphasis => NodeKind::Emphasis,
+            Tag::Strong => NodeKind::Strong,
+            Tag::Strikethrough => NodeKind::Strikethrough,
+            Tag::Link(..) => NodeKind::Link,
+            Tag::Image(..) => NodeKind::Image,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Node {
+    pub(crate) kind: NodeKind,
+    pub(crate) range: TextRange,
+    pub(crate) children: Vec<Node>,
+}
+
+impl Node {
+    /// Parses a document into a tree rooted at a [`NodeKind::Document`].
+    pub(crate) fn parse(text: &str) -> Self {
+        let mut stack = vec![Node::new(
+            NodeKind::Document,
+            TextRange::up_to(TextSize::of(text)),
+        )];
+        for (event, range) in Parser::new_ext(text, parser_options()).into_offset_iter() {
+            let kind = match event {
+                Event::Start(tag) => {
+                    stack.push(Node::new(NodeKind::from_tag(&tag), text_range(range)));
+                    continue;
+                }
+                Event::End(_) => {
+                    let node = stack.pop().expect("unbalanced events");
+                    stack
+                        .last_mut()
+                        .expect("unbalanced events")
+                        .children
+                        .push(node);
+                    continue;
+                }
+                Event::Text(_) => NodeKind::Text,
+                Event::Code(_) => NodeKind::Code,
+                Event::Html(_) => NodeKind::Html,
+                Event::FootnoteReference(_) => NodeKind::FootnoteReference,
+                Event::SoftBreak | Event::HardBreak => NodeKind::Break,
+                Event::Rule => NodeKind::Rule,
+                Event::TaskListMarker(_) => NodeKind::TaskListMarker,
+            };
+            let parent = stack.last_mut().expect("unbalanced events");
+            parent.children.push(Node::new(kind, text_range(range)));
+        }
+        stack.pop().expect("unbalanced events")
+    }
+
+    fn new(kind: NodeKind, range: TextRange) -> Self {
+        Self {
+            kind,
+            range,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the nodes containing an offset, from this node inwards.
+    ///
+    /// At the boundary between two nodes the one starting at the offset wins.
+    pub(crate) fn path_to(&self, offset: TextSize) -> Vec<&Node> {
+        let mut path = vec![self];
+        let mut node = self;
+        while let Some(child) = node
+            .children
+            .iter()
+            .find(|child| child.range.contains(offset))
+            .or_else(|| {
+                node.children
+                    .iter()
+                    .find(|child| child.range.contains_inclusive(offset))
+            })
+        {
+            path.push(child);
+            node = child;
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_to() {
+        let text = "# Title\n\n- a *b `c`*\n- d\n";
+        let root = Node::parse(text);
+        let kinds = root
+            .path_to(TextSize::from(17))
+            .into_iter()
+            .map(|node| node.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                NodeKind::Document,
+                NodeKind::List,
+                NodeKind::Item,
+                NodeKind::Emphasis,
+                NodeKind::Code,
+            ]
+        );
+        assert_eq!(root.children[1].children.len(), 2);
+    }
+}
@@ -0,0 +1,386 @@
+//! Task list checkboxes.
+
+use std::{
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::Deserialize;
+use text_edit::{TextEdit, TextRange, TextSize};
+
+use crate::analysis::{parser_options, text_range};
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct TaskConfig {
+    /// Whether toggling a list item without a checkbox adds one.
+    pub(crate) create_checkbox: bool,
+    /// Whether checking an item appends the date, which unchecking removes.
+    ///
+    /// The server only knows the date in UTC, which may not be the local one.
+    /// Clients that toggle tasks with the `mdls.toggleTask` command can pass
+    /// their local date instead.
+    pub(crate) completion_date: bool,
+    /// The format of the completion date, where `%Y`, `%m` and `%d` are the
+    /// year, month and day.
+    pub(crate) date_format: String,
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            create_checkbox: false,
+            completion_date: false,
+            date_format: "✅ %Y-%m-%d".to_owned(),
+        }
+    }
+}
+
+/// A calendar date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Date {
+    pub(crate) year: i64,
+    pub(crate) month: u32,
+    pub(crate) day: u32,
+}
+
+impl Date {
+    /// Returns the current date in UTC.
+    pub(crate) fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self::from_days(i64::try_from(seconds / 86_400).unwrap_or_default())
+    }
+
+    /// Parses a `YYYY-MM-DD` date.
+    pub(crate) fn parse(date: &str) -> Option<Self> {
+        let mut parts = date.splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts
+            .next()?
+            .parse()
+            .ok()
+            .filter(|month| (1..=12).contains(month))?;
+        let day = parts
+            .next()?
+            .parse()
+            .ok()
+            .filter(|day| (1..=31).contains(day))?;
+        Some(Self { year, month, day })
+    }
+
+    /// Converts days since 1970-01-01 to a date in the proleptic Gregorian
+    /// calendar.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_days(days: i64) -> Self {
+        // Shift the epoch to 0000-03-01, so that leap days end the year.
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+        let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self { year, month, day }
+    }
+
+    fn format(self, format: &str) -> String {
+        let mut formatted = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                formatted.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => write!(formatted, "{:04}", self.year).unwrap(),
+                Some('m') => write!(formatted, "{:02}", self.month).unwrap(),
+                Some('d') => write!(formatted, "{:02}", self.day).unwrap(),
+                Some(other) => formatted.push(other),
+                None => formatted.push('%'),
+            }
+        }
+        formatted
+    }
+}
+
+/// Returns the length of a date at the start of the text, if it matches the
+/// format.
+#[allow(clippy::string_slice)]
+fn date_len(text: &str, format: &str) -> Option<usize> {
+    let mut rest = text;
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        let (expected, digits) = if c == '%' {
+            match chars.next() {
+                Some('Y') => (None, 4),
+                Some('m' | 'd') => (None, 2),
+                Some(other) => (Some(other), 0),
+                None => (Some('%'), 0),
+            }
+        } else {
+            (Some(c), 0)
+        };
+        if let Some(expected) = expected {
+            rest = rest.strip_prefix(expected)?;
+        } else {
+            let len = rest.bytes().take_while(u8::is_ascii_digit).count();
+            if len != digits {
+                return None;
+            }
+            rest = &rest[len..];
+        }
+    }
+    Some(text.len() - rest.len())
+}
+
+/// A list item, and its checkbox if it has one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Item {
+    range: TextRange,
+    /// Where the content after the list marker starts.
+    content_start: TextSize,
+    /// The end of the first line, without trailing whitespace.
+    line_end: TextSize,
+    checked: Option<bool>,
+}
+
+#[allow(clippy::string_slice)]
+fn items(text: &str) -> Vec<Item> {
+    let mut items = Vec::new();
+    for (event, range) in Parser::new_ext(text, parser_options()).into_offset_iter() {
+        let Event::Start(Tag::Item) = event else {
+            continue;
+        };
+        let line = text[range.start..].split('\n').next().unwrap_or_default();
+        let line = line.trim_end();
+        let marker_len = if line.starts_with(['-', '*', '+']) {
+            1
+        } else {
+            let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+            digits + 1
+        };
+        let after_marker = line.get(marker_len..).unwrap_or_default();
+        let spacing = after_marker.len() - after_marker.trim_start_matches([' ', '\t']).len();
+        let content_start = range.start + (marker_len + spacing).min(line.len());
+        let content = &line[content_start - range.start..];
+        let checked = match content.get(..3) {
+            Some("[ ]") => Some(false),
+            Some("[x]" | "[X]") => Some(true),
+            _ => None,
+        }
+        .filter(|_| content[3..].is_empty() || content[3..].starts_with([' ', '\t']));
+
+        items.push(Item {
+            range: text_range(range.clone()),
+            content_start: TextSize::try_from(content_start).expect("offset fits"),
+            line_end: TextSize::try_from(range.start + line.len()).expect("offset fits"),
+            checked,
+        });
+    }
+    items
+}
+
+/// Returns whether a range of the document has list items to toggle.
+pub(crate) fn has_items(text: &str, range: TextRange, config: &TaskConfig) -> bool {
+    selected(&items(text), text, range)
+        .iter()
+        .any(|item| item.checked.is_some() || config.create_checkbox)
+}
+
+/// Selects the innermost item at the cursor, or the items starting on the
+/// selected lines.
+#[allow(clippy::string_slice)]
+fn selected(items: &[Item], text: &str, range: TextRange) -> Vec<Item> {
+    if range.is_empty() {
+        return items
+            .iter()
+            .filter(|item| item.range.contains_inclusive(range.start()))
+            .min_by_key(|item| item.range.len())
+            .into_iter()
+            .copied()
+            .collect();
+    }
+    let line_start = text[..usize::from(range.start())]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let lines = TextRange::new(
+        TextSize::try_from(line_start).expect("offset fits"),
+        range.end(),
+    );
+    items
+        .iter()
+        .filter(|item| lines.contains(item.range.start()))
+        .copied()
+        .collect()
+}
+
+/// Checks unchecked items and unchecks checked ones in a range of the
+/// document, and adds checkboxes to the other items if configured to.
+#[allow(clippy::string_slice)]
+pub(crate) fn toggle(text: &str, range: TextRange, config: &TaskConfig, today: Date) -> TextEdit {
+    let date = format!(" {}", today.format(&config.date_format));
+
+    let mut edit = TextEdit::builder();
+    for item in selected(&items(text), text, range) {
+        let checkbox = TextRange::at(item.content_start + TextSize::from(1), 1.into());
+        match item.checked {
+            Some(false) => {
+                edit.replace(checkbox, "x".to_owned());
+                if config.completion_date {
+                    edit.insert(item.line_end, date.clone());
+                }
+            }
+            Some(true) => {
+                edit.replace(checkbox, " ".to_owned());
+                if config.completion_date {
+                    if let Some(date) = completion_date(text, item, &config.date_format) {
+                        edit.delete(date);
+                    }
+                }
+            }
+            None if config.create_checkbox => {
+                let checkbox = if item.content_start == item.line_end {
+                    " [ ]"
+                } else {
+                    "[ ] "
+                };
+                edit.insert(item.content_start, checkbox.to_owned());
+            }
+            None => {}
+        }
+    }
+    edit.finish()
+}
+
+/// Returns the range of the completion date at the end of the first line of
+/// an item, including the space before it.
+#[allow(clippy::string_slice)]
+fn completion_date(text: &str, item: Item, format: &str) -> Option<TextRange> {
+    let line = &text[usize::from(item.content_start)..usize::from(item.line_end)];
+    // The date is the last word or words, so try every space in the line.
+    line.match_indices(' ')
+        .map(|(index, _)| index)
+        .find(|&index| date_len(&line[index + 1..], format) == Some(line.len() - index - 1))
+        .map(|index| {
+            let start = item.content_start + TextSize::try_from(index).expect("offset fits");
+            TextRange::new(start, item.line_end)
+        })
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+
+    const TODAY: Date = Date {
+        year: 2022,
+        month: 8,
+        day: 5,
+    };
+
+    fn toggled(text: &str, range: TextRange, config: &TaskConfig) -> String {
+        let mut text = text.to_owned();
+        toggle(&text.clone(), range, config, TODAY).apply(&mut text);
+        text
+    }
+
+    #[test]
+    fn test_from_days() {
+        assert_eq!(
+            Date::from_days(0),
+            Date {
+                year: 1970,
+                month: 1,
+                day: 1
+            }
+        );
+        assert_eq!(Date::from_days(19_209), TODAY);
+        assert_eq!(Date::from_days(11_016).format("%d.%m.%Y"), "29.02.2000");
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = Date {
+            year: 2024,
+            month: 2,
+            day: 29,
+        };
+        assert_eq!(Date::parse("2024-02-29"), Some(date));
+        assert_eq!(Date::parse("2024-13-01"), None);
+        assert_eq!(Date::parse("2024-02"), None);
+        assert_eq!(Date::parse("today"), None);
+    }
+
+    #[test]
+    fn test_toggle() {
+        let text = "- [ ] a\n- [x] b\n- c\n  1. [X] d\n";
+        let config = TaskConfig::default();
+        assert_eq!(
+            toggled(text, TextRange::empty(3.into()), &config),
+            "- [x] a\n- [x] b\n- c\n  1. [X] d\n"
+        );
+        assert_eq!(
+            toggled(text, TextRange::empty(28.into()), &config),
+            "- [ ] a\n- [x] b\n- c\n  1. [ ] d\n"
+        );
+        assert_eq!(
+            toggled(text, TextRange::new(2.into(), 20.into()), &config),
+            "- [x] a\n- [ ] b\n- c\n  1. [X] d\n"
+        );
+    }
+
+    #[test]
+    fn test_create_checkbox() {
+        let config = TaskConfig {
+            create_checkbox: true,
+            ..TaskConfig::default()
+        };
+        let text = "- a\n-\n* [x] b\n";
+        assert_eq!(
+            toggled(text, TextRange::new(0.into(), 10.into()), &config),
+            "- [ ] a\n- [ ]\n* [ ] b\n"
+        );
+        assert!(has_items(text, TextRange::empty(1.into()), &config));
+        assert!(!has_items(
+            text,
+            TextRange::empty(1.into()),
+            &TaskConfig::default()
+        ));
+    }
+
+    #[test]
+    fn test_marker_spacing() {
+        let config = TaskConfig {
+            create_checkbox: true,
+            ..TaskConfig::default()
+        };
+        let text = "-   [ ] a\n-\t[x] b\n1.  c\n";
+        assert_eq!(
+            toggled(text, TextRange::new(0.into(), 20.into()), &config),
+            "-   [x] a\n-\t[ ] b\n1.  [ ] c\n"
+        );
+    }
+
+    #[test]
+    fn test_completion_date() {
+        let config = TaskConfig {
+            completion_date: true,
+            ..TaskConfig::default()
+        };
+        let text = "- [ ] a  \n  more\n";
+        let checked = toggled(text, TextRange::empty(0.into()), &config);
+        assert_eq!(checked, "- [x] a ✅ 2022-08-05  \n  more\n");
+        assert_eq!(
+            toggled(&checked, TextRange::empty(0.into()), &config),
+            "- [ ] a  \n  more\n"
+        );
+        assert_eq!(date_len("2022-08-05 a", "%Y-%m-%d"), Some(10));
+        assert_eq!(date_len("2022-8-05", "%Y-%m-%d"), None);
+    }
+}
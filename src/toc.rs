@@ -0,0 +1,269 @@
+//! Tables of contents between `<!-- toc -->` and `<!-- tocstop -->` markers.
+
+use std::{fmt::Write, ops::Range};
+
+use pulldown_cmark::{Event, Parser};
+use serde::Deserialize;
+use text_edit::{TextEdit, TextRange, TextSize};
+
+use crate::analysis::{parser_options, text_range, Analysis};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ListStyle {
+    /// `- item`
+    #[default]
+    Dash,
+    /// `* item`
+    Asterisk,
+    /// `+ item`
+    Plus,
+    /// `1. item`
+    Ordered,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct TocConfig {
+    /// The lowest heading level that is included.
+    pub(crate) min_level: usize,
+    /// The highest heading level that is included.
+    pub(crate) max_level: usize,
+    pub(crate) list_style: ListStyle,
+}
+
+impl Default for TocConfig {
+    fn default() -> Self {
+        Self {
+            min_level: 1,
+            max_level: 6,
+            list_style: ListStyle::default(),
+        }
+    }
+}
+
+impl TocConfig {
+    /// Returns whether headings of a level are listed.
+    pub(crate) fn includes(&self, level: usize) -> bool {
+        (self.min_level..=self.max_level).contains(&level)
+    }
+}
+
+/// The location of an existing table of contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Markers {
+    /// The `<!-- toc -->` marker.
+    pub(crate) start: TextRange,
+    /// Everything between the markers.
+    pub(crate) contents: TextRange,
+}
+
+/// Finds the first pair of TOC markers in the document.
+pub(crate) fn markers(text: &str) -> Option<Markers> {
+    let mut start = None;
+    for (event, range) in Parser::new_ext(text, parser_options()).into_offset_iter() {
+        let Event::Html(html) = event else { continue };
+        let Some(comment) = html
+            .trim()
+            .strip_prefix("<!--")
+            .and_then(|html| html.strip_suffix("-->"))
+        else {
+            continue;
+        };
+        match (comment.trim(), &start) {
+            ("toc", None) => start = Some(range),
+            ("tocstop", Some(start)) => {
+                return Some(Markers {
+                    start: trimmed(text, start.clone()),
+                    contents: text_range(start.end..range.start),
+                })
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns a range without the surrounding whitespace of its text.
+#[allow(clippy::string_slice)]
+fn trimmed(text: &str, range: Range<usize>) -> TextRange {
+    let slice = &text[range.clone()];
+    let start = range.start + slice.len() - slice.trim_start().len();
+    text_range(start..start + slice.trim().len())
+}
+
+/// Generates the list of links to the headings.
+pub(crate) fn generate(analysis: &Analysis, config: &TocConfig) -> String {
+    let mut toc = String::new();
+    // The levels of the enclosing entries, and the number of entries at each
+    // depth for ordered lists.
+    let mut levels: Vec<usize> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+
+    for heading in &analysis.headings {
+        if !config.includes(heading.level) {
+            continue;
+        }
+        while levels.last().is_some_and(|&level| level >= heading.level) {
+            levels.pop();
+        }
+        let depth = levels.len();
+        levels.push(heading.level);
+        counts.resize(depth + 1, 0);
+        counts[depth] += 1;
+
+        let (marker, width) = match config.list_style {
+            ListStyle::Dash => ("-".to_owned(), 2),
+            ListStyle::Asterisk => ("*".to_owned(), 2),
+            ListStyle::Plus => ("+".to_owned(), 2),
+            ListStyle::Ordered => (format!("{}.", counts[depth]), 3),
+        };
+        let text = heading.text.replace('[', "\\[").replace(']', "\\]");
+        let indent = " ".repeat(depth * width);
+        writeln!(toc, "{indent}{marker} [{text}](#{})", heading.anchor).unwrap();
+    }
+    toc
+}
+
+/// Returns an edit that brings an existing table of contents up to date, or
+/// `None` if it already is.
+#[allow(clippy::string_slice)]
+pub(crate) fn update(
+    text: &str,
+    analysis: &Analysis,
+    config: &TocConfig,
+    markers: Markers,
+) -> Option<TextEdit> {
+    let toc = generate(analysis, config);
+    if text[markers.contents].trim() == toc.trim() {
+        return None;
+    }
+    Some(TextEdit::replace(markers.contents, format!("\n{toc}\n")))
+}
+
+/// Returns an edit that inserts a table of contents at the start of the line
+/// containing `offset`, or `None` if there are no headings to list.
+pub(crate) fn insert(
+    text: &str,
+    analysis: &Analysis,
+    config: &TocConfig,
+    offset: TextSize,
+) -> Option<TextEdit> {
+    let toc = generate(analysis, config);
+    if toc.is_empty() {
+        return None;
+    }
+    let offset = usize::from(offset).min(text.len());
+    let line_start = text.as_bytes()[..offset]
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |i| i + 1);
+    Some(TextEdit::insert(
+        TextSize::from(line_start as u32),
+        format!("<!-- toc -->\n\n{toc}\n<!-- tocstop -->\n\n"),
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::slug::SlugStyle;
+
+    const TEXT: &str = "\
+# Title
+
+<!-- toc -->
+- [Old](#old)
+<!-- tocstop -->
+
+## Install `[beta]`
+
+### Linux
+
+## Usage
+
+#### Deep
+";
+
+    #[test]
+    fn test_markers() {
+        let markers = markers(TEXT).unwrap();
+        assert_eq!(&TEXT[markers.start], "<!-- toc -->");
+        assert_eq!(&TEXT[markers.contents], "- [Old](#old)\n");
+        assert_eq!(
+            super::markers("```\n<!-- toc -->\n<!-- tocstop -->\n```\n"),
+            None
+        );
+        let text = "  <!--toc-->\n- [A](#a)\n<!--  tocstop  -->\n";
+        let markers = super::markers(text).unwrap();
+        assert_eq!(&text[markers.start], "<!--toc-->");
+        assert_eq!(&text[markers.contents], "- [A](#a)\n");
+    }
+
+    #[test]
+    fn test_includes() {
+        let config = TocConfig {
+            min_level: 2,
+            max_level: 3,
+            ..TocConfig::default()
+        };
+        let levels = (1..=6)
+            .filter(|&level| config.includes(level))
+            .collect::<Vec<_>>();
+        assert_eq!(levels, [2, 3]);
+    }
+
+    #[test]
+    fn test_generate() {
+        let analysis = Analysis::new(TEXT, SlugStyle::default());
+        let config = TocConfig {
+            min_level: 2,
+            ..TocConfig::default()
+        };
+        assert_eq!(
+            generate(&analysis, &config),
+            "- [Install \\[beta\\]](#install-beta)\n  - [Linux](#linux)\n- [Usage](#usage)\n  - [Deep](#deep)\n"
+        );
+
+        let config = TocConfig {
+            min_level: 1,
+            max_level: 2,
+            list_style: ListStyle::Ordered,
+        };
+        assert_eq!(
+            generate(&analysis, &config),
+            "1. [Title](#title)\n   1. [Install \\[beta\\]](#install-beta)\n   2. [Usage](#usage)\n"
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let analysis = Analysis::new(TEXT, SlugStyle::default());
+        let config = TocConfig::default();
+        let markers = markers(TEXT).unwrap();
+
+        let mut text = TEXT.to_owned();
+        update(&text, &analysis, &config, markers)
+            .unwrap()
+            .apply(&mut text);
+        let analysis = Analysis::new(&text, SlugStyle::default());
+        let markers = super::markers(&text).unwrap();
+        assert!(update(&text, &analysis, &config, markers).is_none());
+    }
+
+    #[test]
+    fn test_insert() {
+        let text = "# A\n\nü\n";
+        let analysis = Analysis::new(text, SlugStyle::default());
+        let config = TocConfig::default();
+        let start = |offset: u32| {
+            let edit = insert(text, &analysis, &config, offset.into()).unwrap();
+            edit.iter().next().unwrap().delete.start()
+        };
+        assert_eq!(start(2), TextSize::from(0));
+        // Inside a character, and past the end of the text.
+        assert_eq!(start(6), TextSize::from(5));
+        assert_eq!(start(100), TextSize::from(8));
+    }
+}
@@ -0,0 +1,183 @@
+//! An index of the links between the Markdown documents of the workspace.
+//!
+//! The index is built the first time it is needed, by reading every Markdown
+//! file that isn't ignored, and kept up to date as documents are edited.
+
+use std::path::{Path, PathBuf};
+
+use hashbrown::HashMap;
+use lsp_types::Url;
+use text_edit::{TextRange, TextSize};
+
+use crate::{
+    analysis::{local_destination, Analysis},
+    hover::is_markdown,
+    ignore::Gitignore,
+    index::LineIndex,
+    slug::SlugStyle,
+};
+
+/// A link to a document in the workspace.
+#[derive(Clone, Debug)]
+pub(crate) struct IndexedLink {
+    pub(crate) range: TextRange,
+    /// The linked document, without a fragment.
+    pub(crate) target: Url,
+    pub(crate) anchor: Option<String>,
+    /// The range of the destination as written, which is in the definition
+    /// for reference-style links.
+    pub(crate) destination_range: Option<TextRange>,
+    /// The range of the anchor in the destination, which is in the
+    /// definition for reference-style links.
+    pub(crate) anchor_range: Option<TextRange>,
+}
+
+#[derive(Clone, Debug)]
+struct Document {
+    line_index: LineIndex,
+    links: Vec<IndexedLink>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LinkIndex {
+    documents: HashMap<Url, Document>,
+}
+
+impl LinkIndex {
+    /// Indexes or reindexes a document.
+    pub(crate) fn update(
+        &mut self,
+        uri: Url,
+        text: &str,
+        line_index: LineIndex,
+        root: Option<&Path>,
+    ) {
+        let links = links(&uri, text, root);
+        self.documents.insert(uri, Document { line_index, links });
+    }
+
+    /// Returns the links to a document, or to a heading in it.
+    pub(crate) fn references<'a>(
+        &'a self,
+        target: &'a Url,
+        anchor: Option<&'a str>,
+    ) -> impl Iterator<Item = (&'a Url, &'a LineIndex, &'a IndexedLink)> + 'a {
+        self.documents.iter().flat_map(move |(uri, document)| {
+            document
+                .links
+                .iter()
+                .filter(move |link| &link.target == target && link.anchor.as_deref() == anchor)
+                .map(move |link| (uri, &document.line_index, link))
+        })
+    }
+}
+
+/// Finds the links of a document that point into the workspace.
+fn links(uri: &Url, text: &str, root: Option<&Path>) -> Vec<IndexedLink> {
+    // Only the destinations are used, which don't depend on the slug style.
+    let analysis = Analysis::new(text, SlugStyle::default());
+    analysis
+        .links
+        .iter()
+        .filter(|link| link.resolved)
+        .filter_map(|link| {
+            let (path, anchor) = local_destination(&link.destination)?;
+            let mut target = resolve(uri, path, root)?;
+            target.set_fragment(None);
+
+            let destination_range = match &link.label {
+                Some(label) => analysis.definition(&label.normalized)?.destination_range,
+                None => link.destination_range,
+            };
+            let anchor_range = anchor.zip(destination_range).map(|(anchor, range)| {
+                TextRange::new(range.end() - TextSize::of(anchor), range.end())
+            });
+            Some(IndexedLink {
+                range: link.range,
+                target,
+                anchor: anchor.map(ToOwned::to_owned),
+                destination_range,
+                anchor_range,
+            })
+        })
+        .collect()
+}
+
+/// Resolves a link path relative to a document, or to the workspace root if
+/// it starts with `/`.
+pub(crate) fn resolve(uri: &Url, path: &str, root: Option<&Path>) -> Option<Url> {
+    match path.strip_prefix('/') {
+        Some(path) => Url::from_directory_path(root?).ok()?.join(path).ok(),
+        None => uri.join(path).ok(),
+    }
+}
+
+/// Lists the Markdown files in a directory and its subdirectories, skipping
+/// hidden and ignored entries.
+pub(crate) fn markdown_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let gitignore = Gitignore::for_directory(root, &dir);
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if is_hidden || gitignore.is_ignored(&path, is_dir) {
+                continue;
+            }
+            if is_dir {
+                dirs.push(path);
+            } else if is_markdown(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+#[allow(clippy::string_slice)]
+mod tests {
+    use super::*;
+    use crate::index::PositionEncoding;
+
+    #[test]
+    fn test_references() {
+        let root = Path::new("/repo");
+        let readme = Url::parse("file:///repo/README.md").unwrap();
+        let guide = Url::parse("file:///repo/docs/guide.md").unwrap();
+
+        let mut index = LinkIndex::default();
+        let readme_text = "[a](docs/guide.md#setup) [b](/docs/guide.md) [c](#intro) [d]\n\n[d]: docs/guide.md#setup\n";
+        index.update(
+            readme.clone(),
+            readme_text,
+            LineIndex::new(readme_text, PositionEncoding::Utf8),
+            Some(root),
+        );
+        let text = "[e](guide.md#setup) [f](https://example.com/guide.md#setup)\n";
+        index.update(
+            guide.clone(),
+            text,
+            LineIndex::new(text, PositionEncoding::Utf8),
+            Some(root),
+        );
+
+        let count = |target, anchor| index.references(target, anchor).count();
+        assert_eq!(count(&guide, Some("setup")), 3);
+        assert_eq!(count(&guide, None), 1);
+        assert_eq!(count(&readme, Some("intro")), 1);
+
+        let anchors = index
+            .references(&guide, Some("setup"))
+            .filter(|(uri, ..)| **uri == readme)
+            .map(|(_, _, link)| link.anchor_range.map(|range| &readme_text[range]))
+            .collect::<Vec<_>>();
+        assert_eq!(anchors, [Some("setup"), Some("setup")]);
+    }
+}